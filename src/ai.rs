@@ -1,16 +1,14 @@
-use crate::config::Config;
-use crate::score::{calculate_score, roll_dice};
+use std::sync::Arc;
+
+use crate::config::{Config, EndMode};
+use crate::engine::{Action, Event, GameState, Player};
+use crate::llm::{ChatRequest, LlmProvider, Role, provider_from_config};
+use crate::strategy::{self, Strategy};
 
 use colored::*;
-use reqwest::blocking::Client;
-use reqwest::blocking::ClientBuilder;
-use serde_json::json;
-use std::env;
-use std::time::Duration;
+use serde_json::{Map, Value};
 use textwrap::wrap;
 
-const NUM_DICE: usize = 6;
-
 #[derive(Debug)]
 struct AIDecisionLog {
     remaining_dice: u32,
@@ -19,115 +17,196 @@ struct AIDecisionLog {
     explanation: String,
 }
 
-pub fn ai_turn(
-    ai_score: u32,
-    other_scores: &[u32],
-    ai_type: &Option<String>,
-    config: &Config,
-) -> u32 {
-    let mut dice = NUM_DICE;
-    let mut turn_score = 0;
-    let mut roll_count = 1;
-    let mut history = Vec::new();
-
-    loop {
-        println!("{} {}", "\n\tRoll number:".bold().green(), roll_count);
-
-        let roll = roll_dice(dice);
-        println!("{} {:?}", "\tAI rolled:".bold().green(), roll);
-
-        let (score, remaining_dice, remaining_dice_values, _rerollable_dice) = calculate_score(&roll);
-        println!("{} {}", "\tScore:".bold().cyan(), score);
-        println!("{} {} - {:?}", "\tRemaining dice:".bold().blue(), remaining_dice, remaining_dice_values);
-
-        if score == 0 {
-            println!("{}", "\tAI scored nothing.".bold().red());
-            display_ai_failure_reaction(turn_score, &history, config, ai_type);
-            return 0;
-        }
+const DECISION_SYSTEM_PROMPT: &str = "You are an expert and strategic AI agent playing the 6000 dice game. \
+    Your role is to analyze each turn in detail, learn from past outcomes, and make optimal decisions by \
+    reasoning through uncertainty and risk. Answer in JSON format with keys: 'decision' and 'explanation'.";
+
+/// Values of `ai_personality`.
+pub const AI_PERSONALITIES: [&str; 4] = ["default", "paranoid", "academic", "vicious"];
+
+/// A decision read from the model's answer.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LlmDecision {
+    /// Number of the chosen keep option, from 1.
+    pub keep: usize,
+    /// True to take the points, false to roll again.
+    pub bank: bool,
+    pub explanation: String,
+}
 
-        turn_score += score;
-        roll_count += 1;
+impl LlmDecision {
+    /// "T" or "R", as the model is asked to answer.
+    pub fn letter(&self) -> &'static str {
+        if self.bank { "T" } else { "R" }
+    }
+}
 
-        if remaining_dice == 0 {
-            println!("{}", "\tAI gets another full roll!".bold().magenta());
-            continue;
-        }
+/// A player whose decisions come from an LLM.
+pub struct LlmPlayer {
+    provider: Box<dyn LlmProvider>,
+    config: Arc<Config>,
+    history: Vec<AIDecisionLog>,
+    decision: String,
+    explanation: Option<String>,
+    /// The `llm_fallback` strategy, created the first time the model fails.
+    fallback: Option<Box<dyn Strategy>>,
+}
 
-        let prompt = build_prompt(
-            ai_score,
-            other_scores,
-            turn_score,
-            remaining_dice,
-            score,
-            &history,
+impl LlmPlayer {
+    pub fn new(provider: Box<dyn LlmProvider>, config: Arc<Config>) -> Self {
+        LlmPlayer {
+            provider,
             config,
-        );
-        let (decision, explanation) = match ai_type {
-            Some(ai_type_str) => match ai_type_str.as_str() {
-                "openai" => ai_decision_with_chatgpt(&prompt, config),
-                "anthropic" => ai_decision_with_claude(&prompt, config),
-                "ollama" => ai_decision_with_ollama(&prompt, config),
-                _ => ai_decision_with_claude(&prompt, config), // Fallback to Claude
-            },
-            None => ai_decision_with_claude(&prompt, config), // Default fallback
-        };
+            history: Vec::new(),
+            decision: String::new(),
+            explanation: None,
+            fallback: None,
+        }
+    }
 
-        history.push(AIDecisionLog {
-            remaining_dice,
-            turn_score,
-            decision: decision.clone(),
-            explanation: explanation.clone(),
-        });
+    /// The LLM player seated as `strategy` (see [`Config::provider`]).
+    pub fn for_strategy(strategy: &str, config: &Config) -> Option<Self> {
+        let provider = config.provider(strategy)?;
+        Some(Self::new(provider_from_config(&provider), Arc::new(config.clone())))
+    }
 
-        let wrapped_explanation = wrap(&explanation, 80);
-        let max_lines = 50;
+    /// Ask the model, and ask again with the problem spelled out while its
+    /// answer is not a valid decision, up to `llm_retries` times.
+    fn ask(&self, prompt: String, options: usize) -> Result<LlmDecision, String> {
+        let mut request = ChatRequest::new(DECISION_SYSTEM_PROMPT, prompt)
+            .max_tokens(1024)
+            .temperature(0.7);
+        let mut retries = 0;
+        loop {
+            let answer = self
+                .provider
+                .complete(&request)
+                .map_err(|e| format!("{} could not be reached ({})", self.provider.name(), e))?;
+            match parse_decision(&answer, options) {
+                Ok(decision) => return Ok(decision),
+                Err(e) if retries < self.config.game.llm_retries => {
+                    retries += 1;
+                    request.push(Role::Assistant, answer);
+                    request.push(Role::User, correction_prompt(&e, options));
+                }
+                Err(e) => return Err(format!("{} gave no valid decision ({})", self.provider.name(), e)),
+            }
+        }
+    }
 
+    /// Decision of the `llm_fallback` strategy, announced on screen; without
+    /// one, keep the first option and take the points.
+    fn fallback_decision(&mut self, state: &GameState, reason: String) -> LlmDecision {
+        let name = &self.config.game.llm_fallback;
+        if self.fallback.is_none() {
+            self.fallback = strategy::create(name, &self.config);
+        }
+        let Some(fallback) = self.fallback.as_mut() else {
+            println!("{}", format!("\t⚠ {}: taking the points.", reason).bold().yellow());
+            return LlmDecision {
+                keep: 1,
+                bank: true,
+                explanation: format!("{}, taking the points.", reason),
+            };
+        };
         println!(
-            "{} {}",
-            "\tAI decision:".bold().blue(),
-            decision.bold().white()
+            "{}",
+            format!("\t⚠ {}: the {} strategy plays this roll.", reason, name).bold().yellow()
         );
-        println!("{}", "\tReason:".bold().green());
-        for line in wrapped_explanation.iter().take(max_lines) {
-            println!("\t  {}", line);
-        }
-        if wrapped_explanation.len() > max_lines {
-            println!("\t  [...]");
+        let indices = Strategy::choose_keep(fallback, state);
+        let keep = state
+            .keep_options
+            .iter()
+            .position(|option| option.indices == indices)
+            .map_or(1, |i| i + 1);
+        // The bank decision is made on the state after the keep
+        let mut after = state.clone();
+        let bank = after.apply(Action::Keep(indices), &mut |_: usize| Vec::new()).is_ok()
+            && fallback.should_bank(&after);
+        LlmDecision {
+            keep,
+            bank,
+            explanation: format!("{}, the {} strategy plays instead.", reason, name),
         }
+    }
+}
 
-        if decision.trim().eq_ignore_ascii_case("T") {
-            println!("{}", "\tAI banks its points.\n".bold().green());
-            break;
-        } else {
-            dice = remaining_dice as usize;
+impl Strategy for LlmPlayer {
+    fn choose_keep(&mut self, state: &GameState) -> Vec<usize> {
+        let options = &state.keep_options;
+        let roll = &state.dice;
+
+        // A single keep that uses every die needs no decision: roll again
+        if options.len() == 1 && options[0].dice.len() == roll.len() && !options[0].ends_turn {
+            return options[0].indices.clone();
         }
+
+        let prompt = build_prompt(state, &self.history, &self.config);
+        let decision = match self.ask(prompt, options.len()) {
+            Ok(decision) => decision,
+            Err(reason) => self.fallback_decision(state, reason),
+        };
+        let keep = &options[decision.keep - 1];
+
+        self.history.push(AIDecisionLog {
+            remaining_dice: (roll.len() - keep.dice.len()) as u32,
+            turn_score: state.current_player().turn_score + keep.score,
+            decision: decision.letter().to_string(),
+            explanation: decision.explanation.clone(),
+        });
+
+        self.decision = decision.letter().to_string();
+        self.explanation = Some(decision.explanation);
+        keep.indices.clone()
+    }
+
+    fn explain(&mut self) -> Option<(String, String)> {
+        let explanation = self.explanation.take()?;
+        Some((self.decision.clone(), explanation))
     }
 
-    turn_score
+    fn should_bank(&mut self, _state: &GameState) -> bool {
+        self.decision == "T"
+    }
+
+    fn observe(&mut self, state: &GameState, event: &Event) {
+        if let Some(fallback) = self.fallback.as_mut() {
+            Strategy::observe(fallback, state, event);
+        }
+        match event {
+            Event::Busted { lost, .. } => {
+                display_ai_failure_reaction(*lost, &self.history, &self.config, self.provider.as_ref());
+                self.history.clear();
+            }
+            // The history covers the current turn only
+            Event::Banked { .. } => self.history.clear(),
+            _ => {}
+        }
+    }
 }
 
 fn display_ai_failure_reaction(
     lost_points: u32,
     history: &[AIDecisionLog],
     config: &Config,
-    ai_type: &Option<String>,
+    provider: &dyn LlmProvider,
 ) {
     println!("{}", "\tAI feedback: ".bright_red());
 
     // get feedback from AI about the failure
     let reaction_prompt =
         build_failure_reaction_prompt(lost_points, history, config.game.ai_personality.as_str());
-
-    let reaction = match ai_type {
-        Some(ai_type_str) => match ai_type_str.as_str() {
-            "openai" => get_ai_reaction_chatgpt(&reaction_prompt, config),
-            "anthropic" => get_ai_reaction_claude(&reaction_prompt, config),
-            "ollama" => get_ai_reaction_ollama(&reaction_prompt, config),
-            _ => get_ai_reaction_claude(&reaction_prompt, config),
-        },
-        None => get_ai_reaction_claude(&reaction_prompt, config),
-    };
+    let system = format!(
+        "You are an AI that just lost all your turn points in a dice game. React emotionally and briefly{}.",
+        language_suffix(&config.game.ai_output_language)
+    );
+    let request = ChatRequest::new(system, reaction_prompt)
+        .max_tokens(100)
+        .temperature(0.9);
+    let reaction = provider
+        .complete(&request)
+        .map(|text| text.trim().to_string())
+        .unwrap_or_else(|_| "💀 CURSE THESE DICE! My beautiful points... *sobs* 💀".to_string());
 
     let max_lines = 50;
     let wrapped_reaction = wrap(&reaction, 70);
@@ -136,6 +215,15 @@ fn display_ai_failure_reaction(
     }
 }
 
+/// " in french" or " in english" for the configured output language.
+fn language_suffix(language: &str) -> &'static str {
+    match language {
+        "fr" => " in french",
+        "en" => " in english",
+        _ => "",
+    }
+}
+
 fn build_failure_reaction_prompt(
     lost_points: u32,
     history: &[AIDecisionLog],
@@ -175,123 +263,6 @@ fn build_failure_reaction_prompt(
     )
 }
 
-fn get_ai_reaction_chatgpt(prompt: &str, config: &Config) -> String {
-    let api_key = match env::var("OPENAI_API_KEY") {
-        Ok(key) => key,
-        Err(_) => return "💀 NOOOOO! My precious points! 💀".to_string(),
-    };
-
-    let client = Client::new();
-    let request_body = json!({
-        "model": config.openai.model,
-        "messages": [
-            { "role": "system", "content": "You are an AI that just lost all your turn points in a dice game. React emotionally and briefly." },
-            { "role": "user", "content": prompt }
-        ],
-        "max_tokens": 100,
-        "temperature": 0.9
-    });
-
-    match client
-        .post(&config.openai.url)
-        .bearer_auth(api_key)
-        .json(&request_body)
-        .send()
-    {
-        Ok(resp) => match resp.json::<serde_json::Value>() {
-            Ok(json_resp) => json_resp["choices"][0]["message"]["content"]
-                .as_str()
-                .unwrap_or("💀 Disaster! All my points... gone! 💀")
-                .to_string(),
-            Err(_) => "💀 Complete failure! The dice have betrayed me! 💀".to_string(),
-        },
-        Err(_) => "💀 CURSE THESE DICE! My beautiful points... *sobs* 💀".to_string(),
-    }
-}
-
-fn get_ai_reaction_claude(prompt: &str, config: &Config) -> String {
-    let api_key = match env::var("ANTHROPIC_API_KEY") {
-        Ok(key) => key,
-        Err(_) => {
-            return "💀 The statistical improbability of this outcome is crushing my circuits! 💀"
-                .to_string();
-        }
-    };
-
-    let client = Client::new();
-    let request_body = json!({
-        "model": config.anthropic.model,
-        "max_tokens": 100,
-        "temperature": 0.9,
-        "system": "You are an AI that just lost all your turn points in a dice game. React emotionally and briefly.",
-        "messages": [
-            {
-                "role": "user",
-                "content": prompt
-            }
-        ]
-    });
-
-    match client
-        .post(&config.anthropic.url)
-        .header("x-api-key", api_key)
-        .header("anthropic-version", "2023-06-01")
-        .json(&request_body)
-        .send()
-    {
-        Ok(resp) => match resp.json::<serde_json::Value>() {
-            Ok(json_resp) => json_resp["content"][0]["text"]
-                .as_str()
-                .unwrap_or("💀 My algorithms failed me! This is catastrophic! 💀")
-                .to_string(),
-            Err(_) => "💀 Error in emotional processing... but the pain is real! 💀".to_string(),
-        },
-        Err(_) => "💀 Connection failed, just like my dice rolling strategy! 💀".to_string(),
-    }
-}
-
-fn get_ai_reaction_ollama(prompt: &str, config: &Config) -> String {
-    let client = ClientBuilder::new()
-        .timeout(Duration::from_secs(60))
-        .build()
-        .expect("Failed to build HTTP client with timeout");
-
-    let language = config.game.ai_output_language.as_str();
-
-    let language_instruction = match language {
-        "fr" => "french",
-        "en" => "english",
-        _ => "",
-    };
-
-    let request_body = json!({
-        "model": config.ollama.model,
-        "prompt": format!(
-            "You are an AI that just lost all your turn points in a dice game. React emotionally and briefly in {}.\n\n{}",
-            language_instruction, prompt
-        ),
-        "temperature": 0.9,
-        "max_tokens": 100,
-        "stream": false
-    });
-
-    match client
-        .post(config.ollama.url.clone())
-        .json(&request_body)
-        .send()
-    {
-        Ok(resp) => match resp.json::<serde_json::Value>() {
-            Ok(json_resp) => json_resp["response"]
-                .as_str()
-                .unwrap_or("💀 My circuits are sobbing in silence! 💀")
-                .trim()
-                .to_string(),
-            Err(_) => "💀 Emotional overload! I can't even parse my own pain. 💀".to_string(),
-        },
-        Err(_) => "💀 I failed to connect, just like I failed that turn! 💀".to_string(),
-    }
-}
-
 fn format_history(history: &[AIDecisionLog]) -> String {
     if history.is_empty() {
         return String::from("No prior AI decisions.");
@@ -347,15 +318,7 @@ fn get_personality_prompt(mode: &str) -> &str {
     }
 }
 
-fn build_prompt(
-    ai_score: u32,
-    other_scores: &[u32],
-    turn_score: u32,
-    remaining_dice: u32,
-    score: u32,
-    history: &[AIDecisionLog],
-    config: &Config,
-) -> String {
+fn build_prompt(state: &GameState, history: &[AIDecisionLog], config: &Config) -> String {
     let history_str = format_history(history);
     let ai_personality = config.game.ai_personality.as_str();
     let language = config.game.ai_output_language.as_str();
@@ -366,27 +329,110 @@ fn build_prompt(
         _ => "",
     };
 
-    let other_scores_str = other_scores
+    let player = state.current_player();
+    let ai_score = player.score;
+    let turn_score = player.turn_score;
+    let bust_streak = player.bust_streak;
+    let limits = state.bank_limits();
+    let roll = &state.dice;
+    let options = &state.keep_options;
+
+    // Players are numbered by seat, as in the players list below
+    let other_scores_str = state
+        .players
         .iter()
         .enumerate()
-        .map(|(i, score)| format!("Player {}: {}", i + 1, score))
+        .filter(|&(i, _)| i != state.current_player_index)
+        .map(|(i, p)| format!("{}) {}: {}", i + 1, p.name, p.score))
         .collect::<Vec<_>>()
         .join(", ");
 
+    let players_str = state
+        .players
+        .iter()
+        .enumerate()
+        .map(|(i, p)| {
+            let you = if i == state.current_player_index { " (you)" } else { "" };
+            format!("  {}) {}{}: {}, {} points", i + 1, p.name, you, player_kind(p), p.score)
+        })
+        .collect::<Vec<_>>()
+        .join("\n");
+
     let personality = get_personality_prompt(ai_personality);
 
+    let keep_options_str = options
+        .iter()
+        .enumerate()
+        .map(|(i, option)| {
+            format!(
+                "  {}) keep {:?} for {} points, {} dice left to roll",
+                i + 1,
+                option.dice,
+                option.score,
+                roll.len() - option.dice.len()
+            )
+        })
+        .collect::<Vec<_>>()
+        .join("\n");
+    let dice_rolled = roll.len();
+    let opening_rule = match state.opening_score {
+        Some(points) if points > 0 => format!(
+            "- A player's first bank must be at least {} points in a single turn (getting on the board).\n",
+            points
+        ),
+        _ => String::new(),
+    };
+    let mut turn_limits = String::new();
+    if limits.min > 0 {
+        turn_limits.push_str(&format!(
+            "- You are not on the board yet: you cannot take points below {} this turn.\n",
+            limits.min
+        ));
+    }
+    if let Some(max) = limits.max {
+        turn_limits.push_str(&format!(
+            "- You need exactly {} more points this turn to win; a higher turn score busts the turn.\n",
+            max
+        ));
+    }
+    let bust_rule = match state.bust_penalty {
+        Some(penalty) if penalty.streak > 0 => format!(
+            "- Busting {} turns in a row costs {} points from your total score.\n",
+            penalty.streak, penalty.points
+        ),
+        _ => String::new(),
+    };
+    let target_score = state.target_score;
+    let end_rule = match state.end_mode {
+        EndMode::Reach => format!("- The first player to reach {} points wins.\n", target_score),
+        EndMode::Exact => format!(
+            "- A player must reach exactly {} points to win; overshooting loses the turn.\n",
+            target_score
+        ),
+        EndMode::FinalRound => format!(
+            "- When a player passes {} points, every other player gets one last turn; the highest score wins.\n",
+            target_score
+        ),
+    };
+    let rules_str = state
+        .rules
+        .describe()
+        .iter()
+        .map(|line| format!("- {}.", line))
+        .collect::<Vec<_>>()
+        .join("\n");
+
     format!(
         "You are reflecting on the decisions made during this turn. Use the outcomes of each step to adjust your next move.\n\
         You are the AI playing the 6000 dice game. Follow these rules exactly:\n\
-        - Straight (1-2-3-4-5-6): 2000 points, all dice used.\n\
-        - Three pairs: 1500 points, all dice used.\n\
-        - Six of a kind: face value × 1000.\n\
-        - Three 1s: 1000 points.\n\
-        - Three of 2–6: face value × 100.\n\
-        - Each 1: 100 points.\n\
-        - Each 5: 50 points.\n\
+        {rules_str}\n\
+        {end_rule}\
+        {opening_rule}\
+        {bust_rule}\
         - A roll scoring 0 loses all turn points.\n\
-        - Only reroll non-scoring dice.\n\
+        - After each roll you set aside at least one scoring combination and may reroll the rest.\n\
+        - You may keep fewer scoring dice than possible to reroll more dice.\n\
+        - If every die scores, you roll all six dice again.\n\
         \n\
         You have the basic rules and scoring system of the 6000 dice game.\n\
         Use the rules and scoring system as a foundation for reasoning.\n\
@@ -404,164 +450,185 @@ fn build_prompt(
         - AI score: {ai_score}\n\
         - Other players' scores: {other_scores_str}\n\
         - Turn score: {turn_score}\n\
-        - Dice remaining: {remaining_dice}\n\
-        - Roll score: {score}\n\
+        - Your consecutive busted turns: {bust_streak}\n\
+        {turn_limits}\
+        - Dice rolled: {dice_rolled}\n\
+        - Current roll: {roll:?}\n\
+        - Keep options:\n{keep_options_str}\n\
         \n\
-        Reminder: Player 1 is always a human.\n\
-        Reminder: Player 2 is always a computer (basic program).\n\
+        Players, in turn order:\n{players_str}\n\
         \n\
         {personality}\n\
         \n\
         Your explanation should be detailed but not more 3 lines and show your complete reasoning process.\n\
         Don't just say 'I'll take the points' - explain WHY, what are the probabilities, \
-        what are you afraid of, what's your strategy against the human player, etc.\n\"
+        what are you afraid of, what's your strategy against the other players, etc.\n\"
         \n\
         Don't mention combinations not present in the roll. Be rigorous.\n\
         \n\
         Respond with valid JSON only and you MUST provide an explanation in {language_instruction}. Do not include literal \\n characters or line breaks in strings. \n\
         Use spaces instead of line breaks in your explanation.\n\
         \n\
-        Respond ONLY with a valid JSON object with keys 'decision' and 'explanation'.\n\
+        Respond ONLY with a valid JSON object with keys 'keep', 'decision' and 'explanation'.\n\
+        The 'keep' JSON key is the number of the keep option you choose.\n\
         The 'decision' JSON key should be either 'R' (roll again) or 'T' (take points).\n\
         The 'explanation' JSON key must be in English and your explanation in {language_instruction}.\n\
         No commentary, no prefix, no prose.\n\
         \n\
         {{\n\
+          \"keep\": 1,  // number of the chosen keep option\n\
           \"decision\": \"R\",  // \"R\" = roll again, \"T\" = take points\n\
           \"explanation\": \"Provide a detailed strategic analysis of your decision, including probability calculations, risk assessment, and psychological considerations about your opponents\"\n\
         }}\n"
     )
 }
 
-fn ai_decision_with_chatgpt(prompt: &str, config: &Config) -> (String, String) {
-    let api_key = env::var("OPENAI_API_KEY").expect("OPENAI_API_KEY not set");
-    let client = Client::new();
-
-    let request_body = json!({
-        "model": config.openai.model,
-        "messages": [
-            { "role": "system", "content": "You are an expert and strategic AI agent playing the 6000 dice game. Your role is to analyze each turn in detail, learn from past outcomes, and make optimal decisions by reasoning through uncertainty and risk. Answer in JSON format with keys: 'decision' and 'explanation'." },
-            { "role": "user", "content": prompt }
-        ]
-    });
-
-    let resp = client
-        .post(&config.openai.url)
-        .bearer_auth(api_key)
-        .json(&request_body)
-        .send()
-        .expect("Request failed");
-
-    let json_resp: serde_json::Value = resp.json().expect("Invalid JSON");
-
-    let content = &json_resp["choices"][0]["message"]["content"];
-    println!("AI response content: {:?}", content);
-    let parsed: serde_json::Value = serde_json::from_str(content.as_str().unwrap_or("")).unwrap();
-
-    (
-        parsed["decision"].as_str().unwrap_or("T").to_string(),
-        parsed["explanation"].as_str().unwrap_or("").to_string(),
-    )
+/// Who plays a seat, for the prompt.
+fn player_kind(player: &Player) -> String {
+    if player.is_human {
+        return "a human".to_string();
+    }
+    match player.ai_type.as_deref() {
+        Some(ai_type) if ai_type.starts_with("llm:") || ["openai", "anthropic", "ollama"].contains(&ai_type) => {
+            "an AI language model".to_string()
+        }
+        Some(strategy) => format!("a computer program ({} strategy)", strategy),
+        None => "a computer program".to_string(),
+    }
 }
 
-fn ai_decision_with_claude(prompt: &str, config: &Config) -> (String, String) {
-    let api_key = env::var("ANTHROPIC_API_KEY").expect("ANTHROPIC_API_KEY not set");
-    let client = Client::new();
-
-    let request_body = json!({
-        "model": config.anthropic.model,
-        "max_tokens": 1024,
-        "temperature": 0.7,
-        "system": "You are an expert and strategic AI agent playing the 6000 dice game. Your role is to analyze each turn in detail, learn from past outcomes, and make optimal decisions by reasoning through uncertainty and risk. Answer in JSON format with keys: 'decision' and 'explanation'.",
-        "messages": [
-            {
-                "role": "user",
-                "content": prompt
-            }
-        ]
-    });
-
-    let resp = client
-        .post(&config.anthropic.url)
-        .header("x-api-key", api_key)
-        .header("anthropic-version", "2023-06-01")
-        .json(&request_body)
-        .send()
-        .expect("Request failed");
-
-    let json_resp: serde_json::Value = resp.json().expect("Invalid JSON");
-
-    let content = &json_resp["content"][0]["text"];
-    let parsed: serde_json::Value = serde_json::from_str(content.as_str().unwrap_or(""))
-        .unwrap_or_else(|_| {
-            eprintln!("Failed to parse AI response as JSON: {:?}", content);
-            json!({
-                "decision": "T",
-                "explanation": "Could not parse response"
-            })
-        });
-
-    (
-        parsed["decision"].as_str().unwrap_or("T").to_string(),
-        parsed["explanation"].as_str().unwrap_or("").to_string(),
+/// What to tell the model when its answer is not a valid decision.
+fn correction_prompt(problem: &str, options: usize) -> String {
+    format!(
+        "Your answer could not be used: {}.\n\
+        Respond ONLY with a JSON object with keys 'keep' (the number of a keep option, from 1 to {}), \
+        'decision' (\"R\" to roll again or \"T\" to take the points) and 'explanation'. \
+        No markdown, no commentary.",
+        problem, options
     )
 }
 
-fn ai_decision_with_ollama(prompt: &str, config: &Config) -> (String, String) {
-    let client = ClientBuilder::new()
-        .timeout(Duration::from_secs(config.ollama.timeout.unwrap_or(60))) // Default to 60 seconds if not set
-        .build()
-        .expect("Failed to build HTTP client with timeout");
-
-    let request_body = json!({
-        "model": config.ollama.model.to_string(),
-        "prompt": format!(
-            "{}",
-            prompt
-        ),
-        "temperature": 0.7,
-        "stream": false
-    });
-
-    let resp = client
-        .post(config.ollama.url.clone())
-        .json(&request_body)
-        .send()
-        .expect("Request failed");
-
-    let json_resp: serde_json::Value = resp.json().expect("Invalid JSON");
-
-    // Clean and parse AI's JSON response
-    let content = &json_resp["response"];
-    let content_str = content.as_str().unwrap_or("");
-
-    // Remove escaped newlines and clean the JSON string
-    let cleaned_content = content_str
-        .replace("\\n", " ")
-        .replace("\n", " ")
-        .trim()
-        .to_string();
-
-    let decision = if cleaned_content.contains("\"decision\": \"T\"") {
-        "T"
-    } else if cleaned_content.contains("\"decision\": \"R\"") {
-        "R"
-    } else {
-        "AI decision malformed"
+/// Read a decision among `options` keep options from the model's answer.
+///
+/// The JSON object may be wrapped in a code fence or prose, hold comments,
+/// raw line breaks in strings or miscased keys, or be cut off at the end.
+/// The error says what is wrong, for the model to correct.
+pub fn parse_decision(answer: &str, options: usize) -> Result<LlmDecision, String> {
+    let objects = json_objects(answer);
+    if objects.is_empty() {
+        return Err("no JSON object was found".to_string());
     }
-    .to_string();
-
-    let explanation = if let Some(start) = cleaned_content.find("\"explanation\": \"") {
-        let after_key = &cleaned_content[start + 16..];
-        // Find the end quote, but handle case where closing brace might be missing
-        if let Some(quote_end) = after_key.rfind("\"") {
-            after_key[..quote_end].to_string()
-        } else {
-            after_key.to_string()
-        }
-    } else {
-        "AI response was malformed".to_string()
+    let mut problem = String::new();
+    for object in objects {
+        problem = match serde_json::from_str::<Value>(&object) {
+            Ok(Value::Object(fields)) => match read_decision(&fields, options) {
+                Ok(decision) => return Ok(decision),
+                Err(e) => e,
+            },
+            Ok(_) => "the answer is not a JSON object".to_string(),
+            Err(e) => format!("the JSON is invalid ({})", e),
+        };
+    }
+    Err(problem)
+}
+
+fn read_decision(fields: &Map<String, Value>, options: usize) -> Result<LlmDecision, String> {
+    let field = |name: &str| {
+        fields
+            .iter()
+            .find(|(key, _)| key.trim().eq_ignore_ascii_case(name))
+            .map(|(_, value)| value)
     };
 
-    (decision, explanation)
+    let bank = match field("decision") {
+        Some(Value::String(decision)) => match decision
+            .trim()
+            .trim_matches(|c: char| !c.is_alphanumeric())
+            .to_lowercase()
+            .as_str()
+        {
+            "t" | "take" | "take points" | "take the points" | "bank" | "stop" => true,
+            "r" | "roll" | "roll again" | "reroll" => false,
+            _ => return Err(format!("'decision' must be \"R\" or \"T\", not {:?}", decision)),
+        },
+        Some(other) => return Err(format!("'decision' must be \"R\" or \"T\", not {}", other)),
+        None => return Err("the 'decision' key is missing".to_string()),
+    };
+
+    let keep = match field("keep") {
+        Some(Value::Number(keep)) => keep.as_u64(),
+        Some(Value::String(keep)) => keep.trim().trim_end_matches(')').parse().ok(),
+        // Nothing to choose
+        None if options == 1 => Some(1),
+        None => return Err("the 'keep' key is missing".to_string()),
+        Some(_) => None,
+    };
+    let keep = keep
+        .map(|keep| keep as usize)
+        .filter(|keep| (1..=options).contains(keep))
+        .ok_or_else(|| format!("'keep' must be a keep option number from 1 to {}", options))?;
+
+    let explanation = match field("explanation") {
+        Some(Value::String(explanation)) => explanation.trim().to_string(),
+        _ => String::new(),
+    };
+    Ok(LlmDecision {
+        keep,
+        bank,
+        explanation,
+    })
+}
+
+/// Top-level `{...}` objects in `text`, with `//` comments dropped and
+/// control characters in strings turned to spaces. An object cut off at
+/// the end is closed.
+fn json_objects(text: &str) -> Vec<String> {
+    let mut objects = Vec::new();
+    let mut current = String::new();
+    let mut depth = 0;
+    let mut in_string = false;
+    let mut escaped = false;
+    let mut chars = text.chars().peekable();
+    while let Some(c) = chars.next() {
+        if depth == 0 {
+            if c == '{' {
+                depth = 1;
+                current = c.to_string();
+            }
+            continue;
+        }
+        if in_string {
+            current.push(if c.is_control() { ' ' } else { c });
+            if escaped {
+                escaped = false;
+            } else if c == '\\' {
+                escaped = true;
+            } else if c == '"' {
+                in_string = false;
+            }
+            continue;
+        }
+        match c {
+            '"' => in_string = true,
+            '/' if chars.peek() == Some(&'/') => {
+                while chars.next_if(|&c| c != '\n').is_some() {}
+                continue;
+            }
+            '{' => depth += 1,
+            '}' => depth -= 1,
+            _ => {}
+        }
+        current.push(c);
+        if depth == 0 {
+            objects.push(std::mem::take(&mut current));
+        }
+    }
+    if depth > 0 {
+        if in_string {
+            current.push('"');
+        }
+        current.extend(std::iter::repeat_n('}', depth));
+        objects.push(current);
+    }
+    objects
 }
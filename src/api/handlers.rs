@@ -1,197 +1,1139 @@
 use axum::Json;
 use axum::{
     extract::{State, Path},
-    http::StatusCode,
+    http::{header, HeaderMap, StatusCode},
+    response::sse::{self, KeepAlive, Sse},
+    response::{IntoResponse, Response},
     Extension,
 };
+use futures_util::stream::{self, Stream, StreamExt};
+use rand::Rng;
+use colored::Colorize;
 use std::collections::HashMap;
+use std::io;
+use std::convert::Infallible;
+use std::time::Duration;
+use tokio::sync::broadcast::{self, error::RecvError};
 use uuid::Uuid;
-use std::sync::Arc;
-use std::sync::Mutex;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, LockResult, Mutex, MutexGuard};
 
 use axum::extract::Query;
+use axum::extract::rejection::{JsonRejection, QueryRejection};
 
+use crate::ai::{AI_PERSONALITIES, LlmPlayer};
 use crate::config::Config;
-use crate::score::{roll_dice, calculate_score};
-use crate::computer::computer_turn_stateful;
+use crate::dice::SeededDice;
+use crate::computer::{computer_step, controller_from_config};
+use crate::engine::{Action, EngineError, Event, NUM_DICE, Player};
+use crate::llm::provider_from_config;
+use crate::record::GameRecord;
+use crate::strategy::{self, Strategy};
 
+use crate::api::repository::{BotSpec, GameRepository, STORE_VERSION, StoredGame, now_secs};
 use crate::api::models::{
-    CreateGameRequest, GameResponse, GameState, StatusQuery,
-    RollDiceRequest, StatusFullResponse
+    BankRequest, CreateGameRequest, ErrorResponse, GameFilter, GameResponse, GameState, GameSummary,
+    GameUpdate, GamesQuery, GamesResponse, JoinRequest, JoinResponse, LobbyResponse, PlayerScore,
+    ScoresResponse, SeatRequest, SeatToken, Status, StatusQuery, RollDiceRequest, StatusFullResponse
 };
 
-const NUM_DICE: usize = 6;
+/// A bot's controller, kept for the whole game so it can learn from its
+/// past turns.
+pub type SharedController = Arc<Mutex<Box<dyn Strategy>>>;
 
-pub type GameStore = Arc<Mutex<HashMap<String, GameState>>>;
+/// A refused request: its status, with an [`ErrorResponse`] as body.
+#[derive(Debug)]
+pub struct ApiError {
+    pub status: StatusCode,
+    pub body: ErrorResponse,
+}
+
+impl ApiError {
+    pub fn new(status: StatusCode, code: &str, message: impl Into<String>) -> Self {
+        ApiError {
+            status,
+            body: ErrorResponse::new(code, message),
+        }
+    }
+
+    pub fn with_errors(mut self, errors: Vec<String>) -> Self {
+        self.body.errors = errors;
+        self
+    }
+
+    fn game_not_found(game_id: &str) -> Self {
+        Self::new(StatusCode::NOT_FOUND, "game_not_found", format!("no game {}", game_id))
+    }
+
+    fn lobby_not_found(code: &str) -> Self {
+        Self::new(StatusCode::NOT_FOUND, "lobby_not_found", format!("no game to join with code {}", code))
+    }
+
+    /// An action the rules do not allow now.
+    fn invalid_action(e: EngineError) -> Self {
+        Self::new(StatusCode::BAD_REQUEST, "invalid_action", e.to_string())
+    }
+}
+
+impl IntoResponse for ApiError {
+    fn into_response(self) -> Response {
+        (self.status, Json(self.body)).into_response()
+    }
+}
+
+impl From<JsonRejection> for ApiError {
+    fn from(rejection: JsonRejection) -> Self {
+        Self::new(rejection.status(), "invalid_request", "Invalid request").with_errors(vec![rejection.body_text()])
+    }
+}
+
+impl From<QueryRejection> for ApiError {
+    fn from(rejection: QueryRejection) -> Self {
+        Self::new(rejection.status(), "invalid_request", "Invalid request").with_errors(vec![rejection.body_text()])
+    }
+}
+
+/// Most seats of a web game.
+pub const MAX_SEATS: usize = 8;
+
+/// Updates a game's stream holds for subscribers that fall behind.
+const UPDATE_BUFFER: usize = 256;
+
+/// How often [`spawn_expiry`] looks for expired games.
+const EXPIRY_INTERVAL: Duration = Duration::from_secs(60);
+
+/// A web game, the dice it is played with, its record and its bots.
+#[derive(Clone)]
+pub struct GameSession {
+    pub state: GameState,
+    pub dice: SeededDice,
+    pub record: GameRecord,
+    /// Controller of every seat, None for humans.
+    pub bots: Vec<Option<SharedController>>,
+    /// How every bot was set up, None for humans.
+    pub bot_specs: Vec<Option<BotSpec>>,
+    /// Every event, for the `/events` subscribers.
+    pub updates: broadcast::Sender<GameUpdate>,
+    /// A task is playing the bots' turns (see [`spawn_bots`]).
+    pub bots_playing: bool,
+    /// Token of every human seat, None for bots and open seats.
+    pub seat_tokens: Vec<Option<String>>,
+    /// Code to join the open seats.
+    pub invite_code: String,
+    /// Last change, in seconds since the Unix epoch.
+    pub updated_at: u64,
+}
+
+impl GameSession {
+    /// A session whose bots play the strategy of their seat (`ai_type`), the
+    /// configured computer when it is unknown.
+    pub fn new(state: GameState, dice: SeededDice, config: &Config) -> Self {
+        let bot_specs = ai_type_specs(&state, config);
+        Self::with_specs(state, dice, bot_specs, config)
+    }
+
+    /// A stored game, its bots seated again as they were set up.
+    pub fn restore(stored: StoredGame, config: &Config) -> Self {
+        let bot_specs = if stored.bots.len() == stored.state.players.len() {
+            stored.bots
+        } else {
+            ai_type_specs(&stored.state, config)
+        };
+        let mut session = Self::with_specs(stored.state, stored.dice, bot_specs, config);
+        session.record = stored.record;
+        session.seat_tokens = stored.seat_tokens;
+        session.invite_code = stored.invite_code;
+        session.updated_at = stored.updated_at;
+        session
+    }
+
+    /// What the repository keeps of the session.
+    pub fn stored(&self) -> StoredGame {
+        StoredGame {
+            version: STORE_VERSION,
+            state: self.state.clone(),
+            dice: self.dice.clone(),
+            record: self.record.clone(),
+            bots: self.bot_specs.clone(),
+            seat_tokens: self.seat_tokens.clone(),
+            invite_code: self.invite_code.clone(),
+            updated_at: self.updated_at,
+        }
+    }
+
+    /// A session whose bots play as `bot_specs` says, the configured computer
+    /// when their strategy is unknown.
+    pub fn with_specs(state: GameState, dice: SeededDice, bot_specs: Vec<Option<BotSpec>>, config: &Config) -> Self {
+        let bots: Vec<Option<SharedController>> = bot_specs
+            .iter()
+            .map(|spec| {
+                let spec = spec.as_ref()?;
+                let controller = spec_bot(spec, config).unwrap_or_else(|| controller_from_config(config));
+                Some(Arc::new(Mutex::new(controller)))
+            })
+            .collect();
+        let record = GameRecord::new(&state, Some(dice.seed()));
+        let (updates, _) = broadcast::channel(UPDATE_BUFFER);
+        let seat_tokens = bots
+            .iter()
+            .map(|bot| bot.is_none().then(|| Uuid::new_v4().simple().to_string()))
+            .collect();
+        GameSession {
+            state,
+            dice,
+            record,
+            bots,
+            bot_specs,
+            updates,
+            bots_playing: false,
+            seat_tokens,
+            invite_code: invite_code(),
+            updated_at: now_secs(),
+        }
+    }
+
+    /// Human seats nobody has taken yet.
+    pub fn open_seats(&self) -> Vec<usize> {
+        (0..self.bots.len())
+            .filter(|&seat| self.bots[seat].is_none() && self.seat_tokens[seat].is_none())
+            .collect()
+    }
+
+    /// Tokens of the human seats that are taken.
+    pub fn taken_seats(&self) -> Vec<SeatToken> {
+        self.seat_tokens
+            .iter()
+            .enumerate()
+            .filter_map(|(seat, token)| {
+                Some(SeatToken {
+                    seat,
+                    name: self.state.players[seat].name.clone(),
+                    token: token.clone()?,
+                })
+            })
+            .collect()
+    }
+
+    /// Apply an action with the game's dice and publish what happened.
+    pub fn apply(&mut self, action: Action) -> Result<Vec<Event>, EngineError> {
+        let events = self.state.apply(action, &mut self.dice)?;
+        self.publish(&events);
+        Ok(events)
+    }
+
+    /// Record events and send them to the subscribers with the current state.
+    pub fn publish(&mut self, events: &[Event]) {
+        self.record.extend(events.iter().cloned());
+        for event in events {
+            // Nobody may be listening
+            let _ = self.updates.send(GameUpdate {
+                event: Some(event.clone()),
+                game_state: self.state.clone(),
+            });
+        }
+    }
+
+    /// True when the current seat is a bot's and the game goes on, with
+    /// every seat taken.
+    pub fn bots_turn(&self) -> bool {
+        !self.state.game_over
+            && self.bots.get(self.state.current_player_index).is_some_and(Option::is_some)
+            && self.open_seats().is_empty()
+    }
+
+    /// The last explanation given this turn, as (decision, explanation).
+    pub fn last_explanation(&self) -> Option<(String, String)> {
+        self.record
+            .events
+            .iter()
+            .rev()
+            .take_while(|event| !matches!(event, Event::TurnStarted { .. }))
+            .find_map(|event| match event {
+                Event::Explanation { decision, explanation, .. } => Some((decision.clone(), explanation.clone())),
+                _ => None,
+            })
+    }
+}
+
+/// Bot of every seat playing its `ai_type`, in the configured language;
+/// None for humans.
+fn ai_type_specs(state: &GameState, config: &Config) -> Vec<Option<BotSpec>> {
+    state
+        .players
+        .iter()
+        .map(|player| {
+            (!player.is_human).then(|| BotSpec {
+                strategy: player.ai_type.clone().unwrap_or_else(|| "computer".to_string()),
+                language: Some(config.game.ai_output_language.clone()),
+                ..BotSpec::default()
+            })
+        })
+        .collect()
+}
+
+/// The bot a seat was set up with, None when its strategy or provider is unknown.
+fn spec_bot(spec: &BotSpec, config: &Config) -> Option<Box<dyn Strategy>> {
+    let mut config = config.clone();
+    if let Some(language) = &spec.language {
+        config.game.ai_output_language = language.clone();
+    }
+    if let Some(personality) = &spec.personality {
+        config.game.ai_personality = personality.clone();
+    }
+    match &spec.model {
+        Some(model) => {
+            let mut endpoint = config.provider(&spec.strategy)?;
+            endpoint.model = model.clone();
+            Some(Box::new(LlmPlayer::new(provider_from_config(&endpoint), Arc::new(config))))
+        }
+        None => strategy::create(&spec.strategy, &config),
+    }
+}
+
+/// Six letters or digits, without the look-alikes (0/O, 1/I).
+fn invite_code() -> String {
+    const CHARS: &[u8] = b"ABCDEFGHJKLMNPQRSTUVWXYZ23456789";
+    let mut rng = rand::thread_rng();
+    (0..6).map(|_| CHARS[rng.gen_range(0..CHARS.len())] as char).collect()
+}
+
+/// The request comes from the seat whose turn it is: its token is sent as
+/// `Authorization: Bearer <token>`. Nobody plays before every seat is taken.
+fn authorize(session: &GameSession, headers: &HeaderMap) -> Result<(), ApiError> {
+    if !session.open_seats().is_empty() {
+        return Err(ApiError::new(
+            StatusCode::CONFLICT,
+            "waiting_for_players",
+            "the game starts once every seat is taken",
+        ));
+    }
+    if session.bots_turn() {
+        return Err(ApiError::new(
+            StatusCode::CONFLICT,
+            "bots_turn",
+            format!("{} is a bot and plays its own turns", session.state.current_player().name),
+        ));
+    }
+    let token = headers
+        .get(header::AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "))
+        .ok_or_else(|| {
+            ApiError::new(StatusCode::UNAUTHORIZED, "missing_token", "send your seat token as a Bearer token")
+        })?;
+    match &session.seat_tokens[session.state.current_player_index] {
+        Some(seat_token) if seat_token == token.trim() => Ok(()),
+        _ => Err(ApiError::new(
+            StatusCode::FORBIDDEN,
+            "not_your_turn",
+            format!("it is {}'s turn", session.state.current_player().name),
+        )),
+    }
+}
+
+/// Play the bots' turns in the background while the current seat is a bot's:
+/// a step every `delay`, then on to the next player when the turn is over.
+/// Does nothing when bots are already playing this game.
+pub fn spawn_bots(store: &GameStore, game_id: &str, delay: Duration) {
+    {
+        let mut games = store.lock().unwrap();
+        let Some(session) = games.get_mut(game_id) else {
+            return;
+        };
+        if session.bots_playing || !session.bots_turn() {
+            return;
+        }
+        session.bots_playing = true;
+    }
+    let store = store.clone();
+    let game_id = game_id.to_string();
+    tokio::spawn(async move {
+        loop {
+            tokio::time::sleep(delay).await;
+            let (step_store, step_game_id) = (store.clone(), game_id.clone());
+            // LLM players wait on their provider, off the async runtime
+            match tokio::task::spawn_blocking(move || bot_step(&step_store, &step_game_id)).await {
+                Ok(true) => {}
+                Ok(false) => break,
+                Err(e) => {
+                    // A panicking bot must not leave the game waiting forever:
+                    // the next request starts the bots again
+                    report_error(format!("bot step of game {} failed: {}", game_id, e));
+                    if let Some(session) = store.lock().unwrap().get_mut(&game_id) {
+                        session.bots_playing = false;
+                    }
+                    break;
+                }
+            }
+        }
+    });
+}
+
+/// One step of the bots' play; false once it is a human's turn or the game is over.
+fn bot_step(store: &GameStore, game_id: &str) -> bool {
+    let (bot, mut state, mut dice) = {
+        let mut games = store.lock().unwrap();
+        let Some(session) = games.get_mut(game_id) else {
+            return false;
+        };
+        if !session.bots_turn() {
+            session.bots_playing = false;
+            return false;
+        }
+        if session.state.turn_terminated {
+            let _ = session.apply(Action::NextPlayer);
+            let pending = store.changed(session);
+            drop(games);
+            if let Err(e) = store.write(pending) {
+                report_error(e);
+            }
+            return true;
+        }
+        let bot = session.bots[session.state.current_player_index].clone();
+        (bot, session.state.clone(), session.dice.clone())
+    };
+    let Some(bot) = bot else {
+        return false;
+    };
+    let summary = {
+        // A bot that panicked mid-step still plays on
+        let mut controller = bot.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+        computer_step(&mut state, &mut *controller, &mut dice)
+    };
+
+    let mut games = store.lock().unwrap();
+    let Some(session) = games.get_mut(game_id) else {
+        return false;
+    };
+    session.state = state;
+    session.dice = dice;
+    session.publish(&summary.events);
+    let pending = store.changed(session);
+    drop(games);
+    if let Err(e) = store.write(pending) {
+        report_error(e);
+    }
+    true
+}
+
+/// The games being played, written through to a [`GameRepository`].
+#[derive(Clone)]
+pub struct GameStore {
+    games: Arc<Mutex<HashMap<String, GameSession>>>,
+    repository: Arc<dyn GameRepository>,
+    /// Numbers the changes, in the order they were made.
+    changes: Arc<AtomicU64>,
+    /// Last change written of every game, by game id.
+    written: Arc<Mutex<HashMap<String, WriteSlot>>>,
+}
+
+/// Last change written of a game, `u64::MAX` once it is deleted. Locked
+/// while the game is written, so its writes happen one at a time.
+type WriteSlot = Arc<Mutex<u64>>;
+
+/// A change to a game, to write once the games are unlocked (see
+/// [`GameStore::changed`]).
+#[must_use = "the change is only written by GameStore::save or GameStore::write"]
+pub struct PendingSave {
+    change: u64,
+    game: StoredGame,
+}
+
+impl GameStore {
+    /// The store of the repository's games, their bots seated again. The
+    /// games that expired while the server was down are dropped.
+    pub fn new(repository: Arc<dyn GameRepository>, config: &Config) -> Self {
+        let stored = repository.load_all().unwrap_or_else(|e| {
+            report_error(format!("could not load the stored games: {}", e));
+            Vec::new()
+        });
+        let games = stored
+            .into_iter()
+            .map(|game| (game.state.id.clone(), GameSession::restore(game, config)))
+            .collect();
+        let store = GameStore {
+            games: Arc::new(Mutex::new(games)),
+            repository,
+            changes: Arc::new(AtomicU64::new(1)),
+            written: Arc::default(),
+        };
+        store.expire(config.server.idle_ttl_secs, config.server.finished_ttl_secs);
+        store
+    }
+
+    pub fn lock(&self) -> LockResult<MutexGuard<'_, HashMap<String, GameSession>>> {
+        self.games.lock()
+    }
+
+    /// Note a change to a session, while the games are locked. The change is
+    /// written once they are unlocked, so the storage never holds up the
+    /// other games.
+    pub fn changed(&self, session: &mut GameSession) -> PendingSave {
+        session.updated_at = now_secs();
+        PendingSave {
+            change: self.changes.fetch_add(1, Ordering::Relaxed),
+            game: session.stored(),
+        }
+    }
+
+    /// [`GameStore::write`] a change off the async runtime. A failed save is
+    /// reported and the game goes on in memory.
+    pub async fn save(&self, pending: PendingSave) {
+        let store = self.clone();
+        match tokio::task::spawn_blocking(move || store.write(pending)).await {
+            Ok(Ok(())) => {}
+            Ok(Err(e)) => report_error(e),
+            Err(e) => report_error(format!("saving a game failed: {}", e)),
+        }
+    }
+
+    /// Write a change, unless a later one of the game was written already or
+    /// the game is gone. Waits on the repository, and on the other writes of
+    /// the same game only.
+    pub fn write(&self, pending: PendingSave) -> io::Result<()> {
+        let id = &pending.game.state.id;
+        let slot = self.write_slot(id);
+        let mut written = slot.lock().unwrap();
+        if *written >= pending.change {
+            return Ok(());
+        }
+        self.repository
+            .save(&pending.game)
+            .map_err(|e| io::Error::new(e.kind(), format!("could not save game {}: {}", id, e)))?;
+        *written = pending.change;
+        Ok(())
+    }
+
+    fn write_slot(&self, id: &str) -> WriteSlot {
+        self.written.lock().unwrap().entry(id.to_string()).or_default().clone()
+    }
+
+    /// Drop the games idle for `idle_ttl` seconds, and the finished ones
+    /// after `finished_ttl` seconds. Their ids. Waits on the repository.
+    pub fn expire(&self, idle_ttl: u64, finished_ttl: u64) -> Vec<String> {
+        let now = now_secs();
+        let expired: Vec<String> = {
+            let mut games = self.games.lock().unwrap();
+            let expired: Vec<String> = games
+                .values()
+                .filter(|session| {
+                    let ttl = if session.state.game_over { finished_ttl.min(idle_ttl) } else { idle_ttl };
+                    now.saturating_sub(session.updated_at) >= ttl
+                })
+                .map(|session| session.state.id.clone())
+                .collect();
+            for id in &expired {
+                games.remove(id);
+            }
+            expired
+        };
+        for id in &expired {
+            let slot = self.write_slot(id);
+            let mut written = slot.lock().unwrap();
+            if let Err(e) = self.repository.delete(id) {
+                report_error(format!("could not delete game {}: {}", id, e));
+            }
+            // Changes noted before the game was dropped are not written after it
+            *written = u64::MAX;
+        }
+        expired
+    }
+
+    /// [`GameStore::expire`] off the async runtime.
+    pub async fn sweep(&self, idle_ttl: u64, finished_ttl: u64) {
+        let store = self.clone();
+        if let Err(e) = tokio::task::spawn_blocking(move || store.expire(idle_ttl, finished_ttl)).await {
+            report_error(format!("expiring games failed: {}", e));
+        }
+    }
+}
+
+/// Drop the expired games every [`EXPIRY_INTERVAL`] for as long as the
+/// server runs. Must be called from within the Tokio runtime.
+pub fn spawn_expiry(store: &GameStore, config: &Config) {
+    let store = store.clone();
+    let (idle_ttl, finished_ttl) = (config.server.idle_ttl_secs, config.server.finished_ttl_secs);
+    tokio::spawn(async move {
+        let start = tokio::time::Instant::now() + EXPIRY_INTERVAL;
+        let mut interval = tokio::time::interval_at(start, EXPIRY_INTERVAL);
+        loop {
+            interval.tick().await;
+            store.sweep(idle_ttl, finished_ttl).await;
+        }
+    });
+}
+
+/// Report a server error no request is waiting for.
+fn report_error(e: impl std::fmt::Display) {
+    println!("{}", format!("Error: {}", e).bold().red());
+}
+
+pub async fn health_handler() -> Json<Status> {
+    Json(Status {
+        status: "ok".to_string(),
+    })
+}
 
 pub async fn create_game(
     State(store): State<GameStore>,
     Extension(config): Extension<Arc<Config>>,
-    Json(_request): Json<CreateGameRequest>,
-) -> Result<Json<GameResponse>, StatusCode> {
+    request: Result<Json<CreateGameRequest>, JsonRejection>,
+) -> Result<Json<GameResponse>, ApiError> {
+    let Json(request) = request?;
+    let invalid = |errors| {
+        ApiError::new(StatusCode::UNPROCESSABLE_ENTITY, "invalid_game", "Invalid game request").with_errors(errors)
+    };
+
+    // The game's own settings
+    let mut config = (*config).clone();
+    let mut errors = Vec::new();
+    match request.target_score {
+        Some(0) => errors.push("target_score must be positive".to_string()),
+        Some(target_score) => config.game.target_score = target_score,
+        None => {}
+    }
+    match request.language.as_deref() {
+        Some(language @ ("en" | "fr")) => config.game.ai_output_language = language.to_string(),
+        Some(language) => errors.push(format!("language must be \"en\" or \"fr\", not \"{}\"", language)),
+        None => {}
+    }
+    let roster = match &request.players {
+        Some(seats) => match build_roster(seats, &config) {
+            Ok(roster) => Some(roster),
+            Err(seat_errors) => {
+                errors.extend(seat_errors);
+                None
+            }
+        },
+        None => None,
+    };
+    if !errors.is_empty() {
+        return Err(invalid(errors));
+    }
+
     let game_id = Uuid::new_v4().to_string();
-    let mut game_state = GameState::new(game_id.clone(), false, false, &config);
+    let dice = SeededDice::from_seed(request.seed.or(config.game.seed));
+    let mut session = match roster {
+        Some(roster) => {
+            let state = GameState::with_players(game_id.clone(), roster.players, &config.game);
+            let mut session = GameSession::with_specs(state, dice, roster.bot_specs, &config);
+            for seat in roster.open_seats {
+                session.seat_tokens[seat] = None;
+            }
+            session
+        }
+        None => GameSession::new(GameState::new(game_id.clone(), false, false, &config), dice, &config),
+    };
+    let mut game_state = session.state.clone();
     // Ensure the first player can roll at the start
     game_state.dice_count = NUM_DICE;
     game_state.dice.clear(); // No dice rolled yet
-    
+    let seats = session.taken_seats();
+
+    // Make room before adding a game
+    store.sweep(config.server.idle_ttl_secs, config.server.finished_ttl_secs).await;
+
     // insert game into the store
-    let mut games = store.lock().unwrap();
-    games.insert(game_id.clone(), game_state.clone());
+    let (invite_code, pending) = {
+        let mut games = store.lock().unwrap();
+        while games.values().any(|game| game.invite_code == session.invite_code) {
+            session.invite_code = invite_code();
+        }
+        let pending = store.changed(&mut session);
+        let invite_code = (!session.open_seats().is_empty()).then(|| session.invite_code.clone());
+        games.insert(game_id.clone(), session);
+        (invite_code, pending)
+    };
+    store.save(pending).await;
+    spawn_bots(&store, &game_id, Duration::from_millis(config.server.bot_delay_ms));
     
     println!("Game created with ID: {}", game_id);
     Ok(Json(GameResponse {
         success: true,
         game_state: Some(game_state),
+        invite_code,
+        seats,
     }))
 }
 
-pub async fn roll_dice_handler(
-    Path(game_id): Path<String>,
+/// The game behind an invite code and its open seats.
+pub async fn lobby_handler(
+    Path(code): Path<String>,
     State(store): State<GameStore>,
-    Extension(_config): Extension<Arc<Config>>,
-    Json(_request): Json<RollDiceRequest>,
-) -> Result<Json<StatusFullResponse>, StatusCode> {
-    let mut games = store.lock().unwrap();
-    let game_state = games.get_mut(&game_id)
-        .ok_or(StatusCode::NOT_FOUND)?;
-
-    if game_state.game_over {
-        return Err(StatusCode::BAD_REQUEST);
-    }
-
-    // Roll dice
-    let dice_to_roll = if game_state.dice_count == 0 { NUM_DICE } else { game_state.dice_count };
-    let roll = roll_dice(dice_to_roll);
-    game_state.dice = roll.clone();
-
-    let (score, remaining_dice, _remaining_dice_values, rerollable_dice) = calculate_score(&roll);
-    game_state.rerollable_dice = rerollable_dice;
-    let busted = score == 0;
+) -> Result<Json<LobbyResponse>, ApiError> {
+    let games = store.lock().unwrap();
+    let session = games
+        .values()
+        .find(|game| game.invite_code.eq_ignore_ascii_case(&code))
+        .ok_or_else(|| ApiError::lobby_not_found(&code))?;
+    Ok(Json(LobbyResponse {
+        game_id: session.state.id.clone(),
+        open_seats: session.open_seats(),
+        game_state: session.state.clone(),
+    }))
+}
 
-    if busted {
-        game_state.current_player_mut().turn_score = 0;
-        game_state.current_player_mut().roll_score = 0;
-        game_state.turn_terminated = true;
+/// Take an open seat with the invite code; the game starts once every seat
+/// is taken.
+pub async fn join_handler(
+    Path(code): Path<String>,
+    State(store): State<GameStore>,
+    Extension(config): Extension<Arc<Config>>,
+    request: Result<Json<JoinRequest>, JsonRejection>,
+) -> Result<Json<JoinResponse>, ApiError> {
+    let Json(request) = request?;
+    let name = request.name.trim().to_string();
+    let (response, pending) = {
+        let mut games = store.lock().unwrap();
+        let session = games
+            .values_mut()
+            .find(|game| game.invite_code.eq_ignore_ascii_case(&code))
+            .ok_or_else(|| ApiError::lobby_not_found(&code))?;
+        let open_seats = session.open_seats();
+        let seat = match request.seat {
+            Some(seat) if open_seats.contains(&seat) => seat,
+            Some(seat) => {
+                return Err(ApiError::new(StatusCode::CONFLICT, "seat_taken", format!("seat {} is not open", seat)));
+            }
+            None => *open_seats
+                .first()
+                .ok_or_else(|| ApiError::new(StatusCode::CONFLICT, "game_full", "every seat is taken"))?,
+        };
+        let taken = session
+            .state
+            .players
+            .iter()
+            .enumerate()
+            .any(|(i, player)| i != seat && player.name.eq_ignore_ascii_case(&name));
+        if name.is_empty() {
+            return Err(ApiError::new(StatusCode::UNPROCESSABLE_ENTITY, "invalid_name", "the name is empty"));
+        }
+        if taken {
+            return Err(ApiError::new(
+                StatusCode::UNPROCESSABLE_ENTITY,
+                "invalid_name",
+                format!("the name \"{}\" is already taken", name),
+            ));
+        }
 
-        return Ok(Json(StatusFullResponse {
+        let token = Uuid::new_v4().simple().to_string();
+        session.seat_tokens[seat] = Some(token.clone());
+        session.state.players[seat].name = name.clone();
+        let pending = store.changed(session);
+        // Let the other seats see who joined
+        let _ = session.updates.send(GameUpdate { event: None, game_state: session.state.clone() });
+        let response = JoinResponse {
             success: true,
-            game_state: Some(game_state.clone()),
-            ai_decision: None,
-            ai_explanation: None,
-            turn_end_reason: Some("busted".to_string()),
-        }));
-    }
+            game_id: session.state.id.clone(),
+            seat: SeatToken { seat, name, token },
+            game_state: session.state.clone(),
+        };
+        (response, pending)
+    };
+    store.save(pending).await;
+    spawn_bots(&store, &response.game_id, Duration::from_millis(config.server.bot_delay_ms));
+    Ok(Json(response))
+}
 
-    {
-        let current_player = game_state.current_player_mut();
-        current_player.roll_score = score;
-        current_player.turn_score += score;
-    }
+pub async fn roll_dice_handler(
+    Path(game_id): Path<String>,
+    State(store): State<GameStore>,
+    headers: HeaderMap,
+    request: Result<Json<RollDiceRequest>, JsonRejection>,
+) -> Result<Json<StatusFullResponse>, ApiError> {
+    let Json(request) = request?;
+    let (game_state, pending) = {
+        let mut games = store.lock().unwrap();
+        let session = games.get_mut(&game_id)
+            .ok_or_else(|| ApiError::game_not_found(&game_id))?;
+        authorize(session, &headers)?;
 
-    let next_dice_count = if remaining_dice == 0 { 6 } else { remaining_dice as usize };
-    game_state.dice_count = next_dice_count;
+        // Set aside the chosen dice from the previous roll before rolling the rest
+        let mut turn_ended = false;
+        if session.state.has_pending_roll() {
+            let keep = request.keep.unwrap_or_else(|| session.state.default_keep());
+            session
+                .apply(Action::Keep(keep))
+                .map_err(ApiError::invalid_action)?;
+            // A turn-ending combination or an overshoot ends the turn right away
+            turn_ended = session.state.turn_terminated;
+        }
+        if !turn_ended {
+            session
+                .apply(Action::Roll)
+                .map_err(ApiError::invalid_action)?;
+        }
+        (session.state.clone(), store.changed(session))
+    };
+    store.save(pending).await;
 
     Ok(Json(StatusFullResponse {
         success: true,
-        game_state: Some(game_state.clone()),
+        game_state: Some(game_state),
         ai_decision: None,
         ai_explanation: None,
-       // turn_end_reason: None,
+        message: None,
     }))
 }
 
 pub async fn bank_points_handler(
     Path(game_id): Path<String>,
     State(store): State<GameStore>,
-    Extension(_config): Extension<Arc<Config>>,
-) -> Result<Json<StatusFullResponse>, StatusCode> {
-    let mut games = store.lock().unwrap();
-    let game_state = games.get_mut(&game_id)
-        .ok_or(StatusCode::NOT_FOUND)?;
+    headers: HeaderMap,
+    request: Result<Json<BankRequest>, JsonRejection>,
+) -> Result<Json<StatusFullResponse>, ApiError> {
+    let Json(request) = request?;
+    let (game_state, pending) = {
+        let mut games = store.lock().unwrap();
+        let session = games.get_mut(&game_id)
+            .ok_or_else(|| ApiError::game_not_found(&game_id))?;
+        authorize(session, &headers)?;
 
-    if game_state.game_over {
-        return Err(StatusCode::BAD_REQUEST);
-    }
+        // Work on a copy of the state so a refused bank leaves the pending roll
+        // untouched and tells the subscribers nothing
+        let mut state = session.state.clone();
+        let mut dice = session.dice.clone();
+        let mut events = Vec::new();
+        if state.has_pending_roll() {
+            let keep = request.keep.unwrap_or_else(|| state.default_keep());
+            events.extend(
+                state
+                    .apply(Action::Keep(keep), &mut dice)
+                    .map_err(ApiError::invalid_action)?,
+            );
+        }
 
-    let current_player = game_state.current_player_mut();
-    current_player.score += current_player.turn_score;
-    current_player.turn_score = 0;
-    current_player.roll_score = 0;
-    game_state.turn_terminated = true;
-    game_state.turn_end_reason = Some("banked".to_string());
+        // The keep itself may end the turn (overshoot, turn-ending combination)
+        if !state.turn_terminated {
+            match state.apply(Action::Bank, &mut dice) {
+                Ok(banked) => events.extend(banked),
+                Err(reason @ (EngineError::BankRejected(_) | EngineError::MustRoll)) => {
+                    return Ok(Json(StatusFullResponse {
+                        success: false,
+                        game_state: Some(session.state.clone()),
+                        ai_decision: None,
+                        ai_explanation: None,
+                        message: Some(reason.to_string()),
+                    }));
+                }
+                Err(e) => return Err(ApiError::invalid_action(e)),
+            }
+        }
+        session.state = state;
+        session.dice = dice;
+        session.publish(&events);
+        (session.state.clone(), store.changed(session))
+    };
+    store.save(pending).await;
 
     Ok(Json(StatusFullResponse {
         success: true,
-        game_state: Some(game_state.clone()),
+        game_state: Some(game_state),
         ai_decision: None,
         ai_explanation: None,
-     //   turn_end_reason: Some("banked".to_string()),
+        message: None,
     }))
 }
 
+/// The game and the last explanation of the turn. Starts the bots if it is
+/// their turn and they are not playing yet.
 pub async fn game_status_handler(
     Path(game_id): Path<String>,
     State(store): State<GameStore>,
     Extension(config): Extension<Arc<Config>>,
-    _query: Query<StatusQuery>,
-) -> Result<Json<StatusFullResponse>, StatusCode> {
-    let mut games = store.lock().unwrap();
-    let game_state = games.get_mut(&game_id)
-        .ok_or(StatusCode::NOT_FOUND)?;
+    query: Result<Query<StatusQuery>, QueryRejection>,
+) -> Result<Json<StatusFullResponse>, ApiError> {
+    let Query(_) = query?;
+    spawn_bots(&store, &game_id, Duration::from_millis(config.server.bot_delay_ms));
+    let games = store.lock().unwrap();
+    let session = games.get(&game_id)
+        .ok_or_else(|| ApiError::game_not_found(&game_id))?;
+    let (ai_decision, ai_explanation) = session.last_explanation().unzip();
 
-    let mut ai_decision = None;
-    let mut ai_explanation = None;
-    if !game_state.current_player().is_human && !game_state.game_over && !game_state.turn_terminated {
-        let summary = computer_turn_stateful(game_state, &config);
-        ai_decision = summary.ai_decision;
-        ai_explanation = summary.ai_explanation;
-
-        if ai_decision.is_some("T") {
-            game_state.turn_end_reason = Some("banked".to_string());
-        }
-    }
-
-    let (score, _remaining_dice, _remaining_dice_values, rerollable_dice) = calculate_score(&game_state.dice.clone());
-    game_state.rerollable_dice = rerollable_dice;
-    
-   // let busted = score == 0;
-    if game_state.turn_score == 0 {
-        game_state.turn_end_reason = Some("busted".to_string());
-    }
-    
     Ok(Json(StatusFullResponse {
         success: true,
-        game_state: Some(game_state.clone()),
+        game_state: Some(session.state.clone()),
         ai_decision,
         ai_explanation,
-       // turn_end_reason,
+        message: None,
     }))
 }
 
 pub async fn next_player_handler(
     Path(game_id): Path<String>,
     State(store): State<GameStore>,
-    Extension(_config): Extension<Arc<Config>>,
-) -> Result<Json<StatusFullResponse>, StatusCode> {
-    let mut games = store.lock().unwrap();
-    let game_state = games.get_mut(&game_id)
-        .ok_or(StatusCode::NOT_FOUND)?;
-    if game_state.game_over {
-        return Err(StatusCode::BAD_REQUEST);
-    }
-   // game_state.turn_terminated = false;
-    game_state.turn_end_reason = Some("".to_string());
-    game_state.next_player();
-    game_state.check_winner();
-    game_state.dice.clear();
+    Extension(config): Extension<Arc<Config>>,
+    headers: HeaderMap,
+) -> Result<Json<StatusFullResponse>, ApiError> {
+    let (game_state, pending) = {
+        let mut games = store.lock().unwrap();
+        let session = games.get_mut(&game_id)
+            .ok_or_else(|| ApiError::game_not_found(&game_id))?;
+        authorize(session, &headers)?;
+        session
+            .apply(Action::NextPlayer)
+            .map_err(ApiError::invalid_action)?;
+        (session.state.clone(), store.changed(session))
+    };
+    store.save(pending).await;
+    spawn_bots(&store, &game_id, Duration::from_millis(config.server.bot_delay_ms));
     let ai_decision = None;
     let ai_explanation = None;
     Ok(Json(StatusFullResponse {
         success: true,
-        game_state: Some(game_state.clone()),
+        game_state: Some(game_state),
+        ai_decision,
+        ai_explanation,
+        message: None,
+    }))
+}
+
+/// The game and the last explanation of the turn. Changes nothing, unlike
+/// the `POST` status that also starts the bots.
+pub async fn game_handler(
+    Path(game_id): Path<String>,
+    State(store): State<GameStore>,
+) -> Result<Json<StatusFullResponse>, ApiError> {
+    let games = store.lock().unwrap();
+    let session = games.get(&game_id)
+        .ok_or_else(|| ApiError::game_not_found(&game_id))?;
+    let (ai_decision, ai_explanation) = session.last_explanation().unzip();
+    Ok(Json(StatusFullResponse {
+        success: true,
+        game_state: Some(session.state.clone()),
         ai_decision,
         ai_explanation,
-      //  turn_end_reason: None,
+        message: None,
     }))
 }
+
+/// The games, most recently played first, filtered by `status` (`active` or
+/// `finished`) and `player`.
+pub async fn list_games_handler(
+    State(store): State<GameStore>,
+    query: Result<Query<GamesQuery>, QueryRejection>,
+) -> Result<Json<GamesResponse>, ApiError> {
+    let Query(query) = query?;
+    let games = store.lock().unwrap();
+    let mut summaries: Vec<GameSummary> = games
+        .values()
+        .filter(|session| match query.status {
+            Some(GameFilter::Active) => !session.state.game_over,
+            Some(GameFilter::Finished) => session.state.game_over,
+            None => true,
+        })
+        .filter(|session| {
+            query.player.as_ref().is_none_or(|name| {
+                session.state.players.iter().any(|player| player.name.eq_ignore_ascii_case(name.trim()))
+            })
+        })
+        .map(|session| GameSummary {
+            game_id: session.state.id.clone(),
+            players: session.state.players.iter().map(|player| player.name.clone()).collect(),
+            current_player: session.state.current_player().name.clone(),
+            turn_number: session.state.turn_number,
+            game_over: session.state.game_over,
+            winner: session.state.winner.clone(),
+            open_seats: session.open_seats(),
+            updated_at: session.updated_at,
+        })
+        .collect();
+    summaries.sort_by(|a, b| b.updated_at.cmp(&a.updated_at).then_with(|| a.game_id.cmp(&b.game_id)));
+    Ok(Json(GamesResponse { games: summaries }))
+}
+
+/// Every player's scores.
+pub async fn scores_handler(
+    Path(game_id): Path<String>,
+    State(store): State<GameStore>,
+) -> Result<Json<ScoresResponse>, ApiError> {
+    let games = store.lock().unwrap();
+    let session = games.get(&game_id)
+        .ok_or_else(|| ApiError::game_not_found(&game_id))?;
+    let state = &session.state;
+    Ok(Json(ScoresResponse {
+        players: state
+            .players
+            .iter()
+            .enumerate()
+            .map(|(i, player)| PlayerScore {
+                name: player.name.clone(),
+                total_score: player.score,
+                turn_score: player.turn_score,
+                roll_score: player.roll_score,
+                is_current: i == state.current_player_index,
+            })
+            .collect(),
+        current_player: state.current_player().name.clone(),
+        turn_number: state.turn_number,
+    }))
+}
+
+/// Server-sent events of a game as they happen, named after the event type
+/// (`rolled`, `kept`, `banked`, `busted`, `turn_started`, `explanation`...),
+/// each with a [`GameUpdate`]. The stream opens with a `state` update.
+pub async fn game_events_handler(
+    Path(game_id): Path<String>,
+    State(store): State<GameStore>,
+) -> Result<Sse<impl Stream<Item = Result<sse::Event, Infallible>>>, ApiError> {
+    let (receiver, game_state) = {
+        let games = store.lock().unwrap();
+        let session = games.get(&game_id)
+            .ok_or_else(|| ApiError::game_not_found(&game_id))?;
+        (session.updates.subscribe(), session.state.clone())
+    };
+    let first = GameUpdate { event: None, game_state };
+    let updates = stream::unfold(receiver, |mut receiver| async move {
+        loop {
+            match receiver.recv().await {
+                Ok(update) => return Some((update, receiver)),
+                // A subscriber that fell behind skips ahead, the next update
+                // carries the whole state
+                Err(RecvError::Lagged(_)) => continue,
+                Err(RecvError::Closed) => return None,
+            }
+        }
+    });
+    let events = stream::once(async { first })
+        .chain(updates)
+        .map(|update| Ok(sse_event(&update)));
+    Ok(Sse::new(events).keep_alive(KeepAlive::default()))
+}
+
+fn sse_event(update: &GameUpdate) -> sse::Event {
+    let name = match &update.event {
+        Some(event) => serde_json::to_value(event)
+            .ok()
+            .and_then(|value| value["type"].as_str().map(str::to_string))
+            .unwrap_or_else(|| "event".to_string()),
+        None => "state".to_string(),
+    };
+    sse::Event::default()
+        .event(name)
+        .json_data(update)
+        .unwrap_or_default()
+}
+
+/// The game record as JSON Lines (see `record::GameRecord`).
+pub async fn game_record_handler(
+    Path(game_id): Path<String>,
+    State(store): State<GameStore>,
+) -> Result<([(header::HeaderName, &'static str); 1], String), ApiError> {
+    let games = store.lock().unwrap();
+    let session = games.get(&game_id)
+        .ok_or_else(|| ApiError::game_not_found(&game_id))?;
+    Ok((
+        [(header::CONTENT_TYPE, "application/x-ndjson")],
+        session.record.to_jsonl(),
+    ))
+}
+
+/// Players of a web game and how their bots are set up, by seat.
+struct Roster {
+    players: Vec<Player>,
+    bot_specs: Vec<Option<BotSpec>>,
+    /// Human seats left for others to join.
+    open_seats: Vec<usize>,
+}
+
+/// A seat's player and how its bot is set up, None for humans.
+type Seat = (Player, Option<BotSpec>);
+
+/// Players and bots of a web game, or every problem with the seats.
+fn build_roster(seats: &[SeatRequest], config: &Config) -> Result<Roster, Vec<String>> {
+    let mut errors = Vec::new();
+    if !(2..=MAX_SEATS).contains(&seats.len()) {
+        errors.push(format!("a game has 2 to {} seats, not {}", MAX_SEATS, seats.len()));
+    }
+
+    let mut players: Vec<Player> = Vec::new();
+    let mut bot_specs = Vec::new();
+    let mut open_seats = Vec::new();
+    for (i, seat) in seats.iter().enumerate() {
+        let seat_error = |e: String| format!("seat {}: {}", i + 1, e);
+        let (mut player, bot_spec) = match seat_player(seat, config) {
+            Ok(seat) => seat,
+            Err(e) => {
+                errors.push(seat_error(e));
+                continue;
+            }
+        };
+        if let SeatRequest::Open { .. } = seat {
+            open_seats.push(i);
+            if player.name.is_empty() {
+                player.name = format!("Seat {}", i + 1);
+            }
+        }
+        if player.name.is_empty() {
+            errors.push(seat_error("the name is empty".to_string()));
+        } else if players.iter().any(|p| p.name.eq_ignore_ascii_case(&player.name)) {
+            errors.push(seat_error(format!("the name \"{}\" is already taken", player.name)));
+        }
+        players.push(player);
+        bot_specs.push(bot_spec);
+    }
+
+    if errors.is_empty() { Ok(Roster { players, bot_specs, open_seats }) } else { Err(errors) }
+}
+
+fn seat_player(seat: &SeatRequest, config: &Config) -> Result<Seat, String> {
+    let player = |name: &str, ai_type: Option<String>| Player {
+        name: name.trim().to_string(),
+        score: 0,
+        is_human: ai_type.is_none(),
+        ai_type,
+        turn_score: 0,
+        roll_score: 0,
+        opened: false,
+        bust_streak: 0,
+    };
+    match seat {
+        SeatRequest::Human { name } => Ok((player(name, None), None)),
+        SeatRequest::Open { name } => Ok((player(name.as_deref().unwrap_or_default(), None), None)),
+        SeatRequest::Computer { name, strategy: chosen } => {
+            let chosen = chosen.as_deref().unwrap_or("computer");
+            let spec = BotSpec {
+                strategy: chosen.to_string(),
+                language: Some(config.game.ai_output_language.clone()),
+                ..BotSpec::default()
+            };
+            if spec_bot(&spec, config).is_none() {
+                return Err(format!("unknown strategy \"{}\" (known: {})", chosen, strategy::names().join(", ")));
+            }
+            let name = match name {
+                Some(name) => name.clone(),
+                None if chosen == "computer" => config.game.computer_player_name.clone(),
+                None => format!("{} ({})", config.game.computer_player_name, chosen),
+            };
+            Ok((player(&name, Some(chosen.to_string())), Some(spec)))
+        }
+        SeatRequest::Llm { name, provider, model, personality } => {
+            // A providers entry by name, or a vendor section
+            let ai_type = if provider.starts_with("llm:") || config.provider(provider).is_some() {
+                provider.clone()
+            } else {
+                format!("llm:{}", provider)
+            };
+            let endpoint = config.provider(&ai_type).ok_or_else(|| {
+                let mut known: Vec<_> = config.providers.iter().map(|p| p.name.as_str()).collect();
+                known.extend(["openai", "anthropic", "ollama"]);
+                format!("unknown provider \"{}\" (known: {})", provider, known.join(", "))
+            })?;
+            if let Some(personality) = personality.as_deref().filter(|p| !AI_PERSONALITIES.contains(p)) {
+                return Err(format!(
+                    "unknown personality \"{}\" (known: {})",
+                    personality,
+                    AI_PERSONALITIES.join(", ")
+                ));
+            }
+            let name = name.clone().unwrap_or_else(|| endpoint.name.clone());
+            let spec = BotSpec {
+                strategy: ai_type.clone(),
+                model: model.clone(),
+                personality: personality.clone(),
+                language: Some(config.game.ai_output_language.clone()),
+            };
+            Ok((player(&name, Some(ai_type)), Some(spec)))
+        }
+    }
+}
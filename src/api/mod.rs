@@ -1,5 +1,7 @@
 pub mod handlers;
 pub mod models;
+pub mod repository;
 pub mod routes;
 
+pub use models::Status;
 pub use routes::create_router;
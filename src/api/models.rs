@@ -1,43 +1,100 @@
 use serde::{Deserialize, Serialize};
 
-use crate::config::Config;
-use crate::game;
+pub use crate::engine::{Event, GameState, Player};
 
 #[derive(Deserialize, Serialize)]
 pub struct StatusQuery {}
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct GameState {
-    pub id: String,
-    pub players: Vec<Player>,
-    pub current_player_index: usize,
-    pub dice: Vec<u8>,
-    pub dice_count: usize,
-    pub game_over: bool,
-    pub winner: Option<String>,
-    pub turn_number: u32,
-   // pub turn_terminated: bool,
-    pub rerollable_dice: Vec<usize>,
-    pub turn_end_reason: Option<String>, // None =  in progress, Some("busted"), Some("banked"), Some("win"), etc.
-}
-
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct Player {
-    pub name: String,
-    pub score: u32,
-    pub is_human: bool,
-    pub ai_type: Option<String>,
-    pub turn_score: u32,
-    pub roll_score: u32
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Status {
+    pub status: String,
 }
 
 #[derive(Debug, Deserialize)]
 pub struct CreateGameRequest {
+    /// Language of the LLM players' explanations, "en" or "fr".
     pub language: Option<String>,
+    /// Dice seed, to replay the same game.
+    pub seed: Option<u64>,
+    /// Points to reach, the configured target when unset.
+    pub target_score: Option<u32>,
+    /// Seats in playing order, the configured players when unset.
+    pub players: Option<Vec<SeatRequest>>,
+}
+
+/// A seat of a new web game.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum SeatRequest {
+    /// A human playing from the browser that creates the game.
+    Human {
+        name: String,
+    },
+    /// A human seat left for someone joining with the invite code.
+    Open {
+        name: Option<String>,
+    },
+    /// A bot playing a registered strategy, the configured computer when unset.
+    Computer {
+        name: Option<String>,
+        strategy: Option<String>,
+    },
+    /// An LLM player: `provider` is an entry of the `providers` config or
+    /// `openai`, `anthropic` or `ollama`.
+    Llm {
+        name: Option<String>,
+        provider: String,
+        model: Option<String>,
+        personality: Option<String>,
+    },
+}
+
+/// Why a request was refused.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ErrorResponse {
+    pub success: bool,
+    /// What went wrong, for programs: `game_not_found`, `not_your_turn`...
+    pub code: String,
+    /// What went wrong, for people.
+    pub message: String,
+    /// Every problem found, e.g. one per invalid seat.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub errors: Vec<String>,
+}
+
+impl ErrorResponse {
+    pub fn new(code: impl Into<String>, message: impl Into<String>) -> Self {
+        ErrorResponse {
+            success: false,
+            code: code.into(),
+            message: message.into(),
+            errors: Vec::new(),
+        }
+    }
+}
+
+/// What `/api/game/{id}/events` streams: an event and the state of the game
+/// after the action that caused it. The first update of a stream has no
+/// event.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GameUpdate {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub event: Option<Event>,
+    pub game_state: GameState,
 }
 
 #[derive(Debug, Deserialize)]
 pub struct RollDiceRequest {
+    /// Indices of the pending roll to set aside before rolling again.
+    /// Defaults to every scoring die.
+    pub keep: Option<Vec<usize>>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct BankRequest {
+    /// Indices of the pending roll to set aside before banking.
+    /// Defaults to every scoring die.
+    pub keep: Option<Vec<usize>>,
 }
 
 #[derive(Debug, Serialize)]
@@ -59,13 +116,89 @@ pub struct StatusFullResponse {
     pub game_state: Option<GameState>,
     pub ai_decision: Option<String>,
     pub ai_explanation: Option<String>,
-    
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub message: Option<String>, // why a request was rejected
 }
 
-#[derive(Debug, Serialize)]
+#[derive(Debug, Serialize, Deserialize)]
 pub struct GameResponse {
     pub success: bool,
     pub game_state: Option<GameState>,
+    /// Code to join the open seats, for games that have some.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub invite_code: Option<String>,
+    /// Tokens of the human seats taken by the creator.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub seats: Vec<SeatToken>,
+}
+
+/// Proof of holding a seat: send the token as `Authorization: Bearer <token>`
+/// to roll, bank and pass the turn for that seat.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SeatToken {
+    pub seat: usize,
+    pub name: String,
+    pub token: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct JoinRequest {
+    pub name: String,
+    /// Open seat to take, the first one when unset.
+    pub seat: Option<usize>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct JoinResponse {
+    pub success: bool,
+    pub game_id: String,
+    pub seat: SeatToken,
+    pub game_state: GameState,
+}
+
+/// A game as seen from its invite code.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct LobbyResponse {
+    pub game_id: String,
+    /// Seats still waiting for a player; the game starts once there are none.
+    pub open_seats: Vec<usize>,
+    pub game_state: GameState,
+}
+
+/// Filters of `GET /api/games`.
+#[derive(Debug, Default, Deserialize)]
+pub struct GamesQuery {
+    pub status: Option<GameFilter>,
+    /// Games with a player of that name (case-insensitive).
+    pub player: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum GameFilter {
+    Active,
+    Finished,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct GamesResponse {
+    /// Most recently played first.
+    pub games: Vec<GameSummary>,
+}
+
+/// A game in a list of games.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GameSummary {
+    pub game_id: String,
+    pub players: Vec<String>,
+    pub current_player: String,
+    pub turn_number: u32,
+    pub game_over: bool,
+    pub winner: Option<String>,
+    /// Seats left for players joining with the invite code.
+    pub open_seats: Vec<usize>,
+    /// Last move, in seconds since the Unix epoch.
+    pub updated_at: u64,
 }
 
 #[derive(Debug, Serialize)]
@@ -83,55 +216,3 @@ pub struct PlayerScore {
     pub roll_score: u32,
     pub is_current: bool,
 }
-
-impl GameState {
-    pub fn new(id: String, has_openai: bool, has_anthropic: bool, config: &Config) -> Self {
-        let game_players = game::setup_players(has_openai, has_anthropic, config);
-
-        Self {
-            id,
-            players: game_players
-                .into_iter()
-                .map(
-                    |p| Player {
-                        name: p.name,
-                        score: p.score,
-                        is_human: p.is_human,
-                        ai_type: p.ai_type,
-                        turn_score: p.turn_score,
-                        roll_score: p.roll_score,
-                    },
-                )
-                .collect(),
-            current_player_index: 0,
-            dice: Vec::new(),
-            game_over: false,
-            turn_terminated: false,
-            winner: None,
-            turn_number: 1,
-            dice_count: 6,
-            rerollable_dice: Vec::new(),
-        }
-    }
-
-    pub fn current_player(&self) -> &Player {
-        &self.players[self.current_player_index]
-    }
-
-    pub fn current_player_mut(&mut self) -> &mut Player {
-        &mut self.players[self.current_player_index]
-    }
-
-    pub fn next_player(&mut self) {
-        self.current_player_index = (self.current_player_index + 1) % self.players.len();
-        self.turn_number += 1;
-        self.dice_count = 6;
-    }
-
-    pub fn check_winner(&mut self) {
-        if let Some(player) = self.players.iter().find(|p| p.score >= 6000) {
-            self.game_over = true;
-            self.winner = Some(player.name.clone());
-        }
-    }
-}
\ No newline at end of file
@@ -0,0 +1,255 @@
+//! Where the web games are kept between server runs.
+//!
+//! The server plays its games in memory and writes every change through to
+//! a [`GameRepository`]: in memory only, a JSON file per game, or an SQLite
+//! database, as `server.storage` says. Saved games are loaded back when the
+//! server starts.
+
+use std::collections::HashMap;
+use std::fs;
+use std::io;
+use std::path::PathBuf;
+use std::sync::Mutex;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use colored::Colorize;
+use rusqlite::{Connection, params};
+use serde::{Deserialize, Serialize};
+
+use crate::config::{ServerConfig, StorageBackend};
+use crate::dice::SeededDice;
+use crate::engine::GameState;
+use crate::record::GameRecord;
+
+/// Bumped whenever the stored format changes in an incompatible way.
+pub const STORE_VERSION: u32 = 1;
+
+/// What a web game needs to go on after a restart.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StoredGame {
+    pub version: u32,
+    pub state: GameState,
+    pub dice: SeededDice,
+    pub record: GameRecord,
+    /// How every seat's bot was set up, None for humans. Games stored
+    /// without it seat their bots again from their `ai_type`.
+    #[serde(default)]
+    pub bots: Vec<Option<BotSpec>>,
+    pub seat_tokens: Vec<Option<String>>,
+    pub invite_code: String,
+    /// Last change, in seconds since the Unix epoch.
+    pub updated_at: u64,
+}
+
+/// How a bot seat was set up.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct BotSpec {
+    /// A registered strategy, or the LLM provider (`llm:<name>`, `openai`...).
+    pub strategy: String,
+    /// Model of an LLM player, the provider's when unset.
+    pub model: Option<String>,
+    /// Personality of an LLM player, the configured one when unset.
+    pub personality: Option<String>,
+    /// Language of the bot's explanations, the configured one when unset.
+    pub language: Option<String>,
+}
+
+/// Storage of the web games, by game id.
+pub trait GameRepository: Send + Sync {
+    /// Insert or replace a game.
+    fn save(&self, game: &StoredGame) -> io::Result<()>;
+
+    fn delete(&self, id: &str) -> io::Result<()>;
+
+    /// Every stored game.
+    fn load_all(&self) -> io::Result<Vec<StoredGame>>;
+}
+
+/// Now, in seconds since the Unix epoch.
+pub fn now_secs() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|elapsed| elapsed.as_secs())
+        .unwrap_or_default()
+}
+
+/// The repository `server.storage` asks for.
+pub fn repository_from_config(config: &ServerConfig) -> io::Result<Box<dyn GameRepository>> {
+    let path = |default: &str| PathBuf::from(config.storage_path.as_deref().unwrap_or(default));
+    Ok(match config.storage {
+        StorageBackend::Memory => Box::new(MemoryRepository::default()),
+        StorageBackend::File => Box::new(FileRepository::new(path("games"))?),
+        StorageBackend::Sqlite => Box::new(SqliteRepository::open(path("games.db"))?),
+    })
+}
+
+/// Games that last as long as the server.
+#[derive(Default)]
+pub struct MemoryRepository {
+    games: Mutex<HashMap<String, StoredGame>>,
+}
+
+impl GameRepository for MemoryRepository {
+    fn save(&self, game: &StoredGame) -> io::Result<()> {
+        self.games
+            .lock()
+            .expect("memory repository poisoned")
+            .insert(game.state.id.clone(), game.clone());
+        Ok(())
+    }
+
+    fn delete(&self, id: &str) -> io::Result<()> {
+        self.games.lock().expect("memory repository poisoned").remove(id);
+        Ok(())
+    }
+
+    fn load_all(&self) -> io::Result<Vec<StoredGame>> {
+        Ok(self.games.lock().expect("memory repository poisoned").values().cloned().collect())
+    }
+}
+
+/// A `<game id>.json` file per game in a directory.
+pub struct FileRepository {
+    dir: PathBuf,
+}
+
+impl FileRepository {
+    pub fn new(dir: impl Into<PathBuf>) -> io::Result<Self> {
+        let dir = dir.into();
+        fs::create_dir_all(&dir)?;
+        Ok(FileRepository { dir })
+    }
+
+    fn path(&self, id: &str) -> io::Result<PathBuf> {
+        // Ids come from URLs: no way out of the directory
+        if id.is_empty() || !id.chars().all(|c| c.is_ascii_alphanumeric() || c == '-') {
+            return Err(io::Error::new(io::ErrorKind::InvalidInput, format!("invalid game id \"{}\"", id)));
+        }
+        Ok(self.dir.join(format!("{}.json", id)))
+    }
+}
+
+impl GameRepository for FileRepository {
+    /// Written atomically, so an interrupted write keeps the previous version.
+    fn save(&self, game: &StoredGame) -> io::Result<()> {
+        let path = self.path(&game.state.id)?;
+        let json = serde_json::to_string(game).map_err(io::Error::other)?;
+        let tmp = path.with_extension("json.tmp");
+        fs::write(&tmp, json)?;
+        fs::rename(tmp, path)
+    }
+
+    fn delete(&self, id: &str) -> io::Result<()> {
+        match fs::remove_file(self.path(id)?) {
+            Err(e) if e.kind() != io::ErrorKind::NotFound => Err(e),
+            _ => Ok(()),
+        }
+    }
+
+    /// Files that cannot be read are skipped with a warning.
+    fn load_all(&self) -> io::Result<Vec<StoredGame>> {
+        let mut games = Vec::new();
+        for entry in fs::read_dir(&self.dir)? {
+            let path = entry?.path();
+            if path.extension().is_none_or(|extension| extension != "json") {
+                continue;
+            }
+            match fs::read_to_string(&path).map_err(|e| e.to_string()).and_then(|json| decode(&json)) {
+                Ok(game) => games.push(game),
+                Err(e) => println!("{}", format!("Skipping stored game {}: {}", path.display(), e).yellow()),
+            }
+        }
+        Ok(games)
+    }
+}
+
+/// A `games` table in an SQLite database, a JSON document per game.
+pub struct SqliteRepository {
+    connection: Mutex<Connection>,
+}
+
+impl SqliteRepository {
+    pub fn open(path: impl Into<PathBuf>) -> io::Result<Self> {
+        let path = path.into();
+        if let Some(dir) = path.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+            fs::create_dir_all(dir)?;
+        }
+        Self::with_connection(Connection::open(path).map_err(io::Error::other)?)
+    }
+
+    /// A database that lasts as long as the repository.
+    pub fn in_memory() -> io::Result<Self> {
+        Self::with_connection(Connection::open_in_memory().map_err(io::Error::other)?)
+    }
+
+    fn with_connection(connection: Connection) -> io::Result<Self> {
+        connection
+            .execute_batch(
+                "CREATE TABLE IF NOT EXISTS games (
+                    id TEXT PRIMARY KEY,
+                    updated_at INTEGER NOT NULL,
+                    game_over INTEGER NOT NULL,
+                    data TEXT NOT NULL
+                )",
+            )
+            .map_err(io::Error::other)?;
+        Ok(SqliteRepository {
+            connection: Mutex::new(connection),
+        })
+    }
+}
+
+impl GameRepository for SqliteRepository {
+    fn save(&self, game: &StoredGame) -> io::Result<()> {
+        let json = serde_json::to_string(game).map_err(io::Error::other)?;
+        self.connection
+            .lock()
+            .expect("sqlite repository poisoned")
+            .execute(
+                "INSERT OR REPLACE INTO games (id, updated_at, game_over, data) VALUES (?1, ?2, ?3, ?4)",
+                params![game.state.id, game.updated_at as i64, game.state.game_over, json],
+            )
+            .map_err(io::Error::other)?;
+        Ok(())
+    }
+
+    fn delete(&self, id: &str) -> io::Result<()> {
+        self.connection
+            .lock()
+            .expect("sqlite repository poisoned")
+            .execute("DELETE FROM games WHERE id = ?1", params![id])
+            .map_err(io::Error::other)?;
+        Ok(())
+    }
+
+    /// Rows that cannot be read are skipped with a warning.
+    fn load_all(&self) -> io::Result<Vec<StoredGame>> {
+        let connection = self.connection.lock().expect("sqlite repository poisoned");
+        let mut statement = connection
+            .prepare("SELECT id, data FROM games")
+            .map_err(io::Error::other)?;
+        let rows = statement
+            .query_map([], |row| Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?)))
+            .map_err(io::Error::other)?;
+        let mut games = Vec::new();
+        for row in rows {
+            let (id, json) = row.map_err(io::Error::other)?;
+            match decode(&json) {
+                Ok(game) => games.push(game),
+                Err(e) => println!("{}", format!("Skipping stored game {}: {}", id, e).yellow()),
+            }
+        }
+        Ok(games)
+    }
+}
+
+fn decode(json: &str) -> Result<StoredGame, String> {
+    let game: StoredGame = serde_json::from_str(json).map_err(|e| e.to_string())?;
+    if game.version != STORE_VERSION {
+        return Err(format!(
+            "store version {} is not supported (expected {})",
+            game.version, STORE_VERSION
+        ));
+    }
+    Ok(game)
+}
@@ -1,26 +1,55 @@
 use crate::api::handlers::{self, GameStore};
+use crate::api::repository::{GameRepository, repository_from_config};
 use crate::config::Config;
 use axum::{
     Router,
-    routing::{post},
+    routing::{get, post},
     middleware,
     http::HeaderValue,
     Extension,
 };
 use std::sync::Arc;
-use std::collections::HashMap;
-use std::sync::Mutex;
 
 
+/// The API, its games kept where `server.storage` says.
+///
+/// Panics if that storage cannot be opened.
 pub fn create_router(config: Arc<Config>) -> Router {
-    let game_store: GameStore = Arc::new(Mutex::new(HashMap::new()));
+    let game_store = open_store(&config);
+    create_router_with_store(config, game_store)
+}
+
+pub fn create_router_with_repository(config: Arc<Config>, repository: Arc<dyn GameRepository>) -> Router {
+    let game_store = GameStore::new(repository, &config);
+    create_router_with_store(config, game_store)
+}
+
+/// The games kept where `server.storage` says.
+///
+/// Panics if that storage cannot be opened.
+pub fn open_store(config: &Config) -> GameStore {
+    let repository = repository_from_config(&config.server)
+        .unwrap_or_else(|e| panic!("cannot open the {:?} game storage: {}", config.server.storage, e));
+    GameStore::new(Arc::from(repository), config)
+}
 
+/// The API on `game_store`. Nothing drops its expired games but new ones:
+/// the server runs [`handlers::spawn_expiry`] next to it.
+pub fn create_router_with_store(config: Arc<Config>, game_store: GameStore) -> Router {
     Router::new()
+        .route("/status", get(handlers::health_handler))
         .route("/api/game", post(handlers::create_game))
+        .route("/api/games", get(handlers::list_games_handler))
+        .route("/api/game/{game_id}", get(handlers::game_handler))
+        .route("/api/game/{game_id}/scores", get(handlers::scores_handler))
         .route("/api/game/{game_id}/roll", post(handlers::roll_dice_handler))
         .route("/api/game/{game_id}/bank", post(handlers::bank_points_handler))
         .route("/api/game/{game_id}/status", post(handlers::game_status_handler))
         .route("/api/game/{game_id}/next", post(handlers::next_player_handler))
+        .route("/api/game/{game_id}/record", get(handlers::game_record_handler))
+        .route("/api/game/{game_id}/events", get(handlers::game_events_handler))
+        .route("/api/lobby/{code}", get(handlers::lobby_handler))
+        .route("/api/lobby/{code}/join", post(handlers::join_handler))
         .with_state(game_store)
         .layer(Extension(config))
         .layer(middleware::from_fn(cors_middleware))
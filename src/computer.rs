@@ -1,78 +1,167 @@
-use crate::config::Config;
-use crate::score::{calculate_score, roll_dice};
-use colored::*;
-use crate::api::models::GameState;
+use std::collections::VecDeque;
 
-const NUM_DICE: usize = 6;
-// const WINNING_SCORE: u32 = 6000;
+use serde::{Deserialize, Serialize};
+
+use crate::config::{BankLimits, Config, EndMode, GameConfig};
+use crate::dice::DiceSource;
+use crate::engine::{Action, BustReason, Event, GameState, NUM_DICE, TurnController, TurnPhase};
+use crate::monte_carlo::MonteCarloPlayer;
+use crate::optimal::{OptimalPlayer, roll_outcomes};
+use crate::rules::RuleSet;
+use crate::score::{KeepOption, validate_keep};
+use crate::strategy::Strategy;
+use crate::win_probability::WinProbabilityPlayer;
 
 #[derive(Debug, Clone, Copy)]
 pub enum AIPersonality {
     Conservative,
     Balanced,
     Aggressive,
+    /// Starts balanced and takes fewer risks when its rolls keep busting.
+    Adaptive,
 }
 
 impl AIPersonality {
-    fn min_score(&self) -> u32 {
-        match self {
-            AIPersonality::Conservative => 200,
-            AIPersonality::Balanced => 300,
-            AIPersonality::Aggressive => 400,
+    /// Personality for a `computer_strategy` name, case-insensitive.
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.to_lowercase().as_str() {
+            "conservative" => Some(AIPersonality::Conservative),
+            "balanced" => Some(AIPersonality::Balanced),
+            "aggressive" => Some(AIPersonality::Aggressive),
+            "adaptive" => Some(AIPersonality::Adaptive),
+            _ => None,
         }
     }
 
-    fn max_score(&self) -> u32 {
-        match self {
-            AIPersonality::Conservative => 600,
-            AIPersonality::Balanced => 800,
-            AIPersonality::Aggressive => 1200,
+    /// The rule-based player's parameters for this personality.
+    pub fn params(&self) -> PersonalityParams {
+        let (min_score, max_score) = match self {
+            AIPersonality::Conservative => (200, 600),
+            AIPersonality::Balanced | AIPersonality::Adaptive => (300, 800),
+            AIPersonality::Aggressive => (400, 1200),
+        };
+        PersonalityParams {
+            min_score,
+            max_score,
+            ..PersonalityParams::default()
         }
     }
 }
 
-pub fn computer_turn(current_score: u32, other_scores: &[u32], config: &Config) -> u32 {
-    let mut dice = NUM_DICE;
-    let mut turn_score = 0;
-    let mut roll_count = 1;
-    let personality = get_ai_personality(config);
+/// Thresholds of the rule-based computer player. Named sets in the
+/// `personalities` section of the config, e.g. written by `tune`, can be
+/// used as `computer_strategy`; missing keys keep the balanced values.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PersonalityParams {
+    /// With 3 dice or more, roll until the turn is worth this.
+    pub min_score: u32,
+    /// Always bank from this turn score.
+    pub max_score: u32,
+    /// Bank from this turn score with a single die left.
+    pub single_die_bank: u32,
+    /// Always roll under this turn score with 3 dice or more.
+    pub safe_score: u32,
+    /// Without a special situation, 3 dice or more roll up to
+    /// `min_score` plus this.
+    pub margin: u32,
+    /// Roll on up to `max_score` when an opponent leads by more than this.
+    pub catch_up_gap: u32,
+    /// Only roll on under `min_score` with `lead_dice` dice when leading
+    /// by more than this.
+    pub lead_gap: u32,
+    pub lead_dice: u32,
+    /// Points a die left to roll is worth when choosing a keep.
+    pub die_value: u32,
+}
 
-    loop {
-        println!("{} {}", "\n\tRoll number:".bold().green(), roll_count);
-        let roll = roll_dice(dice);
-        println!("{} {:?}", "\tComputer rolled:".bold().green(), roll);
+impl Default for PersonalityParams {
+    fn default() -> Self {
+        PersonalityParams {
+            min_score: 300,
+            max_score: 800,
+            single_die_bank: 300,
+            safe_score: 200,
+            margin: 100,
+            catch_up_gap: 1500,
+            lead_gap: 1000,
+            lead_dice: 4,
+            die_value: 50,
+        }
+    }
+}
 
-        let (score, remaining_dice, remaining_dice_values, _rerollable_dice) = calculate_score(&roll);
-        println!("{} +{}", "\tScore:".bold().cyan(), score);
+/// The rule-based computer player.
+pub struct ComputerPlayer {
+    params: PersonalityParams,
+}
 
-        if score == 0 {
-            println!("{}", "\tNo points! Computer loses the turn.\n".bold().red());
-            return 0;
-        }
+impl ComputerPlayer {
+    pub fn new(personality: AIPersonality) -> Self {
+        Self::with_params(personality.params())
+    }
 
-        turn_score += score;
-        roll_count += 1;
+    pub fn with_params(params: PersonalityParams) -> Self {
+        ComputerPlayer { params }
+    }
+}
 
-        // Relance obligatoire si tous les dés ont scoré
-        if remaining_dice == 0 {
-            println!("{}", "\tAll dice scored! Computer gets to roll again!".bold().magenta());
-            dice = NUM_DICE;
-            continue;
-        }
+/// Names of the built-in computer strategies.
+pub const COMPUTER_STRATEGIES: [&str; 7] =
+    ["conservative", "balanced", "aggressive", "adaptive", "optimal", "win_probability", "monte_carlo"];
 
-        println!("{} {} - {:?}", "\tRemaining dice:".bold().blue(), remaining_dice, remaining_dice_values);
+/// Controller for a built-in computer strategy or a `personalities` entry,
+/// case-insensitive, or None if the name is unknown. Bots are created with
+/// [`strategy::create`](crate::strategy::create), which builds them with this.
+pub(crate) fn strategy_controller(name: &str, config: &GameConfig) -> Option<Box<dyn Strategy>> {
+    match name.to_lowercase().as_str() {
+        "optimal" => Some(Box::new(OptimalPlayer::new())),
+        "win_probability" => Some(Box::new(WinProbabilityPlayer::new())),
+        "monte_carlo" => Some(Box::new(MonteCarloPlayer::from_config(config))),
+        "adaptive" => Some(Box::new(ComputerAI::new(0, &[], AIPersonality::Adaptive))),
+        lower => match AIPersonality::from_name(lower) {
+            Some(personality) => Some(Box::new(ComputerPlayer::new(personality))),
+            None => {
+                let params = config.personalities.get(name).or_else(|| config.personalities.get(lower))?;
+                Some(Box::new(ComputerPlayer::with_params(*params)))
+            }
+        },
+    }
+}
 
-        // Décision simple mais efficace
-        if should_continue(turn_score, remaining_dice, current_score, other_scores, &personality) {
-            println!("{}", "\tComputer decides to ROLL AGAIN!".bold().yellow());
-            dice = remaining_dice as usize;
-        } else {
-            println!("{}", "\tComputer decides to TAKE the points!".bold().green());
-            break;
-        }
+/// Controller for the configured computer strategy, balanced if unknown.
+pub fn controller_from_config(config: &Config) -> Box<dyn Strategy> {
+    strategy_controller(&config.game.computer_strategy, &config.game)
+        .unwrap_or_else(|| Box::new(ComputerPlayer::new(AIPersonality::Balanced)))
+}
+
+impl Strategy for ComputerPlayer {
+    fn choose_keep(&mut self, state: &GameState) -> Vec<usize> {
+        let player = state.current_player();
+        choose_keep(
+            &state.keep_options,
+            state.dice.len(),
+            player.turn_score,
+            player.score,
+            &state.other_scores(),
+            state.bank_limits(),
+            &self.params,
+        )
+        .indices
+        .clone()
     }
 
-    turn_score
+    fn should_bank(&mut self, state: &GameState) -> bool {
+        let player = state.current_player();
+        !should_continue(
+            player.turn_score,
+            state.dice_count as u32,
+            player.score,
+            &state.other_scores(),
+            state.bank_limits(),
+            &self.params,
+        )
+    }
 }
 
 fn should_continue(
@@ -80,19 +169,30 @@ fn should_continue(
     remaining_dice: u32,
     current_score: u32,
     other_scores: &[u32],
-    personality: &AIPersonality,
+    limits: BankLimits,
+    params: &PersonalityParams,
 ) -> bool {
+    // Impossible de sécuriser sous le score d'ouverture
+    if turn_score < limits.min {
+        return true;
+    }
+
+    // Arrivée exacte : sécuriser dès que la cible est atteinte
+    if limits.max.is_some_and(|max| turn_score >= max) {
+        return false;
+    }
+
     let opponent_best = other_scores.iter().max().copied().unwrap_or(0);
-    let min_score = personality.min_score();
-    let max_score = personality.max_score();
+    let min_score = params.min_score;
+    let max_score = params.max_score;
 
-    // Jamais continuer avec 1 dé et plus de 300 points
-    if remaining_dice == 1 && turn_score >= 300 {
+    // Jamais continuer avec 1 dé et assez de points
+    if remaining_dice == 1 && turn_score >= params.single_die_bank {
         return false;
     }
 
     // Toujours continuer si on a peu de points et assez de dés
-    if turn_score < 200 && remaining_dice >= 3 {
+    if turn_score < params.safe_score && remaining_dice >= 3 {
         return true;
     }
 
@@ -107,31 +207,401 @@ fn should_continue(
     }
 
     // Logique de rattrapage : plus agressif si on est en retard
-    if opponent_best > current_score + 1500 {
+    if opponent_best > current_score + params.catch_up_gap {
         return turn_score < max_score && remaining_dice >= 2;
     }
 
     // Logique de protection : plus conservateur si on mène
-    if current_score > opponent_best + 1000 {
-        return turn_score < min_score && remaining_dice >= 4;
+    if current_score > opponent_best + params.lead_gap {
+        return turn_score < min_score && remaining_dice >= params.lead_dice;
     }
 
     // Décision par défaut basée sur les dés restants
     match remaining_dice {
         1 => false,
         2 => turn_score < min_score,
-        _ => turn_score < min_score + 100,
+        _ => turn_score < min_score + params.margin,
+    }
+}
+
+/// Pick which scoring dice to set aside.
+///
+/// If keeping everything would already make the computer bank, it takes the
+/// best score. Otherwise it prefers a smaller keep that leaves more dice to
+/// roll, valuing each extra die at `die_value` points (hot dice count as six).
+/// With an exact finish, keeps that overshoot the target are avoided.
+pub fn choose_keep<'a>(
+    options: &'a [KeepOption],
+    dice_rolled: usize,
+    turn_score: u32,
+    current_score: u32,
+    other_scores: &[u32],
+    limits: BankLimits,
+    params: &PersonalityParams,
+) -> &'a KeepOption {
+    pick_keep(options, dice_rolled, turn_score, limits, params.die_value, |score, left| {
+        should_continue(score, left as u32, current_score, other_scores, limits, params)
+    })
+}
+
+/// Keep selection shared by the computer players; `keeps_rolling` tells
+/// whether the player would roll again with this turn score and dice left.
+fn pick_keep(
+    options: &[KeepOption],
+    dice_rolled: usize,
+    turn_score: u32,
+    limits: BankLimits,
+    die_value: u32,
+    keeps_rolling: impl Fn(u32, usize) -> bool,
+) -> &KeepOption {
+    let allowed: Vec<&KeepOption> = options
+        .iter()
+        .filter(|o| !limits.overshoots(turn_score + o.score))
+        .collect();
+    let Some(&best) = allowed.first() else {
+        // Every keep overshoots, the turn is lost anyway
+        return &options[0];
+    };
+    if let Some(exact) = allowed.iter().find(|o| Some(turn_score + o.score) == limits.max) {
+        return exact;
+    }
+    let best_left = dice_rolled - best.dice.len();
+    if best_left > 0 && !keeps_rolling(turn_score + best.score, best_left) {
+        return best;
     }
+
+    let dice_left = |o: &KeepOption| match dice_rolled - o.dice.len() {
+        0 => NUM_DICE as u32,
+        n => n as u32,
+    };
+    allowed
+        .into_iter()
+        .max_by_key(|o| o.score + die_value * dice_left(o))
+        .unwrap_or(best)
+}
+
+/// Stage of the game, from how close the leader is to the target.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GamePhase {
+    Early,
+    Mid,
+    Late,
+    Critical,
+}
+
+impl GamePhase {
+    fn from_progress(leader: u32, target: u32) -> Self {
+        let progress = leader as f64 / target.max(1) as f64;
+        if progress < 1.0 / 3.0 {
+            GamePhase::Early
+        } else if progress < 2.0 / 3.0 {
+            GamePhase::Mid
+        } else if progress < 0.9 {
+            GamePhase::Late
+        } else {
+            GamePhase::Critical
+        }
+    }
+}
+
+/// Busted and banked turns of a computer player.
+#[derive(Debug, Clone, Default)]
+pub struct PerformanceTracker {
+    banks: u32,
+    points_banked: u32,
+    points_lost: u32,
+    /// Busts by number of dice rolled.
+    busts: [u32; NUM_DICE],
+}
+
+impl PerformanceTracker {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// A turn lost with `turn_score` at stake, busting on a roll of `dice` dice.
+    pub fn record_bust(&mut self, turn_score: u32, dice: usize) {
+        self.points_lost += turn_score;
+        self.busts[dice.clamp(1, NUM_DICE) - 1] += 1;
+    }
+
+    pub fn record_success(&mut self, points: u32) {
+        self.banks += 1;
+        self.points_banked += points;
+    }
+
+    pub fn turns(&self) -> u32 {
+        self.banks + self.busts.iter().sum::<u32>()
+    }
+
+    /// Busts on a roll of `dice` dice.
+    pub fn busts_with(&self, dice: usize) -> u32 {
+        self.busts[dice.clamp(1, NUM_DICE) - 1]
+    }
+
+    pub fn bust_rate(&self) -> f64 {
+        match self.turns() {
+            0 => 0.0,
+            turns => self.busts.iter().sum::<u32>() as f64 / turns as f64,
+        }
+    }
+}
+
+/// Where the computer stands when it looks back at its decisions.
+pub struct DecisionContext<'a> {
+    pub remaining_dice: usize,
+    pub turn_score: u32,
+    pub total_score: u32,
+    pub roll_count: u32,
+    pub last_roll: &'a [u8],
+    pub performance: &'a PerformanceTracker,
 }
 
-fn get_ai_personality(config: &Config) -> AIPersonality {
-    match config.game.computer_strategy.to_lowercase().as_str() {
-        "conservative" => AIPersonality::Conservative,
-        "aggressive" => AIPersonality::Aggressive,
-        _ => AIPersonality::Balanced,
+/// A roll-or-bank decision.
+#[derive(Debug, Clone)]
+struct Decision {
+    rolled: bool,
+    turn_score: u32,
+    dice: usize,
+}
+
+impl Decision {
+    /// Rolling two dice or less with more at stake than such a roll earns.
+    fn is_risky(&self) -> bool {
+        self.rolled && self.dice <= 2 && self.turn_score >= ROLL_THRESHOLDS[1]
     }
 }
 
+/// Decisions the adaptive computer looks back on.
+const HISTORY_WINDOW: usize = 10;
+
+/// Target assumed by [`ComputerAI::determine_game_phase`].
+const DEFAULT_TARGET: u32 = 6000;
+
+/// Turn score from which rolling this many dice loses points on average,
+/// under the classic rules. Six dice are always worth rolling.
+const ROLL_THRESHOLDS: [u32; NUM_DICE - 1] = [300, 250, 450, 1000, 2900];
+
+/// Computer player weighing the bust odds of each roll against its risk
+/// tolerance, the game phase and the score gap.
+///
+/// With the adaptive personality the tolerance goes down after risky
+/// decisions or a high bust rate, and up again while busts stay rare.
+#[derive(Debug, Clone)]
+pub struct ComputerAI {
+    personality: AIPersonality,
+    risk_tolerance: f64,
+    score: u32,
+    opponent_scores: Vec<u32>,
+    target: u32,
+    end_mode: EndMode,
+    rules: RuleSet,
+    /// Bust probability by number of dice rolled.
+    bust_odds: [f64; NUM_DICE],
+    history: VecDeque<Decision>,
+    performance: PerformanceTracker,
+    roll_count: u32,
+    last_roll: Vec<u8>,
+}
+
+impl ComputerAI {
+    pub fn new(score: u32, opponent_scores: &[u32], personality: AIPersonality) -> Self {
+        let rules = RuleSet::default();
+        ComputerAI {
+            personality,
+            risk_tolerance: match personality {
+                AIPersonality::Conservative => 0.3,
+                AIPersonality::Balanced | AIPersonality::Adaptive => 0.5,
+                AIPersonality::Aggressive => 0.7,
+            },
+            score,
+            opponent_scores: opponent_scores.to_vec(),
+            target: DEFAULT_TARGET,
+            end_mode: EndMode::Reach,
+            bust_odds: bust_odds(&rules),
+            rules,
+            history: VecDeque::with_capacity(HISTORY_WINDOW),
+            performance: PerformanceTracker::new(),
+            roll_count: 0,
+            last_roll: Vec::new(),
+        }
+    }
+
+    pub fn risk_tolerance(&self) -> f64 {
+        self.risk_tolerance
+    }
+
+    /// Busts and banks of the turns played so far.
+    pub fn performance(&self) -> &PerformanceTracker {
+        &self.performance
+    }
+
+    /// Phase of a two-player game to the default target of 6000.
+    pub fn determine_game_phase(my_score: u32, opponent_score: u32) -> GamePhase {
+        GamePhase::from_progress(my_score.max(opponent_score), DEFAULT_TARGET)
+    }
+
+    pub fn game_phase(&self) -> GamePhase {
+        let leader = self.opponent_scores.iter().copied().fold(self.score, u32::max);
+        GamePhase::from_progress(leader, self.target)
+    }
+
+    /// Probability that a roll of `dice` dice scores nothing.
+    pub fn calculate_dice_risk(&self, dice: usize) -> f64 {
+        self.bust_odds[dice.clamp(1, NUM_DICE) - 1]
+    }
+
+    /// Record a roll-or-bank decision as if the computer had made it.
+    pub fn add_test_decision(&mut self, rolled: bool, turn_score: u32, dice: usize) {
+        self.record_decision(Decision { rolled, turn_score, dice });
+    }
+
+    pub fn decision_history_len(&self) -> usize {
+        self.history.len()
+    }
+
+    fn record_decision(&mut self, decision: Decision) {
+        if self.history.len() == HISTORY_WINDOW {
+            self.history.pop_front();
+        }
+        self.history.push_back(decision);
+    }
+
+    /// Adjust the risk tolerance from the recent decisions and the bust rate.
+    /// Only the adaptive personality changes its mind.
+    pub fn adapt_from_history(&mut self, context: &DecisionContext) {
+        if !matches!(self.personality, AIPersonality::Adaptive) {
+            return;
+        }
+        let risky = self.history.iter().filter(|d| d.is_risky()).count();
+        if self.history.len() >= HISTORY_WINDOW && risky > HISTORY_WINDOW / 2 {
+            self.risk_tolerance -= 0.1;
+        }
+        if context.performance.turns() >= 5 {
+            let bust_rate = context.performance.bust_rate();
+            if bust_rate > 0.4 {
+                self.risk_tolerance -= 0.1;
+            } else if bust_rate < 0.2 {
+                self.risk_tolerance += 0.05;
+            }
+        }
+        self.risk_tolerance = self.risk_tolerance.clamp(0.1, 0.9);
+    }
+
+    /// Risk tolerance for this point of the game: chase when an opponent
+    /// is close to winning, play safe when leading near the end.
+    fn effective_risk(&self) -> f64 {
+        let opponent_best = self.opponent_scores.iter().copied().max().unwrap_or(0);
+        let behind = opponent_best > self.score;
+        let modifier = match (self.game_phase(), behind) {
+            (GamePhase::Early | GamePhase::Mid, _) => 0.0,
+            (GamePhase::Late, true) => 0.1,
+            (GamePhase::Late, false) => -0.1,
+            (GamePhase::Critical, true) => 0.25,
+            (GamePhase::Critical, false) => -0.15,
+        };
+        (self.risk_tolerance + modifier).clamp(0.0, 1.0)
+    }
+
+    fn wants_to_roll(&self, turn_score: u32, dice: usize, limits: BankLimits) -> bool {
+        if turn_score < limits.min {
+            return true;
+        }
+        if limits.max.is_some_and(|max| turn_score >= max) {
+            return false;
+        }
+        if self.end_mode == EndMode::Reach && self.score + turn_score >= self.target {
+            return false;
+        }
+        let Some(&threshold) = ROLL_THRESHOLDS.get(dice.saturating_sub(1)) else {
+            return true;
+        };
+        // A tolerance of 0.5 rolls exactly while it pays off on average
+        (turn_score as f64) < threshold as f64 * (0.5 + self.effective_risk())
+    }
+
+    fn sync(&mut self, state: &GameState) {
+        self.score = state.current_player().score;
+        self.opponent_scores = state.other_scores();
+        self.target = state.target_score;
+        self.end_mode = state.end_mode;
+        if self.rules != state.rules {
+            self.rules = state.rules.clone();
+            self.bust_odds = bust_odds(&self.rules);
+        }
+    }
+
+    fn end_turn(&mut self) {
+        let (performance, last_roll) = (self.performance.clone(), self.last_roll.clone());
+        let context = DecisionContext {
+            remaining_dice: last_roll.len(),
+            turn_score: 0,
+            total_score: self.score,
+            roll_count: self.roll_count,
+            last_roll: &last_roll,
+            performance: &performance,
+        };
+        self.adapt_from_history(&context);
+        self.roll_count = 0;
+    }
+}
+
+impl Strategy for ComputerAI {
+    fn choose_keep(&mut self, state: &GameState) -> Vec<usize> {
+        self.sync(state);
+        let limits = state.bank_limits();
+        pick_keep(
+            &state.keep_options,
+            state.dice.len(),
+            state.current_player().turn_score,
+            limits,
+            PersonalityParams::default().die_value,
+            |score, left| self.wants_to_roll(score, left, limits),
+        )
+        .indices
+        .clone()
+    }
+
+    fn should_bank(&mut self, state: &GameState) -> bool {
+        self.sync(state);
+        let turn_score = state.current_player().turn_score;
+        let rolled = self.wants_to_roll(turn_score, state.dice_count, state.bank_limits());
+        self.record_decision(Decision { rolled, turn_score, dice: state.dice_count });
+        !rolled
+    }
+
+    fn observe(&mut self, _state: &GameState, event: &Event) {
+        match event {
+            Event::Rolled { dice, .. } => {
+                self.roll_count += 1;
+                self.last_roll = dice.clone();
+            }
+            Event::Busted { lost, .. } => {
+                self.performance.record_bust(*lost, self.last_roll.len());
+                self.end_turn();
+            }
+            Event::Banked { points, .. } => {
+                self.performance.record_success(*points);
+                self.end_turn();
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Bust probability of a roll by number of dice.
+fn bust_odds(rules: &RuleSet) -> [f64; NUM_DICE] {
+    let mut odds = [0.0; NUM_DICE];
+    for (dice, odds) in odds.iter_mut().enumerate() {
+        *odds = roll_outcomes(dice + 1, rules)
+            .iter()
+            .filter(|outcome| outcome.options.is_empty())
+            .map(|outcome| outcome.probability)
+            .sum();
+    }
+    odds
+}
+
 // Version pour l'API web
 pub struct ComputerTurnResult {
     pub turn_score: u32,
@@ -139,81 +609,139 @@ pub struct ComputerTurnResult {
     pub rolls: Vec<Vec<u8>>,
     pub ai_decision: Option<String>,
     pub ai_explanation: Option<String>,
+    /// Engine events of this step, ending with the explanation.
+    pub events: Vec<Event>,
 }
 
-pub fn computer_turn_stateful(game_state: &mut GameState, config: &Config) -> ComputerTurnResult {
-    let dice = if game_state.dice_count == 0 { NUM_DICE } else { game_state.dice_count };
-    let current_score = game_state.current_player().score;
-    let other_scores: Vec<u32> = game_state.players.iter().enumerate()
-        .filter(|(i, _)| *i != game_state.current_player_index)
-        .map(|(_, p)| p.score)
-        .collect();
-    let personality = get_ai_personality(config);
-    let mut rolls = Vec::new();
+/// Play one roll of the computer's turn: roll, keep, then roll again or bank.
+/// The controller observes every event, so it can be kept across steps.
+pub fn computer_step(
+    game_state: &mut GameState,
+    computer: &mut dyn TurnController,
+    dice: &mut dyn DiceSource,
+) -> ComputerTurnResult {
+    let mut events = Vec::new();
 
     // Si aucun dé n'a été lancé ce tour-ci, on lance
-    let roll = roll_dice(dice);
-    rolls.push(roll.clone());
-    let (score, remaining_dice, _remaining_dice_values, _rerollable_dice) = calculate_score(&roll);
-
-    if score == 0 {
-        // Busted
-        game_state.current_player_mut().turn_score = 0;
-        game_state.current_player_mut().roll_score = 0;
-        game_state.dice = roll;
-        game_state.turn_terminated = true;
-        return ComputerTurnResult {
-            turn_score: 0,
-            busted: true,
-            rolls,
-            ai_decision: Some("BUSTED".to_string()),
-            ai_explanation: Some("L'IA a fait 0 point, tour terminé.".to_string()),
-        };
+    if game_state.phase() == TurnPhase::AwaitingRoll {
+        events.extend(game_state.apply(Action::Roll, dice).unwrap_or_default());
     }
 
-    // Mise à jour du score
-    let turn_score = game_state.current_player().turn_score + score;
-    game_state.dice = roll.clone();
-    game_state.current_player_mut().roll_score = score;
-    game_state.current_player_mut().turn_score = turn_score;
-
-    // Relance obligatoire si tous les dés sont scorants
-    if remaining_dice == 0 {
-        game_state.dice_count = NUM_DICE;
-        // On ne termine pas le tour, l'IA doit relancer au prochain appel
-        return ComputerTurnResult {
-            turn_score,
-            busted: false,
-            rolls,
-            ai_decision: Some("RELANCE_OBLIGATOIRE".to_string()),
-            ai_explanation: Some("Tous les dés sont scorants, relance obligatoire.".to_string()),
-        };
+    // Choix des dés à garder
+    let mut kept = Vec::new();
+    let mut reasoning = None;
+    if game_state.phase() == TurnPhase::AwaitingKeep {
+        let mut keep = computer.choose_keep(game_state);
+        reasoning = computer.explain();
+        // Bots from elsewhere may answer an illegal keep: play the best option
+        if let Err(e) = validate_keep(&game_state.dice, &keep, &game_state.rules) {
+            events.push(Event::KeepRejected {
+                player: game_state.current_player_index,
+                reason: e.to_string(),
+            });
+            keep = game_state.default_keep();
+        }
+        let roll = game_state.dice.clone();
+        if let Ok(applied) = game_state.apply(Action::Keep(keep.clone()), dice) {
+            kept = keep.iter().map(|&i| roll[i]).collect();
+            events.extend(applied);
+        }
     }
 
     // Décision IA : continuer ou sécuriser
-    if should_continue(turn_score, remaining_dice, current_score, &other_scores, &personality) {
-        // L'IA décide de relancer
-        game_state.dice_count = remaining_dice as usize;
-        // On ne termine pas le tour, l'IA doit relancer au prochain appel
-        return ComputerTurnResult {
-            turn_score,
-            busted: false,
-            rolls,
-            ai_decision: Some("R".to_string()),
-            ai_explanation: Some("L'IA décide de relancer.".to_string()),
-        };
-    } else {
-        // L'IA décide de sécuriser
-        game_state.current_player_mut().score += turn_score;
-        game_state.current_player_mut().turn_score = 0;
-        game_state.current_player_mut().roll_score = 0;
-        game_state.turn_terminated = true;
-        return ComputerTurnResult {
-            turn_score,
-            busted: false,
-            rolls,
-            ai_decision: Some("T".to_string()),
-            ai_explanation: Some(format!("L'IA sécurise {} points.", turn_score)),
-        };
+    if game_state.phase() == TurnPhase::AwaitingRoll
+        && !game_state.must_roll()
+        && !computer.roll_again(game_state)
+    {
+        // Un refus (score d'ouverture) oblige à relancer
+        if let Ok(bank_events) = game_state.apply(Action::Bank, dice) {
+            events.extend(bank_events);
+        }
+    }
+
+    for event in &events {
+        computer.observe(game_state, event);
     }
-}
\ No newline at end of file
+
+    let turn_score = game_state.current_player().turn_score;
+    let limits = game_state.bank_limits();
+    let (decision, explanation, busted) = events
+        .iter()
+        .rev()
+        .find_map(|event| match event {
+            Event::Busted { reason: BustReason::NoScore, .. } => Some((
+                "BUSTED",
+                "L'IA a fait 0 point, tour terminé.".to_string(),
+                true,
+            )),
+            Event::Busted { reason: BustReason::Overshoot, .. } => Some((
+                "BUSTED",
+                "L'IA a dépassé la cible, tour perdu.".to_string(),
+                true,
+            )),
+            Event::Busted { reason: BustReason::BelowOpening, .. } => Some((
+                "BUSTED",
+                format!("Tour terminé sous le score d'ouverture ({} points requis).", limits.min),
+                true,
+            )),
+            Event::Banked { points, .. } if events.contains(&Event::TurnEndingCombination {
+                player: game_state.current_player_index,
+            }) => Some((
+                "T",
+                format!("Combinaison qui termine le tour : l'IA sécurise {} points.", points),
+                false,
+            )),
+            Event::Banked { points, .. } => Some(("T", format!("L'IA sécurise {} points.", points), false)),
+            // Relance obligatoire si tous les dés sont scorants
+            Event::HotDice { .. } => Some((
+                "RELANCE_OBLIGATOIRE",
+                "Tous les dés sont scorants, relance obligatoire.".to_string(),
+                false,
+            )),
+            _ => None,
+        })
+        .unwrap_or_else(|| {
+            // L'IA décide de relancer, on ne termine pas le tour
+            (
+                "R",
+                format!("L'IA garde {:?} et relance {} dés.", kept, game_state.dice_count),
+                false,
+            )
+        });
+
+    let turn_score = events
+        .iter()
+        .find_map(|event| match event {
+            Event::Banked { points, .. } => Some(*points),
+            _ => None,
+        })
+        .unwrap_or(turn_score);
+
+    let rolls = events
+        .iter()
+        .filter_map(|event| match event {
+            Event::Rolled { dice, .. } => Some(dice.clone()),
+            _ => None,
+        })
+        .collect();
+
+    // Bots that explain themselves (LLM players) are quoted
+    let (decision, explanation) = match reasoning {
+        Some((decision, reasoning)) if !busted => (decision, reasoning),
+        _ => (decision.to_string(), explanation),
+    };
+    events.push(Event::Explanation {
+        player: game_state.current_player_index,
+        decision: decision.clone(),
+        explanation: explanation.clone(),
+    });
+
+    ComputerTurnResult {
+        turn_score,
+        busted,
+        rolls,
+        ai_decision: Some(decision),
+        ai_explanation: Some(explanation),
+        events,
+    }
+}
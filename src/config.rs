@@ -1,6 +1,10 @@
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 use std::fs;
 
+use crate::computer::PersonalityParams;
+use crate::rules::RuleSet;
+
 #[derive(Debug, Deserialize, Serialize, Clone)]
 pub struct Config {
     pub server: ServerConfig,
@@ -8,6 +12,10 @@ pub struct Config {
     pub openai: OpenAIConfig,
     pub anthropic: AnthropicConfig,
     pub ollama: OllamaConfig,
+    /// LLM players, one per entry. When empty, the `openai`, `anthropic`
+    /// and `ollama` sections give one player per vendor.
+    #[serde(default)]
+    pub providers: Vec<ProviderConfig>,
 }
 
 #[derive(Debug, Deserialize, Serialize, Clone)]
@@ -17,24 +25,209 @@ pub struct GameConfig {
     pub computer_strategy: String,
     pub ai_output_language: String,
     pub ai_personality: String,
+    #[serde(default)]
+    pub rules: RuleSet,
+    /// Minimum turn score for a player's first bank ("getting on the board").
+    #[serde(default)]
+    pub opening_score: Option<u32>,
+    #[serde(default = "default_target_score")]
+    pub target_score: u32,
+    #[serde(default)]
+    pub end_mode: EndMode,
+    /// Points taken off after too many busts in a row.
+    #[serde(default)]
+    pub bust_penalty: Option<BustPenalty>,
+    /// Dice seed for reproducible games; random when unset.
+    #[serde(default)]
+    pub seed: Option<u64>,
+    /// Directory where CLI games are recorded; no records when unset.
+    #[serde(default)]
+    pub record_dir: Option<String>,
+    /// Directory where CLI games are saved after every turn; no saves when unset.
+    #[serde(default)]
+    pub save_dir: Option<String>,
+    /// Seats in playing order; the human, the computer and the enabled LLMs
+    /// when empty.
+    #[serde(default)]
+    pub players: Vec<PlayerConfig>,
+    /// Work the `monte_carlo` strategy puts into each decision.
+    #[serde(default)]
+    pub rollout_budget: RolloutBudget,
+    /// Named parameter sets of the rule-based computer, usable as
+    /// `computer_strategy` (see the `tune` command).
+    #[serde(default)]
+    pub personalities: BTreeMap<String, PersonalityParams>,
+    /// Times an LLM player is asked to correct an answer that is not a
+    /// valid decision.
+    #[serde(default = "default_llm_retries")]
+    pub llm_retries: u32,
+    /// Strategy deciding for an LLM player that gives no valid answer.
+    #[serde(default = "default_llm_fallback")]
+    pub llm_fallback: String,
+}
+
+/// A seat of the game.
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq, Eq)]
+pub struct PlayerConfig {
+    pub name: String,
+    /// Name of the strategy playing this seat (see [`crate::strategy`]);
+    /// a human at the terminal when unset.
+    #[serde(default)]
+    pub strategy: Option<String>,
+}
+
+/// Time or rollouts the Monte Carlo player spends on a decision.
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum RolloutBudget {
+    /// Milliseconds per decision.
+    Millis(u64),
+    /// Rollouts per decision, shared by the candidate actions.
+    Rollouts(u32),
+}
+
+impl Default for RolloutBudget {
+    fn default() -> Self {
+        RolloutBudget::Rollouts(2000)
+    }
+}
+
+/// Penalty for busting several turns in a row.
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, PartialEq, Eq)]
+pub struct BustPenalty {
+    /// Consecutive busts that trigger the penalty.
+    #[serde(default = "default_bust_streak")]
+    pub streak: u32,
+    /// Points deducted from the player's total (never below 0).
+    pub points: u32,
+}
+
+fn default_bust_streak() -> u32 {
+    3
+}
+
+fn default_target_score() -> u32 {
+    6000
+}
+
+fn default_llm_retries() -> u32 {
+    2
+}
+
+fn default_llm_fallback() -> String {
+    "balanced".to_string()
+}
+
+/// How a game ends once someone reaches the target score.
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum EndMode {
+    /// The first player to reach or pass the target wins.
+    #[default]
+    Reach,
+    /// The target must be hit exactly; overshooting busts the turn.
+    Exact,
+    /// Passing the target gives every other player one last turn.
+    FinalRound,
+}
+
+/// Bounds on what the current player may bank this turn.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct BankLimits {
+    /// Banks below this are refused (opening score).
+    pub min: u32,
+    /// Turn totals above this bust the turn (exact finish).
+    pub max: Option<u32>,
+}
+
+impl BankLimits {
+    pub fn new(min: u32, score: u32, target_score: u32, end_mode: EndMode) -> Self {
+        let max = match end_mode {
+            EndMode::Exact => Some(target_score.saturating_sub(score)),
+            _ => None,
+        };
+        BankLimits { min, max }
+    }
+
+    /// True when this turn total overshoots an exact finish.
+    pub fn overshoots(&self, turn_score: u32) -> bool {
+        self.max.is_some_and(|max| turn_score > max)
+    }
+
+    /// Check a bank attempt against the opening rule.
+    pub fn check(&self, turn_score: u32) -> Result<(), String> {
+        if turn_score < self.min {
+            return Err(format!(
+                "You need at least {} points in a single turn to get on the board ({} so far)",
+                self.min, turn_score
+            ));
+        }
+        Ok(())
+    }
 }
 
 #[derive(Debug, Deserialize, Serialize, Clone)]
 pub struct ServerConfig {
     pub host: String,
     pub port: u16,
+    /// Pause before each step of a bot's turn in web games, so players can
+    /// follow it.
+    #[serde(default = "default_bot_delay_ms")]
+    pub bot_delay_ms: u64,
+    /// Where web games are kept.
+    #[serde(default)]
+    pub storage: StorageBackend,
+    /// Directory of the `file` storage (`games` by default) or database of
+    /// the `sqlite` one (`games.db` by default).
+    #[serde(default)]
+    pub storage_path: Option<String>,
+    /// Games nobody played for this long are dropped.
+    #[serde(default = "default_idle_ttl_secs")]
+    pub idle_ttl_secs: u64,
+    /// Finished games are dropped this long after their last move.
+    #[serde(default = "default_finished_ttl_secs")]
+    pub finished_ttl_secs: u64,
+}
+
+fn default_bot_delay_ms() -> u64 {
+    800
+}
+
+fn default_idle_ttl_secs() -> u64 {
+    24 * 60 * 60
+}
+
+fn default_finished_ttl_secs() -> u64 {
+    60 * 60
+}
+
+/// Storage of the web games.
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum StorageBackend {
+    /// Lost when the server stops.
+    #[default]
+    Memory,
+    /// A JSON file per game.
+    File,
+    /// An SQLite database.
+    Sqlite,
 }
 
 #[derive(Debug, Deserialize, Serialize, Clone)]
 pub struct OpenAIConfig {
     pub url: String,
     pub model: String,
+    #[serde(default)]
+    pub timeout: Option<u64>, // Optional timeout in seconds
 }
 
 #[derive(Debug, Deserialize, Serialize, Clone)]
 pub struct AnthropicConfig {
     pub url: String,
     pub model: String,
+    #[serde(default)]
+    pub timeout: Option<u64>, // Optional timeout in seconds
 }
 
 #[derive(Debug, Deserialize, Serialize, Clone)]
@@ -45,7 +238,90 @@ pub struct OllamaConfig {
     pub timeout: Option<u64>, // Optional timeout in seconds
 }
 
+/// API spoken by an LLM provider.
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ProviderKind {
+    Openai,
+    Anthropic,
+    Ollama,
+    /// Any server with OpenAI's chat completions API (vLLM, LM Studio...).
+    OpenaiCompatible,
+}
+
+/// An LLM player and the endpoint it is asked through.
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq, Eq)]
+pub struct ProviderConfig {
+    /// Player name; `llm:<name>` seats it in the `players` list.
+    pub name: String,
+    pub kind: ProviderKind,
+    /// Chat endpoint, the vendor's default when unset.
+    #[serde(default)]
+    pub url: Option<String>,
+    pub model: String,
+    /// Environment variable holding the API key, `OPENAI_API_KEY` or
+    /// `ANTHROPIC_API_KEY` by default for those vendors.
+    #[serde(default)]
+    pub api_key_env: Option<String>,
+    /// Request timeout in seconds.
+    #[serde(default)]
+    pub timeout: Option<u64>,
+    /// Times a request that timed out, could not connect or got a 429 or
+    /// 5xx status is sent again, waiting twice as long before each retry.
+    #[serde(default)]
+    pub retries: Option<u32>,
+}
+
+impl ProviderConfig {
+    pub fn url(&self) -> &str {
+        if let Some(url) = &self.url {
+            return url;
+        }
+        match self.kind {
+            ProviderKind::Openai => "https://api.openai.com/v1/chat/completions",
+            ProviderKind::Anthropic => "https://api.anthropic.com/v1/messages",
+            ProviderKind::Ollama => "http://localhost:11434/api/chat",
+            ProviderKind::OpenaiCompatible => "http://localhost:8000/v1/chat/completions",
+        }
+    }
+
+    pub fn api_key_env(&self) -> Option<&str> {
+        if let Some(var) = &self.api_key_env {
+            return Some(var);
+        }
+        match self.kind {
+            ProviderKind::Openai => Some("OPENAI_API_KEY"),
+            ProviderKind::Anthropic => Some("ANTHROPIC_API_KEY"),
+            ProviderKind::Ollama | ProviderKind::OpenaiCompatible => None,
+        }
+    }
+}
+
 impl Config {
+    /// Provider of the LLM player seated as `strategy`: `llm:<name>` for an
+    /// entry of `providers`, or `openai`, `anthropic` or `ollama` for the
+    /// vendor sections.
+    pub fn provider(&self, strategy: &str) -> Option<ProviderConfig> {
+        if let Some(name) = strategy.strip_prefix("llm:") {
+            return self.providers.iter().find(|p| p.name == name).cloned();
+        }
+        let (name, kind, url, model, timeout) = match strategy {
+            "openai" => ("AI (OpenAI)", ProviderKind::Openai, &self.openai.url, &self.openai.model, self.openai.timeout),
+            "anthropic" => ("AI (Claude)", ProviderKind::Anthropic, &self.anthropic.url, &self.anthropic.model, self.anthropic.timeout),
+            "ollama" => ("AI (Ollama)", ProviderKind::Ollama, &self.ollama.url, &self.ollama.model, self.ollama.timeout),
+            _ => return None,
+        };
+        Some(ProviderConfig {
+            name: name.to_string(),
+            kind,
+            url: Some(url.clone()),
+            model: model.clone(),
+            api_key_env: None,
+            timeout,
+            retries: None,
+        })
+    }
+
     pub fn load(path: &str) -> Result<Self, Box<dyn std::error::Error>> {
         let contents = fs::read_to_string(path)?;
         let config: Config = serde_yaml::from_str(&contents)?;
@@ -57,21 +333,41 @@ impl Config {
             server: ServerConfig {
                 host: "0.0.0.0".to_string(),
                 port: 3000,
+                bot_delay_ms: default_bot_delay_ms(),
+                storage: StorageBackend::Memory,
+                storage_path: None,
+                idle_ttl_secs: default_idle_ttl_secs(),
+                finished_ttl_secs: default_finished_ttl_secs(),
             },
             game: GameConfig {
                 human_player_name: "Human".to_string(),
                 computer_player_name: "Computer".to_string(),
-                computer_strategy: "adaptative".to_string(),
+                computer_strategy: "adaptive".to_string(),
                 ai_output_language: "en".to_string(),
                 ai_personality: "default".to_string(),
+                rules: RuleSet::default(),
+                opening_score: None,
+                target_score: default_target_score(),
+                end_mode: EndMode::Reach,
+                bust_penalty: None,
+                seed: None,
+                record_dir: None,
+                save_dir: None,
+                players: Vec::new(),
+                rollout_budget: RolloutBudget::default(),
+                personalities: BTreeMap::new(),
+                llm_retries: default_llm_retries(),
+                llm_fallback: default_llm_fallback(),
             },
             openai: OpenAIConfig {
                 url: "https://api.openai.com/v1/chat/completions".to_string(),
                 model: "gpt-4".to_string(),
+                timeout: None,
             },
             anthropic: AnthropicConfig {
                 url: "https://api.anthropic.com/v1/messages".to_string(),
                 model: "claude-sonnet-4-20250514".to_string(),
+                timeout: None,
             },
             ollama: OllamaConfig {
                 enabled: false,
@@ -79,6 +375,7 @@ impl Config {
                 model: "llama3.1:8b".to_string(),
                 timeout: Some(120), // Optional timeout in seconds
             },
+            providers: Vec::new(),
         }
     }
 }
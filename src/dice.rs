@@ -0,0 +1,80 @@
+//! Where rolls come from.
+//!
+//! The engine asks a [`DiceSource`] for every roll. Games use [`SeededDice`],
+//! so a seed reproduces the whole game; tests use [`ScriptedDice`] to play
+//! a fixed list of rolls.
+
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+
+pub trait DiceSource {
+    /// Roll `n` six-sided dice.
+    fn roll(&mut self, n: usize) -> Vec<u8>;
+}
+
+impl<F: FnMut(usize) -> Vec<u8>> DiceSource for F {
+    fn roll(&mut self, n: usize) -> Vec<u8> {
+        self(n)
+    }
+}
+
+/// Pseudo-random dice from a seed. Serializable, so a saved game resumes
+/// with the same upcoming rolls.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SeededDice {
+    seed: u64,
+    rng: ChaCha8Rng,
+}
+
+impl SeededDice {
+    pub fn new(seed: u64) -> Self {
+        SeededDice {
+            seed,
+            rng: ChaCha8Rng::seed_from_u64(seed),
+        }
+    }
+
+    /// Use `seed` when given, a random one otherwise.
+    pub fn from_seed(seed: Option<u64>) -> Self {
+        Self::new(seed.unwrap_or_else(|| rand::thread_rng().r#gen()))
+    }
+
+    /// The seed this source started from.
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+}
+
+impl DiceSource for SeededDice {
+    fn roll(&mut self, n: usize) -> Vec<u8> {
+        (0..n).map(|_| self.rng.gen_range(1..=6)).collect()
+    }
+}
+
+/// Hands out a fixed list of rolls in order, whatever the number of dice
+/// asked for. Once exhausted it returns empty rolls, which the engine rejects.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ScriptedDice {
+    rolls: VecDeque<Vec<u8>>,
+}
+
+impl ScriptedDice {
+    pub fn new(rolls: Vec<Vec<u8>>) -> Self {
+        ScriptedDice {
+            rolls: rolls.into(),
+        }
+    }
+
+    /// Rolls not handed out yet.
+    pub fn remaining(&self) -> usize {
+        self.rolls.len()
+    }
+}
+
+impl DiceSource for ScriptedDice {
+    fn roll(&mut self, _n: usize) -> Vec<u8> {
+        self.rolls.pop_front().unwrap_or_default()
+    }
+}
@@ -0,0 +1,581 @@
+//! Game engine: the whole turn logic of 6000 as a state machine.
+//!
+//! The engine never prints nor reads input. Front ends (CLI, web API, bots)
+//! apply [`Action`]s to a [`GameState`] and render the [`Event`]s it returns.
+//! Dice come from the caller's [`DiceSource`], so a game is fully determined
+//! by its rolls and the players' decisions.
+
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+use crate::config::{BankLimits, BustPenalty, Config, EndMode, GameConfig};
+use crate::dice::DiceSource;
+use crate::game;
+use crate::rules::RuleSet;
+use crate::score::{KeepError, KeepOption, keep_options, validate_keep};
+
+pub const NUM_DICE: usize = 6;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GameState {
+    pub id: String,
+    pub players: Vec<Player>,
+    pub current_player_index: usize,
+    pub dice: Vec<u8>,
+    pub dice_count: usize,
+    pub game_over: bool,
+    /// Name of the winner, for display; seats may share a name.
+    pub winner: Option<String>,
+    /// Seat of the winner.
+    #[serde(default)]
+    pub winner_index: Option<usize>,
+    pub turn_number: u32,
+    pub turn_terminated: bool,
+    pub rerollable_dice: Vec<usize>,
+    pub keep_options: Vec<KeepOption>, // legal keeps for the pending roll, empty once resolved
+    pub turn_end_reason: Option<String>, // None =  in progress, Some("busted"), Some("banked"), Some("win"), etc.
+    pub target_score: u32,
+    pub end_mode: EndMode,
+    pub final_round_trigger: Option<usize>, // player who passed the target in final-round mode
+    pub bust_penalty: Option<BustPenalty>,
+    #[serde(default)]
+    pub rules: RuleSet,
+    #[serde(default)]
+    pub opening_score: Option<u32>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Player {
+    pub name: String,
+    pub score: u32,
+    pub is_human: bool,
+    pub ai_type: Option<String>,
+    pub turn_score: u32,
+    pub roll_score: u32,
+    #[serde(default)]
+    pub opened: bool, // has banked at least once (opening rule)
+    #[serde(default)]
+    pub bust_streak: u32, // consecutive busted turns
+}
+
+impl Player {
+    /// Update the bust streak after a turn. Returns the points deducted when
+    /// the streak reaches the penalty threshold (the streak then restarts).
+    pub fn record_turn_result(&mut self, busted: bool, penalty: Option<BustPenalty>) -> Option<u32> {
+        if !busted {
+            self.bust_streak = 0;
+            return None;
+        }
+
+        self.bust_streak += 1;
+        let penalty = penalty.filter(|p| p.streak > 0 && self.bust_streak >= p.streak)?;
+        let deducted = penalty.points.min(self.score);
+        self.score -= deducted;
+        self.bust_streak = 0;
+        Some(deducted)
+    }
+}
+
+/// Where the current turn stands, i.e. which actions are legal.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TurnPhase {
+    /// Roll the dice, or bank once something has been kept.
+    AwaitingRoll,
+    /// A roll is waiting for the player to set dice aside.
+    AwaitingKeep,
+    /// The turn is over, move on with `NextPlayer`.
+    TurnOver,
+    GameOver,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type", content = "keep", rename_all = "snake_case")]
+pub enum Action {
+    Roll,
+    /// Indices (0-based) of the pending roll to set aside.
+    Keep(Vec<usize>),
+    Bank,
+    NextPlayer,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BustReason {
+    /// The roll scored nothing.
+    NoScore,
+    /// The turn total went past an exact finish.
+    Overshoot,
+    /// A turn-ending combination left the player below the opening score.
+    BelowOpening,
+}
+
+/// What happened while applying an action. `player` is an index into `players`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Event {
+    Rolled { player: usize, dice: Vec<u8> },
+    Kept { player: usize, dice: Vec<u8>, score: u32, turn_score: u32, dice_left: usize },
+    /// Every die scored: all six must be rolled again.
+    HotDice { player: usize },
+    /// A combination that ends the turn was kept.
+    TurnEndingCombination { player: usize },
+    Busted { player: usize, reason: BustReason, lost: u32 },
+    BustPenalty { player: usize, points: u32 },
+    Banked { player: usize, points: u32, total: u32 },
+    /// Emitted by [`play_turn`] when a bank was refused and the player has to keep rolling.
+    BankRejected { player: usize, reason: String },
+    /// A bot chose an illegal keep; the first keep option is played instead.
+    KeepRejected { player: usize, reason: String },
+    /// Reasoning a bot gave for its decision (see [`TurnController::explain`]).
+    Explanation { player: usize, decision: String, explanation: String },
+    FinalRound { player: usize },
+    TurnStarted { player: usize, turn_number: u32 },
+    GameOver { winner: usize, score: u32 },
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum EngineError {
+    GameOver,
+    TurnOver,
+    /// The pending roll needs a keep first.
+    MustKeep,
+    /// There is no pending roll to keep from.
+    NothingToKeep,
+    /// Hot dice: the six dice have to be rolled again.
+    MustRoll,
+    NothingToBank,
+    TurnInProgress,
+    InvalidRoll(Vec<u8>),
+    InvalidKeep(KeepError),
+    /// The bank is below the opening score.
+    BankRejected(String),
+}
+
+impl fmt::Display for EngineError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            EngineError::GameOver => write!(f, "the game is over"),
+            EngineError::TurnOver => write!(f, "the turn is over"),
+            EngineError::MustKeep => write!(f, "choose which dice to keep first"),
+            EngineError::NothingToKeep => write!(f, "there is no roll to keep dice from"),
+            EngineError::MustRoll => write!(f, "all dice scored, roll them again"),
+            EngineError::NothingToBank => write!(f, "nothing to bank yet"),
+            EngineError::TurnInProgress => write!(f, "the current turn is not over"),
+            EngineError::InvalidRoll(dice) => write!(f, "invalid roll {:?}", dice),
+            EngineError::InvalidKeep(e) => e.fmt(f),
+            EngineError::BankRejected(reason) => write!(f, "{}", reason),
+        }
+    }
+}
+
+impl std::error::Error for EngineError {}
+
+impl From<KeepError> for EngineError {
+    fn from(e: KeepError) -> Self {
+        EngineError::InvalidKeep(e)
+    }
+}
+
+/// Decisions a player makes during a turn, driven by [`play_turn`].
+pub trait TurnController {
+    /// Indices of the pending roll (`state.dice`) to set aside.
+    fn choose_keep(&mut self, state: &GameState) -> Vec<usize>;
+
+    /// After a keep: true to roll the remaining dice, false to bank.
+    fn roll_again(&mut self, state: &GameState) -> bool;
+
+    /// Decision and reasoning behind the last keep, for players that give one.
+    fn explain(&mut self) -> Option<(String, String)> {
+        None
+    }
+
+    /// Called with every event of the turn, after the front end rendered it.
+    fn observe(&mut self, _state: &GameState, _event: &Event) {}
+}
+
+/// Play the current player's turn until it is over.
+///
+/// A refused bank is reported as [`Event::BankRejected`] and the player
+/// rolls again. An illegal keep is reported as [`Event::KeepRejected`] and
+/// the first keep option is played instead.
+pub fn play_turn(
+    state: &mut GameState,
+    controller: &mut dyn TurnController,
+    dice: &mut dyn DiceSource,
+    on_event: &mut dyn FnMut(&GameState, &Event),
+) -> Result<(), EngineError> {
+    let mut force_roll = false;
+    loop {
+        let mut events = Vec::new();
+        let action = match state.phase() {
+            TurnPhase::TurnOver | TurnPhase::GameOver => return Ok(()),
+            TurnPhase::AwaitingKeep => {
+                let keep = controller.choose_keep(state);
+                if let Some((decision, explanation)) = controller.explain() {
+                    events.push(Event::Explanation {
+                        player: state.current_player_index,
+                        decision,
+                        explanation,
+                    });
+                }
+                Action::Keep(keep)
+            }
+            TurnPhase::AwaitingRoll => {
+                if force_roll
+                    || state.current_player().turn_score == 0
+                    || state.must_roll()
+                    || controller.roll_again(state)
+                {
+                    Action::Roll
+                } else {
+                    Action::Bank
+                }
+            }
+        };
+        force_roll = false;
+
+        match state.apply(action, dice) {
+            Ok(applied) => events.extend(applied),
+            Err(EngineError::InvalidKeep(e)) => {
+                events.push(Event::KeepRejected {
+                    player: state.current_player_index,
+                    reason: e.to_string(),
+                });
+                let keep = state.default_keep();
+                events.extend(state.apply(Action::Keep(keep), dice)?);
+            }
+            Err(EngineError::BankRejected(reason)) => {
+                force_roll = true;
+                events.push(Event::BankRejected {
+                    player: state.current_player_index,
+                    reason,
+                });
+            }
+            Err(e) => return Err(e),
+        }
+        for event in &events {
+            on_event(state, event);
+            controller.observe(state, event);
+        }
+    }
+}
+
+impl GameState {
+    pub fn new(id: String, has_openai: bool, has_anthropic: bool, config: &Config) -> Self {
+        Self::with_players(id, game::setup_players(has_openai, has_anthropic, config), &config.game)
+    }
+
+    pub fn with_players(id: String, players: Vec<Player>, config: &GameConfig) -> Self {
+        Self {
+            id,
+            players,
+            current_player_index: 0,
+            dice: Vec::new(),
+            game_over: false,
+            turn_terminated: false,
+            winner: None,
+            winner_index: None,
+            turn_number: 1,
+            dice_count: NUM_DICE,
+            rerollable_dice: Vec::new(),
+            keep_options: Vec::new(),
+            turn_end_reason: None,
+            target_score: config.target_score,
+            end_mode: config.end_mode,
+            final_round_trigger: None,
+            bust_penalty: config.bust_penalty,
+            rules: config.rules.clone(),
+            opening_score: config.opening_score,
+        }
+    }
+
+    pub fn current_player(&self) -> &Player {
+        &self.players[self.current_player_index]
+    }
+
+    pub fn current_player_mut(&mut self) -> &mut Player {
+        &mut self.players[self.current_player_index]
+    }
+
+    /// Total scores of everyone but the current player.
+    pub fn other_scores(&self) -> Vec<u32> {
+        self.players
+            .iter()
+            .enumerate()
+            .filter(|(i, _)| *i != self.current_player_index)
+            .map(|(_, p)| p.score)
+            .collect()
+    }
+
+    /// Round number, counting one turn per player.
+    pub fn round(&self) -> u32 {
+        (self.turn_number - 1) / self.players.len().max(1) as u32 + 1
+    }
+
+    pub fn phase(&self) -> TurnPhase {
+        if self.game_over {
+            TurnPhase::GameOver
+        } else if self.turn_terminated {
+            TurnPhase::TurnOver
+        } else if self.has_pending_roll() {
+            TurnPhase::AwaitingKeep
+        } else {
+            TurnPhase::AwaitingRoll
+        }
+    }
+
+    /// True while the current roll is waiting for a keep decision.
+    pub fn has_pending_roll(&self) -> bool {
+        !self.keep_options.is_empty()
+    }
+
+    /// True after hot dice: the player cannot bank before rolling all six again.
+    pub fn must_roll(&self) -> bool {
+        self.phase() == TurnPhase::AwaitingRoll
+            && self.current_player().turn_score > 0
+            && self.dice_count == NUM_DICE
+    }
+
+    /// The keep used when a player does not choose: every scoring die.
+    pub fn default_keep(&self) -> Vec<usize> {
+        self.keep_options
+            .first()
+            .map(|o| o.indices.clone())
+            .unwrap_or_default()
+    }
+
+    /// Bank bounds for the current player (opening score, exact finish).
+    pub fn bank_limits(&self) -> BankLimits {
+        let player = self.current_player();
+        let min = if player.opened { 0 } else { self.opening_score.unwrap_or(0) };
+        BankLimits::new(min, player.score, self.target_score, self.end_mode)
+    }
+
+    /// Apply one action. On error the state is left untouched.
+    ///
+    /// `source` is only rolled for [`Action::Roll`].
+    pub fn apply(
+        &mut self,
+        action: Action,
+        source: &mut dyn DiceSource,
+    ) -> Result<Vec<Event>, EngineError> {
+        let mut events = Vec::new();
+        match action {
+            Action::Roll => {
+                self.expect_phase(TurnPhase::AwaitingRoll)?;
+                let dice = source.roll(self.dice_count);
+                if dice.len() != self.dice_count || dice.iter().any(|d| !(1..=6).contains(d)) {
+                    return Err(EngineError::InvalidRoll(dice));
+                }
+                self.record_roll(dice, &mut events);
+            }
+            Action::Keep(indices) => {
+                self.expect_phase(TurnPhase::AwaitingKeep)?;
+                self.keep(&indices, &mut events)?;
+            }
+            Action::Bank => {
+                self.expect_phase(TurnPhase::AwaitingRoll)?;
+                let turn_score = self.current_player().turn_score;
+                if turn_score == 0 {
+                    return Err(EngineError::NothingToBank);
+                }
+                if self.must_roll() {
+                    return Err(EngineError::MustRoll);
+                }
+                self.bank_limits()
+                    .check(turn_score)
+                    .map_err(EngineError::BankRejected)?;
+                self.bank_turn(&mut events);
+            }
+            Action::NextPlayer => {
+                match self.phase() {
+                    TurnPhase::GameOver => return Err(EngineError::GameOver),
+                    TurnPhase::TurnOver => {}
+                    _ => return Err(EngineError::TurnInProgress),
+                }
+                self.turn_end_reason = None;
+                self.next_player();
+                self.dice.clear();
+
+                let trigger = self.final_round_trigger;
+                self.check_winner();
+                if self.game_over {
+                    let winner = self.winner_index.unwrap_or(self.current_player_index);
+                    events.push(Event::GameOver {
+                        winner,
+                        score: self.players[winner].score,
+                    });
+                } else {
+                    if let (None, Some(player)) = (trigger, self.final_round_trigger) {
+                        events.push(Event::FinalRound { player });
+                    }
+                    events.push(Event::TurnStarted {
+                        player: self.current_player_index,
+                        turn_number: self.turn_number,
+                    });
+                }
+            }
+        }
+        Ok(events)
+    }
+
+    fn expect_phase(&self, expected: TurnPhase) -> Result<(), EngineError> {
+        match self.phase() {
+            phase if phase == expected => Ok(()),
+            TurnPhase::GameOver => Err(EngineError::GameOver),
+            TurnPhase::TurnOver => Err(EngineError::TurnOver),
+            TurnPhase::AwaitingKeep => Err(EngineError::MustKeep),
+            TurnPhase::AwaitingRoll => Err(EngineError::NothingToKeep),
+        }
+    }
+
+    pub fn next_player(&mut self) {
+        self.current_player_index = (self.current_player_index + 1) % self.players.len();
+        self.turn_number += 1;
+        self.dice_count = NUM_DICE;
+        self.turn_terminated = false;
+        self.keep_options.clear();
+        self.rerollable_dice.clear();
+    }
+
+    /// Record a fresh roll. `roll_score` shows the best possible keep;
+    /// nothing is added to the turn score until the keep.
+    fn record_roll(&mut self, roll: Vec<u8>, events: &mut Vec<Event>) {
+        let options = keep_options(&roll, &self.rules);
+        events.push(Event::Rolled {
+            player: self.current_player_index,
+            dice: roll.clone(),
+        });
+        self.dice = roll;
+        match options.first() {
+            Some(best) => {
+                self.rerollable_dice = (0..self.dice.len())
+                    .filter(|i| !best.indices.contains(i))
+                    .collect();
+                self.current_player_mut().roll_score = best.score;
+                self.keep_options = options;
+            }
+            None => {
+                self.rerollable_dice = (0..self.dice.len()).collect();
+                self.current_player_mut().roll_score = 0;
+                self.forfeit_turn(BustReason::NoScore, events);
+            }
+        }
+    }
+
+    /// Set dice aside from the pending roll and add their score to the turn.
+    fn keep(&mut self, indices: &[usize], events: &mut Vec<Event>) -> Result<(), EngineError> {
+        let result = validate_keep(&self.dice, indices, &self.rules)?;
+        let limits = self.bank_limits();
+        let player = self.current_player_index;
+
+        self.rerollable_dice = result.reroll_indices.clone();
+        self.keep_options.clear();
+        self.dice_count = if result.dice_left() == 0 { NUM_DICE } else { result.dice_left() };
+        let current = self.current_player_mut();
+        current.roll_score = result.score;
+        current.turn_score += result.score;
+        let turn_score = current.turn_score;
+        events.push(Event::Kept {
+            player,
+            dice: result.kept.clone(),
+            score: result.score,
+            turn_score,
+            dice_left: result.dice_left(),
+        });
+
+        if limits.overshoots(turn_score) {
+            self.forfeit_turn(BustReason::Overshoot, events);
+        } else if result.ends_turn {
+            events.push(Event::TurnEndingCombination { player });
+            if limits.check(turn_score).is_ok() {
+                self.bank_turn(events);
+            } else {
+                self.forfeit_turn(BustReason::BelowOpening, events);
+            }
+        } else if result.dice_left() == 0 {
+            events.push(Event::HotDice { player });
+        }
+        Ok(())
+    }
+
+    /// Add the current player's turn score to their total and end the turn.
+    fn bank_turn(&mut self, events: &mut Vec<Event>) {
+        let index = self.current_player_index;
+        let player = self.current_player_mut();
+        let points = player.turn_score;
+        player.score += points;
+        player.turn_score = 0;
+        player.roll_score = 0;
+        player.opened = true;
+        player.record_turn_result(false, None);
+        events.push(Event::Banked {
+            player: index,
+            points,
+            total: player.score,
+        });
+        self.turn_terminated = true;
+        self.turn_end_reason = Some("banked".to_string());
+    }
+
+    /// End the turn without banking. Applies the bust penalty, in which case
+    /// the reason becomes "bust_penalty".
+    fn forfeit_turn(&mut self, reason: BustReason, events: &mut Vec<Event>) {
+        let index = self.current_player_index;
+        let penalty = self.bust_penalty;
+        let player = self.current_player_mut();
+        let lost = player.turn_score;
+        player.turn_score = 0;
+        player.roll_score = 0;
+        events.push(Event::Busted {
+            player: index,
+            reason,
+            lost,
+        });
+        let deducted = player.record_turn_result(true, penalty);
+        if let Some(points) = deducted {
+            events.push(Event::BustPenalty {
+                player: index,
+                points,
+            });
+        }
+        self.turn_terminated = true;
+        self.turn_end_reason = Some(match deducted {
+            Some(_) => "bust_penalty".to_string(),
+            None => "busted".to_string(),
+        });
+    }
+
+    /// Update `game_over`/`winner`; call it once the next player is up.
+    pub fn check_winner(&mut self) {
+        let Some(reached) = self.players.iter().position(|p| p.score >= self.target_score) else {
+            return;
+        };
+
+        if self.end_mode != EndMode::FinalRound {
+            self.set_winner(reached);
+            return;
+        }
+
+        match self.final_round_trigger {
+            None => self.final_round_trigger = Some(reached),
+            Some(trigger) if trigger == self.current_player_index => {
+                // Everyone had their last turn, the best score wins (first to pass on ties)
+                let best = self.players.iter().map(|p| p.score).max().unwrap_or(0);
+                let winner = if self.players[trigger].score == best {
+                    trigger
+                } else {
+                    self.players.iter().position(|p| p.score == best).unwrap_or(trigger)
+                };
+                self.set_winner(winner);
+            }
+            Some(_) => {}
+        }
+    }
+
+    fn set_winner(&mut self, seat: usize) {
+        self.game_over = true;
+        self.winner_index = Some(seat);
+        self.winner = Some(self.players[seat].name.clone());
+    }
+}
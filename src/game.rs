@@ -1,84 +1,308 @@
-use crate::ai::ai_turn;
-use crate::computer::computer_turn;
 use crate::config::Config;
-use crate::human::human_turn;
-use crate::api::models::{Player};
+use crate::dice::SeededDice;
+use crate::engine::{Action, BustReason, Event, GameState, Player, TurnController, play_turn};
+use crate::human::HumanPlayer;
+use crate::record::GameRecord;
+use crate::save::SavedGame;
+use crate::strategy;
 
 use colored::Colorize;
+use std::path::{Path, PathBuf};
 use std::process::Command;
+use textwrap::wrap;
+use uuid::Uuid;
 
-const WINNING_SCORE: u32 = 6000;
+pub fn start_game(has_openai: bool, has_anthropic: bool, config: &Config) {
+    let game_id = Uuid::new_v4().to_string();
+    let state = GameState::new(game_id.clone(), has_openai, has_anthropic, config);
+    let dice = SeededDice::from_seed(config.game.seed);
+    let save_path = config
+        .game
+        .save_dir
+        .as_ref()
+        .map(|dir| Path::new(dir).join(format!("{}.json", game_id)));
+    play_game(state, dice, save_path, config);
+}
 
+/// Continue a game saved by `start_game`, saving back to the same file.
+pub fn resume_game(path: &str, config: &Config) {
+    let saved = match SavedGame::load(path) {
+        Ok(saved) => saved,
+        Err(e) => {
+            println!("{}", format!("Could not resume {}: {}", path, e).bold().red());
+            return;
+        }
+    };
+    if saved.state.game_over {
+        println!("{}", "This game is already over.".bold().yellow());
+        return;
+    }
+    play_game(saved.state, saved.dice, Some(PathBuf::from(path)), config);
+}
 
-pub fn start_game(has_openai: bool, has_anthropic: bool, config: &Config) {
-    let mut players = setup_players(has_openai, has_anthropic, config);
-    let mut turn_number = 1;
+fn play_game(mut state: GameState, mut dice: SeededDice, save_path: Option<PathBuf>, config: &Config) {
+    let record_path = config
+        .game
+        .record_dir
+        .as_ref()
+        .map(|dir| Path::new(dir).join(format!("{}.jsonl", state.id)));
+    // A resumed game keeps adding to its record
+    let mut record = record_path
+        .as_ref()
+        .and_then(|path| GameRecord::load(path).ok())
+        .unwrap_or_else(|| GameRecord::new(&state, Some(dice.seed())));
+
+    // Bots keep their strategy for the whole game
+    let mut bots = Vec::new();
+    for player in &state.players {
+        let bot = match &player.ai_type {
+            Some(name) if !player.is_human => match strategy::create(name, config) {
+                Some(bot) => Some(bot),
+                None => {
+                    println!(
+                        "{}",
+                        format!(
+                            "Unknown strategy '{}' for {}. Known strategies: {}",
+                            name,
+                            player.name,
+                            strategy::names().join(", ")
+                        )
+                        .bold()
+                        .red()
+                    );
+                    return;
+                }
+            },
+            _ => None,
+        };
+        bots.push(bot);
+    }
 
     loop {
         clear_screen();
-        print_summary(turn_number, &players);
+        print_summary(&state);
+        if let Some(path) = &save_path {
+            println!("{}", format!("Saved after every turn to {}", path.display()).dimmed());
+        }
 
-        for i in 0..players.len() {
+        let player = state.current_player().clone();
+        println!("{}", format!("--- {} is playing ---", player.name).bold().cyan());
+
+        let limits = state.bank_limits();
+        if limits.min > 0 {
             println!(
                 "{}",
-                format!("--- {} is playing ---", players[i].name)
-                    .bold()
-                    .cyan()
+                format!("{} points needed in this turn to get on the board", limits.min).yellow()
             );
+        }
+        if let Some(max) = limits.max {
+            println!(
+                "{}",
+                format!("Exactly {} points needed to finish, more busts the turn", max).yellow()
+            );
+        }
 
-            let turn_score = if players[i].is_human {
-                human_turn()
-            } else {
-                let other_scores: Vec<u32> = players
-                    .iter()
-                    .enumerate()
-                    .filter(|(j, _)| *j != i)
-                    .map(|(_, p)| p.score)
-                    .collect();
-
-                if players[i].ai_type == Some("computer".to_string()) {
-                    computer_turn(players[i].score, &other_scores, config)
-                } else {
-                    ai_turn(players[i].score, &other_scores, &players[i].ai_type, config)
-                }
-            };
-
-            players[i].score += turn_score;
-
-            if players[i].score >= WINNING_SCORE {
-                clear_screen();
-                print_summary(turn_number, &players);
-                println!(
-                    "{}",
-                    format!("{} wins with {} points!", players[i].name, players[i].score)
-                        .bold()
-                        .red()
-                        .on_white()
-                );
-                return;
+        let mut human = HumanPlayer;
+        let controller: &mut dyn TurnController = match &mut bots[state.current_player_index] {
+            Some(bot) => bot,
+            None => &mut human,
+        };
+        play_turn(&mut state, controller, &mut dice, &mut |state, event| {
+            print_event(state, event);
+            record.push(event.clone());
+            // Let the players take in the last round (replays do not stop here)
+            if let Event::FinalRound { .. } = event {
+                std::io::stdin().read_line(&mut String::new()).unwrap();
             }
+        })
+        .expect("play_turn replaces illegal keeps and refused banks");
 
-            if !players[i].is_human {
-                println!(
-                    "{}",
-                    format!(
-                        "{} has finished its turn. Press Enter to continue...",
-                        players[i].name
-                    )
+        let busted = state.turn_end_reason.as_deref() != Some("banked");
+        if !player.is_human || busted {
+            println!(
+                "{}",
+                format!("{} has finished the turn. Press Enter to continue...", player.name)
                     .bold()
                     .magenta()
-                );
-                std::io::stdin().read_line(&mut String::new()).unwrap();
+            );
+            std::io::stdin().read_line(&mut String::new()).unwrap();
+        }
+
+        let events = state
+            .apply(Action::NextPlayer, &mut dice)
+            .expect("the turn is over");
+        for event in &events {
+            print_event(&state, event);
+        }
+        record.extend(events);
+        if let Some(Err(e)) = record_path.as_ref().map(|path| record.save(path)) {
+            println!("{}", format!("Could not save the game record: {}", e).red());
+        }
+        let saved = SavedGame::new(&state, &dice);
+        if let Some(Err(e)) = save_path.as_ref().map(|path| saved.save(path)) {
+            println!("{}", format!("Could not save the game: {}", e).red());
+        }
+        if state.game_over {
+            if let Some(path) = &record_path {
+                println!("Game record saved to {}", path.display());
             }
+            return;
+        }
+    }
+}
 
+/// Step through a recorded game, one turn at a time.
+pub fn replay_game(path: &str) {
+    let record = match GameRecord::load(path) {
+        Ok(record) => record,
+        Err(e) => {
+            println!("{}", format!("Could not read {}: {}", path, e).bold().red());
+            return;
+        }
+    };
+
+    clear_screen();
+    print_summary(&record.header.state);
+    if let Some(seed) = record.header.seed {
+        println!("Dice seed: {}", seed);
+    }
+    println!(
+        "{}",
+        format!("--- {} is playing ---", record.header.state.current_player().name)
+            .bold()
+            .cyan()
+    );
+
+    let result = record.replay(&mut |state, event| {
+        if let Event::TurnStarted { player, .. } = event {
+            println!("{}", "Press Enter for the next turn...".bold().magenta());
+            std::io::stdin().read_line(&mut String::new()).unwrap();
             clear_screen();
-            print_summary(turn_number, &players);
+            print_summary(state);
+            println!(
+                "{}",
+                format!("--- {} is playing ---", state.players[*player].name).bold().cyan()
+            );
+        } else {
+            print_event(state, event);
+        }
+    });
+
+    match result {
+        Ok(state) if !state.game_over => {
+            println!("{}", "End of the record, the game was not finished.".bold().yellow());
+        }
+        Ok(_) => {}
+        Err(e) => println!("{}", format!("Replay stopped: {}", e).bold().red()),
+    }
+}
+
+/// Render an engine event on the terminal.
+fn print_event(state: &GameState, event: &Event) {
+    match event {
+        Event::Rolled { player, dice } => {
+            println!("{} {:?}", format!("\n\t{} rolled:", state.players[*player].name).bold().green(), dice);
+        }
+        Event::Kept { dice, score, dice_left, .. } => {
+            println!("{} {:?}", "\tKeeps:".bold().green(), dice);
+            println!("{} +{}", "\tScore:".bold().cyan(), score);
+            if *dice_left > 0 {
+                let reroll: Vec<u8> = state.rerollable_dice.iter().map(|&i| state.dice[i]).collect();
+                println!("{} {} - {:?}", "\tRemaining dice:".bold().blue(), dice_left, reroll);
+            }
+        }
+        Event::HotDice { .. } => {
+            println!("{}", "\tAll dice scored! Rolling all six dice again!".bold().magenta());
+        }
+        Event::TurnEndingCombination { .. } => {
+            println!("{}", "\tThis combination ends the turn!".bold().magenta());
+        }
+        Event::Busted { player, reason, .. } => {
+            let name = &state.players[*player].name;
+            let message = match reason {
+                BustReason::NoScore => format!("\tNo points! {} loses the turn.", name),
+                BustReason::Overshoot => format!("\tTarget overshot! {} loses the turn.", name),
+                BustReason::BelowOpening => {
+                    format!("\tThe turn ends below the opening score! {} loses the turn.", name)
+                }
+            };
+            println!("{}", message.bold().red());
+        }
+        Event::BustPenalty { player, points } => {
+            println!(
+                "{}",
+                format!("{} busted too many turns in a row: -{} points!", state.players[*player].name, points)
+                    .bold()
+                    .red()
+            );
+        }
+        Event::Banked { player, points, total } => {
+            println!(
+                "{}",
+                format!("\t{} banks {} points ({} total).", state.players[*player].name, points, total)
+                    .bold()
+                    .green()
+            );
         }
-        turn_number += 1;
+        Event::Explanation { decision, explanation, .. } => {
+            let wrapped_explanation = wrap(explanation, 80);
+            let max_lines = 50;
+
+            println!("{} {}", "\tAI decision:".bold().blue(), decision.bold().white());
+            println!("{}", "\tReason:".bold().green());
+            for line in wrapped_explanation.iter().take(max_lines) {
+                println!("\t  {}", line);
+            }
+            if wrapped_explanation.len() > max_lines {
+                println!("\t  [...]");
+            }
+        }
+        Event::BankRejected { reason, .. } => {
+            println!("{} {}. Rolling again!", "\tBank rejected:".bold().red(), reason);
+        }
+        Event::KeepRejected { player, reason } => {
+            println!(
+                "{} {} ({}), keeping the best option instead.",
+                "\tInvalid keep:".bold().yellow(),
+                state.players[*player].name,
+                reason
+            );
+        }
+        Event::FinalRound { player } => {
+            println!(
+                "{}",
+                format!(
+                    "{} passed {} points! Every other player gets one last turn.",
+                    state.players[*player].name, state.target_score
+                )
+                .bold()
+                .yellow()
+            );
+        }
+        Event::GameOver { winner, .. } => announce_winner(state, *winner),
+        Event::TurnStarted { .. } => {}
     }
 }
 
 pub fn setup_players(has_openai: bool, has_anthropic: bool, config: &Config) -> Vec<Player> {
+    if !config.game.players.is_empty() {
+        return config
+            .game
+            .players
+            .iter()
+            .map(|seat| Player {
+                name: seat.name.clone(),
+                score: 0,
+                is_human: seat.strategy.is_none(),
+                ai_type: seat.strategy.clone(),
+                turn_score: 0,
+                roll_score: 0,
+                opened: false,
+                bust_streak: 0,
+            })
+            .collect();
+    }
+
     let mut players = Vec::new();
 
     // Always add human player
@@ -89,6 +313,8 @@ pub fn setup_players(has_openai: bool, has_anthropic: bool, config: &Config) ->
         ai_type: None,
         turn_score: 0,
         roll_score: 0,
+        opened: false,
+        bust_streak: 0,
     });
 
     // Always add computer player
@@ -99,9 +325,25 @@ pub fn setup_players(has_openai: bool, has_anthropic: bool, config: &Config) ->
         ai_type: Some("computer".to_string()),
         turn_score: 0,
         roll_score: 0,
+        opened: false,
+        bust_streak: 0,
     });
 
-    // add ai players
+    // add ai players, from the providers list when there is one
+    if !config.providers.is_empty() {
+        players.extend(config.providers.iter().map(|provider| Player {
+            name: provider.name.clone(),
+            score: 0,
+            is_human: false,
+            ai_type: Some(format!("llm:{}", provider.name)),
+            turn_score: 0,
+            roll_score: 0,
+            opened: false,
+            bust_streak: 0,
+        }));
+        return players;
+    }
+
     if has_openai {
         players.push(Player {
             name: "AI (OpenAI)".to_string(),
@@ -110,6 +352,8 @@ pub fn setup_players(has_openai: bool, has_anthropic: bool, config: &Config) ->
             ai_type: Some("openai".to_string()),
             turn_score: 0,
             roll_score: 0,
+            opened: false,
+            bust_streak: 0,
         });
     }
 
@@ -121,6 +365,8 @@ pub fn setup_players(has_openai: bool, has_anthropic: bool, config: &Config) ->
             ai_type: Some("anthropic".to_string()),
             turn_score: 0,
             roll_score: 0,
+            opened: false,
+            bust_streak: 0,
         });
     }
 
@@ -132,23 +378,41 @@ pub fn setup_players(has_openai: bool, has_anthropic: bool, config: &Config) ->
             ai_type: Some("ollama".to_string()),
             turn_score: 0,
             roll_score: 0,
+            opened: false,
+            bust_streak: 0,
         });
     }
 
     players
 }
 
+fn announce_winner(state: &GameState, winner: usize) {
+    let players = &state.players;
+    clear_screen();
+    print_summary(state);
+    println!(
+        "{}",
+        format!("{} wins with {} points!", players[winner].name, players[winner].score)
+            .bold()
+            .red()
+            .on_white()
+    );
+}
+
 fn clear_screen() {
     Command::new("clear").status().unwrap();
 }
 
-fn print_summary(turn_number: u32, players: &[Player]) {
+fn print_summary(state: &GameState) {
     println!("{}", "6000 Dice Game".bold().blue());
     println!("{}", "==============================".blue());
 
-    let mut summary = format!("Turn: {}", turn_number);
-    for player in players {
+    let mut summary = format!("Turn: {}", state.round());
+    for player in &state.players {
         summary.push_str(&format!(" | {}: {}", player.name, player.score));
+        if player.bust_streak > 0 {
+            summary.push_str(&format!(" ({} bust streak)", player.bust_streak));
+        }
     }
 
     println!("{}", summary.bold().yellow());
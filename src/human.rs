@@ -1,58 +1,89 @@
-use crate::score::{calculate_score, roll_dice};
+use crate::engine::{GameState, TurnController};
+use crate::rules::RuleSet;
+use crate::score::{KeepOption, validate_keep};
 use colored::*;
 use std::io::{self, Write};
 
-const NUM_DICE: usize = 6;
+/// A player at the terminal.
+pub struct HumanPlayer;
 
-pub fn human_turn() -> u32 {
-    let mut dice = NUM_DICE;
-    let mut turn_score = 0;
-    let mut roll_count = 1;
-
-    loop {
-        println!("{} {}", "\n\tRoll number:".bold().green(), roll_count);
-
-        let roll = roll_dice(dice);
-        println!("{} {:?}", "\tYou rolled:".bold().green(), roll);
-
-        let (score, remaining_dice, remaining_dice_values, _rerollable_dice) = calculate_score(&roll);
-        println!("{} +{}", "\tScore:".bold().cyan(), score);
-
-        if score == 0 {
-            println!(
-                "{}",
-                "\tNo points! You lose the turn. Press Enter to continue...\n"
-                    .bold()
-                    .red()
-            );
-            std::io::stdin().read_line(&mut String::new()).unwrap();
-            return 0;
-        }
-
-        turn_score += score;
-        roll_count += 1;
-
-        if remaining_dice == 0 {
-            println!(
-                "{}",
-                "\tAll dice scored! You get to roll again!".bold().magenta()
-            );
-            continue;
-        }
+impl TurnController for HumanPlayer {
+    fn choose_keep(&mut self, state: &GameState) -> Vec<usize> {
+        ask_keep(&state.dice, &state.keep_options, &state.rules)
+    }
 
-        println!("{} {} - {:?}", "\tRemaining dice:".bold().blue(), remaining_dice, remaining_dice_values);
+    fn roll_again(&mut self, _state: &GameState) -> bool {
         print!("{}", "\t(T)ake points or (R)oll again? ".bold().white());
         io::stdout().flush().unwrap();
 
         let mut choice = String::new();
         io::stdin().read_line(&mut choice).unwrap();
+        !choice.trim().eq_ignore_ascii_case("T")
+    }
+}
 
-        if choice.trim().eq_ignore_ascii_case("T") {
-            break;
-        } else {
-            dice = remaining_dice as usize;
+/// Ask which scoring dice to set aside. Accepts an option number, or the
+/// dice positions (1-based) after a `d` or separated by spaces: a lone
+/// number is an option, `d4` keeps the fourth die. Enter keeps the best
+/// option.
+fn ask_keep(roll: &[u8], options: &[KeepOption], rules: &RuleSet) -> Vec<usize> {
+    println!("{}", "\tKeep options:".bold().blue());
+    for (i, option) in options.iter().enumerate() {
+        println!(
+            "\t  {}) {:?} -> +{} ({} dice left)",
+            i + 1,
+            option.dice,
+            option.score,
+            roll.len() - option.dice.len()
+        );
+    }
+
+    loop {
+        print!(
+            "{}",
+            "\tKeep which option? (option number, or dice positions like \"d4\" or \"1 4\", Enter = 1) "
+                .bold()
+                .white()
+        );
+        io::stdout().flush().unwrap();
+
+        let mut input = String::new();
+        io::stdin().read_line(&mut input).unwrap();
+
+        match parse_keep_input(input.trim(), roll, options, rules) {
+            Ok(indices) => return indices,
+            Err(message) => println!("{} {}", "\tInvalid keep:".bold().red(), message),
         }
     }
+}
+
+fn parse_keep_input(
+    input: &str,
+    roll: &[u8],
+    options: &[KeepOption],
+    rules: &RuleSet,
+) -> Result<Vec<usize>, String> {
+    let positions = input.strip_prefix(['d', 'D']);
+    let indices = if input.is_empty() {
+        options[0].indices.clone()
+    } else if positions.is_some() || input.contains(' ') || input.contains(',') {
+        positions
+            .unwrap_or(input)
+            .split([' ', ','])
+            .filter(|part| !part.is_empty())
+            .map(|part| match part.parse::<usize>() {
+                Ok(n) if n >= 1 => Ok(n - 1),
+                _ => Err(format!("'{}' is not a dice position", part)),
+            })
+            .collect::<Result<Vec<_>, _>>()?
+    } else {
+        match input.parse::<usize>() {
+            Ok(n) if n >= 1 && n <= options.len() => options[n - 1].indices.clone(),
+            _ => return Err(format!("'{}' is not one of the options", input)),
+        }
+    };
 
-    turn_score
+    validate_keep(roll, &indices, rules)
+        .map(|_| indices)
+        .map_err(|e| e.to_string())
 }
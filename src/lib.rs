@@ -2,6 +2,19 @@ pub mod ai;
 pub mod api;
 pub mod computer;
 pub mod config;
+pub mod dice;
+pub mod engine;
 pub mod game;
 pub mod human;
+pub mod llm;
+pub mod mock_llm;
+pub mod monte_carlo;
+pub mod optimal;
+pub mod record;
+pub mod rules;
+pub mod save;
 pub mod score;
+pub mod simulate;
+pub mod strategy;
+pub mod tune;
+pub mod win_probability;
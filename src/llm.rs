@@ -0,0 +1,316 @@
+//! Chat-completion clients for the LLM players.
+//!
+//! Every vendor sits behind [`LlmProvider`]: a system prompt and messages
+//! go in, the model's text comes out. OpenAI and any OpenAI-compatible
+//! server (vLLM, LM Studio, llama.cpp...) share one client; Anthropic and
+//! Ollama have their own request and response shapes.
+
+use std::env;
+use std::fmt;
+use std::thread;
+use std::time::Duration;
+
+use reqwest::StatusCode;
+use reqwest::blocking::{Client, RequestBuilder};
+use serde::Serialize;
+use serde_json::{Value, json};
+
+use crate::config::{ProviderConfig, ProviderKind};
+
+/// Who wrote a chat message.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Role {
+    User,
+    Assistant,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ChatMessage {
+    pub role: Role,
+    pub content: String,
+}
+
+/// A chat completion request, whatever the vendor.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ChatRequest {
+    pub system: String,
+    pub messages: Vec<ChatMessage>,
+    pub max_tokens: Option<u32>,
+    pub temperature: Option<f64>,
+}
+
+impl ChatRequest {
+    /// A system prompt and a single user message.
+    pub fn new(system: impl Into<String>, user: impl Into<String>) -> Self {
+        ChatRequest {
+            system: system.into(),
+            messages: vec![ChatMessage {
+                role: Role::User,
+                content: user.into(),
+            }],
+            max_tokens: None,
+            temperature: None,
+        }
+    }
+
+    /// Add a message to the conversation.
+    pub fn push(&mut self, role: Role, content: impl Into<String>) {
+        self.messages.push(ChatMessage {
+            role,
+            content: content.into(),
+        });
+    }
+
+    pub fn max_tokens(mut self, max_tokens: u32) -> Self {
+        self.max_tokens = Some(max_tokens);
+        self
+    }
+
+    pub fn temperature(mut self, temperature: f64) -> Self {
+        self.temperature = Some(temperature);
+        self
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum LlmError {
+    /// The environment variable holding the API key is not set.
+    MissingApiKey(String),
+    /// The request failed or the server answered with an error status.
+    Request(String),
+    /// The answer has no text where the vendor puts it.
+    Response(String),
+}
+
+impl fmt::Display for LlmError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LlmError::MissingApiKey(var) => write!(f, "{} is not set", var),
+            LlmError::Request(e) => write!(f, "request failed: {}", e),
+            LlmError::Response(e) => write!(f, "unexpected response: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for LlmError {}
+
+/// A chat-completion endpoint.
+pub trait LlmProvider: Send + Sync {
+    /// Name of the player or endpoint, for messages.
+    fn name(&self) -> &str;
+
+    /// Text of the model's answer to `request`.
+    fn complete(&self, request: &ChatRequest) -> Result<String, LlmError>;
+}
+
+/// Client for a configured provider.
+pub fn provider_from_config(config: &ProviderConfig) -> Box<dyn LlmProvider> {
+    let endpoint = Endpoint {
+        name: config.name.clone(),
+        url: config.url().to_string(),
+        model: config.model.clone(),
+        api_key_env: config.api_key_env().map(str::to_string),
+        timeout: Duration::from_secs(config.timeout.unwrap_or(DEFAULT_TIMEOUT_SECS)),
+        retries: config.retries.unwrap_or(DEFAULT_RETRIES),
+    };
+    match config.kind {
+        ProviderKind::Openai | ProviderKind::OpenaiCompatible => Box::new(OpenAiProvider {
+            endpoint,
+            key_required: config.kind == ProviderKind::Openai,
+        }),
+        ProviderKind::Anthropic => Box::new(AnthropicProvider { endpoint }),
+        ProviderKind::Ollama => Box::new(OllamaProvider { endpoint }),
+    }
+}
+
+const DEFAULT_TIMEOUT_SECS: u64 = 60;
+
+const DEFAULT_RETRIES: u32 = 2;
+
+/// Wait before the first retry, doubled for each one after.
+const RETRY_BACKOFF: Duration = Duration::from_millis(500);
+
+/// Anthropic requires a token limit.
+const ANTHROPIC_MAX_TOKENS: u32 = 1024;
+
+/// Where and what to ask, common to every vendor.
+struct Endpoint {
+    name: String,
+    url: String,
+    model: String,
+    api_key_env: Option<String>,
+    timeout: Duration,
+    retries: u32,
+}
+
+impl Endpoint {
+    /// The API key, None when the provider needs none.
+    fn api_key(&self, required: bool) -> Result<Option<String>, LlmError> {
+        let Some(var) = &self.api_key_env else {
+            return Ok(None);
+        };
+        match env::var(var) {
+            Ok(key) => Ok(Some(key)),
+            Err(_) if required => Err(LlmError::MissingApiKey(var.clone())),
+            Err(_) => Ok(None),
+        }
+    }
+
+    /// Send `body`, retrying with backoff on timeouts, connection errors
+    /// and 429 or 5xx statuses.
+    fn post(&self, body: &Value, headers: impl Fn(RequestBuilder) -> RequestBuilder) -> Result<Value, LlmError> {
+        let client = Client::builder()
+            .timeout(self.timeout)
+            .build()
+            .map_err(|e| self.request_error(e))?;
+        let mut attempt = 0;
+        loop {
+            let response = headers(client.post(&self.url).json(body)).send();
+            let transient = match &response {
+                Ok(response) => {
+                    response.status().is_server_error() || response.status() == StatusCode::TOO_MANY_REQUESTS
+                }
+                Err(e) => e.is_timeout() || e.is_connect(),
+            };
+            if transient && attempt < self.retries {
+                thread::sleep(RETRY_BACKOFF * 2u32.pow(attempt));
+                attempt += 1;
+                continue;
+            }
+            let response = response
+                .and_then(|response| response.error_for_status())
+                .map_err(|e| self.request_error(e))?;
+            return response.json().map_err(|e| LlmError::Response(e.to_string()));
+        }
+    }
+
+    fn request_error(&self, e: reqwest::Error) -> LlmError {
+        if e.is_timeout() {
+            return LlmError::Request(format!("no answer within {} s", self.timeout.as_secs()));
+        }
+        LlmError::Request(e.to_string())
+    }
+}
+
+/// Text at `pointer` (a JSON pointer) in a response.
+fn text_at(response: &Value, pointer: &str) -> Result<String, LlmError> {
+    response
+        .pointer(pointer)
+        .and_then(Value::as_str)
+        .map(str::to_string)
+        .ok_or_else(|| LlmError::Response(format!("no text at {} in {}", pointer, response)))
+}
+
+/// Chat messages with the system prompt first, as OpenAI and Ollama take them.
+fn with_system(request: &ChatRequest) -> Vec<Value> {
+    let mut messages = vec![json!({ "role": "system", "content": request.system })];
+    messages.extend(request.messages.iter().map(|message| json!(message)));
+    messages
+}
+
+/// OpenAI's chat completions API, also spoken by most self-hosted servers.
+struct OpenAiProvider {
+    endpoint: Endpoint,
+    key_required: bool,
+}
+
+impl LlmProvider for OpenAiProvider {
+    fn name(&self) -> &str {
+        &self.endpoint.name
+    }
+
+    fn complete(&self, request: &ChatRequest) -> Result<String, LlmError> {
+        let key = self.endpoint.api_key(self.key_required)?;
+        let mut body = json!({
+            "model": self.endpoint.model,
+            "messages": with_system(request),
+        });
+        if let Some(max_tokens) = request.max_tokens {
+            body["max_tokens"] = json!(max_tokens);
+        }
+        if let Some(temperature) = request.temperature {
+            body["temperature"] = json!(temperature);
+        }
+        let response = self.endpoint.post(&body, |builder| match &key {
+            Some(key) => builder.bearer_auth(key),
+            None => builder,
+        })?;
+        text_at(&response, "/choices/0/message/content")
+    }
+}
+
+struct AnthropicProvider {
+    endpoint: Endpoint,
+}
+
+impl LlmProvider for AnthropicProvider {
+    fn name(&self) -> &str {
+        &self.endpoint.name
+    }
+
+    fn complete(&self, request: &ChatRequest) -> Result<String, LlmError> {
+        let key = self.endpoint.api_key(true)?.unwrap_or_default();
+        let mut body = json!({
+            "model": self.endpoint.model,
+            "max_tokens": request.max_tokens.unwrap_or(ANTHROPIC_MAX_TOKENS),
+            "system": request.system,
+            "messages": request.messages,
+        });
+        if let Some(temperature) = request.temperature {
+            body["temperature"] = json!(temperature);
+        }
+        let response = self.endpoint.post(&body, |builder| {
+            builder
+                .header("x-api-key", &key)
+                .header("anthropic-version", "2023-06-01")
+        })?;
+        text_at(&response, "/content/0/text")
+    }
+}
+
+/// Ollama's chat API, or its older generate API when the URL points there.
+struct OllamaProvider {
+    endpoint: Endpoint,
+}
+
+impl LlmProvider for OllamaProvider {
+    fn name(&self) -> &str {
+        &self.endpoint.name
+    }
+
+    fn complete(&self, request: &ChatRequest) -> Result<String, LlmError> {
+        let mut options = json!({});
+        if let Some(max_tokens) = request.max_tokens {
+            options["num_predict"] = json!(max_tokens);
+        }
+        if let Some(temperature) = request.temperature {
+            options["temperature"] = json!(temperature);
+        }
+        if self.endpoint.url.trim_end_matches('/').ends_with("/generate") {
+            let prompt = request
+                .messages
+                .iter()
+                .map(|message| message.content.as_str())
+                .collect::<Vec<_>>()
+                .join("\n\n");
+            let body = json!({
+                "model": self.endpoint.model,
+                "system": request.system,
+                "prompt": prompt,
+                "options": options,
+                "stream": false,
+            });
+            let response = self.endpoint.post(&body, |builder| builder)?;
+            return text_at(&response, "/response").map(|text| text.trim().to_string());
+        }
+        let body = json!({
+            "model": self.endpoint.model,
+            "messages": with_system(request),
+            "options": options,
+            "stream": false,
+        });
+        let response = self.endpoint.post(&body, |builder| builder)?;
+        text_at(&response, "/message/content").map(|text| text.trim().to_string())
+    }
+}
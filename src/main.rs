@@ -12,14 +12,19 @@ use tower_http::{
 use std::sync::Arc;
 
 use dice6000::api;
-use dice6000::config::Config;
-use dice6000::game::start_game;
+use dice6000::computer::AIPersonality;
+use dice6000::config::{Config, EndMode};
+use dice6000::game::{replay_game, resume_game, start_game};
+use dice6000::mock_llm::{self, MockLlmOptions};
+use dice6000::simulate::{SimulationOptions, print_report, simulate};
+use dice6000::tune::{TuneOptions, personalities_yaml, tune};
 
 fn main() {
     let args: Vec<String> = env::args().collect();
 
     // Parse command line arguments to find config path
     let mut config_path = "config.yaml".to_string(); // default path
+    let mut seed = None;
     let mut command_args = Vec::new();
 
     let mut i = 1;
@@ -40,6 +45,22 @@ fn main() {
                     return;
                 }
             }
+            "--seed" => {
+                match args.get(i + 1).and_then(|value| value.parse::<u64>().ok()) {
+                    Some(value) => {
+                        seed = Some(value);
+                        i += 2;
+                    }
+                    None => {
+                        println!(
+                            "{}",
+                            "Error: --seed option requires a number".bold().red()
+                        );
+                        print_usage(&args[0]);
+                        return;
+                    }
+                }
+            }
             _ => {
                 command_args.push(args[i].clone());
                 i += 1;
@@ -48,13 +69,16 @@ fn main() {
     }
 
     // Load configuration with the specified path
-    let config = Config::load(&config_path).unwrap_or_else(|e| {
+    let mut config = Config::load(&config_path).unwrap_or_else(|e| {
         println!(
             "Warning: Could not load {} ({}), using defaults",
             config_path, e
         );
         Config::init()
     });
+    if seed.is_some() {
+        config.game.seed = seed;
+    }
 
     let openai_key = env::var("OPENAI_API_KEY").is_ok();
     let anthropic_key = env::var("ANTHROPIC_API_KEY").is_ok();
@@ -64,16 +88,55 @@ fn main() {
         return;
     }
 
-    match args[1].as_str() {
+    match command_args[0].as_str() {
         "rules" => {
-            display_rules();
-        }
-        "play" => {
-            run_local_game(openai_key, anthropic_key, &config);
+            display_rules(&config);
         }
+        "play" => match command_args.get(1).map(String::as_str) {
+            None => run_local_game(openai_key, anthropic_key, &config),
+            Some("--resume") => match command_args.get(2) {
+                Some(path) => resume_game(path, &config),
+                None => {
+                    println!("{}", "Error: --resume requires a save file".bold().red());
+                    print_usage(&args[0]);
+                }
+            },
+            Some(other) => {
+                println!("Unknown play option: {}", other);
+                print_usage(&args[0]);
+            }
+        },
         "serve" => {
             run_server_async(&config);
         }
+        "replay" => match command_args.get(1) {
+            Some(path) => replay_game(path),
+            None => {
+                println!("{}", "Error: replay requires a record file".bold().red());
+                print_usage(&args[0]);
+            }
+        },
+        "simulate" => match parse_simulate_args(&command_args[1..], &config) {
+            Ok(options) => run_simulation(&options, &config),
+            Err(e) => {
+                println!("{}", format!("Error: {}", e).bold().red());
+                print_usage(&args[0]);
+            }
+        },
+        "mock-llm" => match parse_mock_llm_args(&command_args[1..], &config) {
+            Ok((options, port)) => run_mock_llm(options, port),
+            Err(e) => {
+                println!("{}", format!("Error: {}", e).bold().red());
+                print_usage(&args[0]);
+            }
+        },
+        "tune" => match parse_tune_args(&command_args[1..], &config) {
+            Ok((options, name, output)) => run_tuning(&options, &name, &output, &config),
+            Err(e) => {
+                println!("{}", format!("Error: {}", e).bold().red());
+                print_usage(&args[0]);
+            }
+        },
         _ => {
             println!("Unknown command: {}", command_args[0]);
             print_usage(&args[0]);
@@ -87,6 +150,212 @@ fn run_local_game(openai_key: bool, anthropic_key: bool, config: &Config) {
     start_game(openai_key, anthropic_key, config);
 }
 
+/// `simulate [--games N] [--threads N] <strategy> <strategy>...`
+fn parse_simulate_args(args: &[String], config: &Config) -> Result<SimulationOptions, String> {
+    let mut options = SimulationOptions {
+        games: 10_000,
+        strategies: Vec::new(),
+        threads: std::thread::available_parallelism().map_or(1, |n| n.get()),
+        seed: config.game.seed.unwrap_or_else(rand::random),
+    };
+
+    let mut i = 0;
+    while i < args.len() {
+        match args[i].as_str() {
+            "--games" => {
+                options.games = args
+                    .get(i + 1)
+                    .and_then(|value| value.parse().ok())
+                    .ok_or("--games requires a number")?;
+                i += 2;
+            }
+            "--threads" => {
+                options.threads = args
+                    .get(i + 1)
+                    .and_then(|value| value.parse().ok())
+                    .filter(|&n| n > 0)
+                    .ok_or("--threads requires a positive number")?;
+                i += 2;
+            }
+            strategy => {
+                options.strategies.push(strategy.to_string());
+                i += 1;
+            }
+        }
+    }
+    Ok(options)
+}
+
+fn run_simulation(options: &SimulationOptions, config: &Config) {
+    println!(
+        "Simulating {} games on {} threads (seed {})...",
+        options.games, options.threads, options.seed
+    );
+    let start = std::time::Instant::now();
+    match simulate(options, config) {
+        Ok(report) => {
+            print_report(&report);
+            println!("Done in {:.2}s", start.elapsed().as_secs_f64());
+        }
+        Err(e) => println!("{}", format!("Error: {}", e).bold().red()),
+    }
+}
+
+/// `tune [--generations N] [--games N] [--threads N] [--from <strategy>]
+/// [--name <name>] [--output <file>]`
+fn parse_tune_args(args: &[String], config: &Config) -> Result<(TuneOptions, String, String), String> {
+    let mut options = TuneOptions {
+        generations: 50,
+        games: 2000,
+        threads: std::thread::available_parallelism().map_or(1, |n| n.get()),
+        seed: config.game.seed.unwrap_or_else(rand::random),
+        start: AIPersonality::Balanced.params(),
+    };
+    let mut name = "tuned".to_string();
+    let mut output = "tuned.yaml".to_string();
+
+    let mut i = 0;
+    while i < args.len() {
+        let value = args.get(i + 1);
+        match args[i].as_str() {
+            "--generations" => {
+                options.generations = value
+                    .and_then(|value| value.parse().ok())
+                    .ok_or("--generations requires a number")?;
+            }
+            "--games" => {
+                options.games = value
+                    .and_then(|value| value.parse().ok())
+                    .filter(|&n| n > 0)
+                    .ok_or("--games requires a positive number")?;
+            }
+            "--threads" => {
+                options.threads = value
+                    .and_then(|value| value.parse().ok())
+                    .filter(|&n| n > 0)
+                    .ok_or("--threads requires a positive number")?;
+            }
+            "--from" => {
+                let from = value.ok_or("--from requires a personality")?;
+                options.start = AIPersonality::from_name(from)
+                    .map(|personality| personality.params())
+                    .or_else(|| config.game.personalities.get(from).copied())
+                    .ok_or(format!("'{}' is not a personality or parameter set", from))?;
+            }
+            "--name" => name = value.ok_or("--name requires a name")?.clone(),
+            "--output" => output = value.ok_or("--output requires a file")?.clone(),
+            other => return Err(format!("unknown tune option '{}'", other)),
+        }
+        i += 2;
+    }
+    Ok((options, name, output))
+}
+
+fn run_tuning(options: &TuneOptions, name: &str, output: &str, config: &Config) {
+    println!(
+        "Tuning over {} generations of {} games (seed {})...",
+        options.generations, options.games, options.seed
+    );
+    let result = tune(options, config, &mut |generation| {
+        let verdict = if generation.accepted { "kept".green() } else { "dropped".dimmed() };
+        println!(
+            "Generation {:>3}: {:.2}% ±{:.2} against the best so far, {}",
+            generation.index,
+            generation.win_rate.mean * 100.0,
+            generation.win_rate.margin * 100.0,
+            verdict
+        );
+    });
+    let best = match result {
+        Ok(best) => best,
+        Err(e) => {
+            println!("{}", format!("Error: {}", e).bold().red());
+            return;
+        }
+    };
+    let yaml = match personalities_yaml(name, &best) {
+        Ok(yaml) => format!("# Written by `tune`: copy under `game:` in the config\n{}", yaml),
+        Err(e) => {
+            println!("{}", format!("Error: {}", e).bold().red());
+            return;
+        }
+    };
+    print!("{}", yaml);
+    match std::fs::write(output, &yaml) {
+        Ok(()) => println!(
+            "Saved to {}. Use it with computer_strategy: \"{}\" once copied into the config.",
+            output, name
+        ),
+        Err(e) => println!("{}", format!("Could not write {}: {}", output, e).red()),
+    }
+}
+
+/// `mock-llm [--port N] [--script <file>] [--bank-at N] [--malformed P]
+/// [--errors P] [--delay-ms N]`
+fn parse_mock_llm_args(args: &[String], config: &Config) -> Result<(MockLlmOptions, u16), String> {
+    let mut options = MockLlmOptions {
+        seed: config.game.seed.unwrap_or_else(rand::random),
+        ..MockLlmOptions::default()
+    };
+    let mut port = 8000;
+    let rate = |value: Option<&String>, option: &str| {
+        value
+            .and_then(|value| value.parse::<f64>().ok())
+            .filter(|rate| (0.0..=1.0).contains(rate))
+            .ok_or(format!("{} requires a rate between 0 and 1", option))
+    };
+
+    let mut i = 0;
+    while i < args.len() {
+        let value = args.get(i + 1);
+        match args[i].as_str() {
+            "--port" => {
+                port = value
+                    .and_then(|value| value.parse().ok())
+                    .ok_or("--port requires a port number")?;
+            }
+            "--script" => {
+                let path = value.ok_or("--script requires a file")?;
+                let script = std::fs::read_to_string(path)
+                    .map_err(|e| format!("could not read {}: {}", path, e))?;
+                options.script = script
+                    .lines()
+                    .map(str::trim)
+                    .filter(|line| !line.is_empty())
+                    .map(str::to_string)
+                    .collect();
+            }
+            "--bank-at" => {
+                options.bank_at = value
+                    .and_then(|value| value.parse().ok())
+                    .ok_or("--bank-at requires a number")?;
+            }
+            "--malformed" => options.malformed_rate = rate(value, "--malformed")?,
+            "--errors" => options.error_rate = rate(value, "--errors")?,
+            "--delay-ms" => {
+                let ms = value
+                    .and_then(|value| value.parse().ok())
+                    .ok_or("--delay-ms requires a number")?;
+                options.delay = std::time::Duration::from_millis(ms);
+            }
+            other => return Err(format!("unknown mock-llm option '{}'", other)),
+        }
+        i += 2;
+    }
+    Ok((options, port))
+}
+
+#[tokio::main]
+async fn run_mock_llm(options: MockLlmOptions, port: u16) {
+    let socket_addr = SocketAddr::from(([127, 0, 0, 1], port));
+    println!("Mock LLM listening on http://{}", socket_addr);
+    println!("  OpenAI:    http://{}/v1/chat/completions", socket_addr);
+    println!("  Anthropic: http://{}/v1/messages", socket_addr);
+    println!("  Ollama:    http://{}/api/chat (or /api/generate)", socket_addr);
+    let listener = TcpListener::bind(socket_addr).await.unwrap();
+    axum::serve(listener, mock_llm::create_router(options)).await.unwrap();
+}
+
 #[tokio::main]
 async fn run_server_async(config: &Config) {
     run_api_server(config).await;
@@ -94,9 +363,13 @@ async fn run_server_async(config: &Config) {
 
 async fn run_api_server(config: &Config) {
     // Create the main router
+    // Games are kept where the config says, and expired ones dropped as the server runs
+    let game_store = api::routes::open_store(config);
+    api::handlers::spawn_expiry(&game_store, config);
+
     let app = Router::new()
         // API routes
-        .merge(api::routes::create_router(Arc::new(config.clone())))
+        .merge(api::routes::create_router_with_store(Arc::new(config.clone()), game_store))
         // Serve static files from web/static directory
         .nest_service("/static", ServeDir::new("src/web/static"))
         // Serve index.html at root
@@ -127,37 +400,69 @@ fn print_usage(program_name: &str) {
     println!();
     println!("Options:");
     println!("  -c, --config <path>    Path to configuration file (default: config.yaml)");
+    println!("  --seed <number>        Dice seed, to replay the same rolls");
     println!();
     println!("Commands:");
     println!("  rules                  Display the game rules");
     println!("  play                   Play the local game");
+    println!("  play --resume <file>   Continue a saved game");
     println!("  serve                 Start server");
+    println!("  replay <file>          Step through a recorded game");
+    println!("  simulate [--games N] [--threads N] <strategy> <strategy>...");
+    println!("                         Play computer strategies against each other");
+    println!("                         (conservative, balanced, aggressive, adaptive,");
+    println!("                         optimal, win_probability, monte_carlo)");
+    println!("  tune [--generations N] [--games N] [--from <personality>] [--name <name>] [--output <file>]");
+    println!("                         Tune the rule-based computer by self-play");
+    println!("  mock-llm [--port N] [--script <file>] [--bank-at N] [--malformed P] [--errors P] [--delay-ms N]");
+    println!("                         Serve fake OpenAI, Anthropic and Ollama APIs on localhost");
     println!();
     println!("Examples:");
     println!("  {} play", program_name);
     println!("  {} --config my_config.yaml play", program_name);
     println!("  {} -c /path/to/config.yaml rules", program_name);
+    println!("  {} --seed 42 play", program_name);
+    println!("  {} replay records/<game id>.jsonl", program_name);
+    println!("  {} simulate --games 1000000 conservative aggressive", program_name);
 }
 
-fn display_rules() {
+fn display_rules(config: &Config) {
     println!("{}", "SIX THOUSAND DICE GAME RULES".bold().green());
     println!("{}", "============================".green());
     println!();
     println!("OBJECTIVE:");
-    println!("  Be the first player to reach or exceed 6,000 points.");
+    let target = config.game.target_score;
+    match config.game.end_mode {
+        EndMode::Reach => println!("  Be the first player to reach or exceed {} points.", target),
+        EndMode::Exact => {
+            println!("  Be the first player to reach exactly {} points.", target);
+            println!("  Going over the target busts the turn.");
+        }
+        EndMode::FinalRound => {
+            println!("  Finish with the highest score once someone passes {} points.", target);
+            println!("  Every other player gets one last turn after the target is passed.");
+        }
+    }
     println!();
     println!("GAMEPLAY:");
     println!("  1. Players take turns rolling six dice");
     println!("  2. After each roll, you must set aside at least one scoring die");
     println!("  3. You may continue rolling with remaining dice or bank your points");
     println!("  4. If you cannot score with a roll, you lose all points from that turn");
+    let mut step = 5;
+    if let Some(points) = config.game.opening_score.filter(|&p| p > 0) {
+        println!("  {}. Your first bank must be at least {} points (getting on the board)", step, points);
+        step += 1;
+    }
+    if let Some(penalty) = config.game.bust_penalty.filter(|p| p.streak > 0) {
+        println!(
+            "  {}. Busting {} turns in a row costs {} points",
+            step, penalty.streak, penalty.points
+        );
+    }
     println!();
     println!("SCORING COMBINATIONS:");
-    println!("  - Straight (1-2-3-4-5-6): 2000 points, all dice used");
-    println!("  - Three pairs: 1500 points, all dice used");
-    println!("  - Six of a kind: face value × 1000 points");
-    println!("  - Three 1s: 1000 points");
-    println!("  - Three of 2-6: face value × 100 points");
-    println!("  - Single 1: 100 points each");
-    println!("  - Single 5: 50 points each");
+    for line in config.game.rules.describe() {
+        println!("  - {}", line);
+    }
 }
@@ -0,0 +1,213 @@
+//! Fake LLM endpoints, to play against the LLM players with no network.
+//!
+//! The `mock-llm` command serves OpenAI's chat completions
+//! (`/v1/chat/completions`), Anthropic's messages (`/v1/messages`) and
+//! Ollama's chat and generate APIs (`/api/chat`, `/api/generate`). Decisions
+//! come from a script or from a simple rule reading the keep options off the
+//! prompt, and malformed answers, delays and HTTP errors can be injected.
+
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use axum::extract::State;
+use axum::http::StatusCode;
+use axum::routing::post;
+use axum::{Json, Router};
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+use serde_json::{Value, json};
+
+/// Answer to the prompts that are not decisions (bust reactions).
+const REACTION: &str = "Nooo! Those dice are cursed, I will get them back next turn.";
+
+/// An answer the decision parser cannot read.
+const MALFORMED: &str = "Hmm, I think I will keep the first option and roll again.";
+
+pub struct MockLlmOptions {
+    /// Answers served in turn, over and over; `!<status>` answers with that
+    /// HTTP status. Decisions follow the rule when empty.
+    pub script: Vec<String>,
+    /// Turn score from which the rule takes the points.
+    pub bank_at: u32,
+    /// Share of decisions answered with prose instead of JSON.
+    pub malformed_rate: f64,
+    /// Share of requests answered with a 503.
+    pub error_rate: f64,
+    /// Wait before every answer.
+    pub delay: Duration,
+    pub seed: u64,
+}
+
+impl Default for MockLlmOptions {
+    fn default() -> Self {
+        MockLlmOptions {
+            script: Vec::new(),
+            bank_at: 350,
+            malformed_rate: 0.0,
+            error_rate: 0.0,
+            delay: Duration::ZERO,
+            seed: 0,
+        }
+    }
+}
+
+struct MockLlm {
+    options: MockLlmOptions,
+    rng: Mutex<ChaCha8Rng>,
+    /// Next line of the script.
+    next: AtomicUsize,
+}
+
+type SharedMock = Arc<MockLlm>;
+
+/// Routes of the fake OpenAI, Anthropic and Ollama APIs.
+pub fn create_router(options: MockLlmOptions) -> Router {
+    let mock = Arc::new(MockLlm {
+        rng: Mutex::new(ChaCha8Rng::seed_from_u64(options.seed)),
+        next: AtomicUsize::new(0),
+        options,
+    });
+    Router::new()
+        .route("/v1/chat/completions", post(openai_handler))
+        .route("/v1/messages", post(anthropic_handler))
+        .route("/api/chat", post(ollama_chat_handler))
+        .route("/api/generate", post(ollama_generate_handler))
+        .with_state(mock)
+}
+
+async fn openai_handler(State(mock): State<SharedMock>, Json(body): Json<Value>) -> Result<Json<Value>, StatusCode> {
+    let answer = mock.answer(&user_messages(&body["messages"])).await?;
+    Ok(Json(json!({
+        "object": "chat.completion",
+        "model": body["model"],
+        "choices": [{
+            "index": 0,
+            "message": { "role": "assistant", "content": answer },
+            "finish_reason": "stop",
+        }],
+    })))
+}
+
+async fn anthropic_handler(State(mock): State<SharedMock>, Json(body): Json<Value>) -> Result<Json<Value>, StatusCode> {
+    let answer = mock.answer(&user_messages(&body["messages"])).await?;
+    Ok(Json(json!({
+        "type": "message",
+        "role": "assistant",
+        "model": body["model"],
+        "content": [{ "type": "text", "text": answer }],
+        "stop_reason": "end_turn",
+    })))
+}
+
+async fn ollama_chat_handler(State(mock): State<SharedMock>, Json(body): Json<Value>) -> Result<Json<Value>, StatusCode> {
+    let answer = mock.answer(&user_messages(&body["messages"])).await?;
+    Ok(Json(json!({
+        "model": body["model"],
+        "message": { "role": "assistant", "content": answer },
+        "done": true,
+    })))
+}
+
+async fn ollama_generate_handler(
+    State(mock): State<SharedMock>,
+    Json(body): Json<Value>,
+) -> Result<Json<Value>, StatusCode> {
+    let prompt = body["prompt"].as_str().unwrap_or_default().to_string();
+    let answer = mock.answer(&[prompt]).await?;
+    Ok(Json(json!({
+        "model": body["model"],
+        "response": answer,
+        "done": true,
+    })))
+}
+
+/// Text of the user messages of a conversation, in order.
+fn user_messages(messages: &Value) -> Vec<String> {
+    messages
+        .as_array()
+        .into_iter()
+        .flatten()
+        .filter(|message| message["role"] == "user")
+        .filter_map(|message| message["content"].as_str())
+        .map(str::to_string)
+        .collect()
+}
+
+impl MockLlm {
+    /// Answer to a conversation whose user messages are `prompts`.
+    async fn answer(&self, prompts: &[String]) -> Result<String, StatusCode> {
+        if !self.options.delay.is_zero() {
+            tokio::time::sleep(self.options.delay).await;
+        }
+        let (error, malformed) = {
+            let mut rng = self.rng.lock().expect("mock rng poisoned");
+            (
+                rng.gen_bool(self.options.error_rate),
+                rng.gen_bool(self.options.malformed_rate),
+            )
+        };
+        if error {
+            return Err(StatusCode::SERVICE_UNAVAILABLE);
+        }
+
+        let script = &self.options.script;
+        if !script.is_empty() {
+            let line = &script[self.next.fetch_add(1, Ordering::Relaxed) % script.len()];
+            if let Some(status) = line.strip_prefix('!') {
+                return Err(status
+                    .trim()
+                    .parse()
+                    .ok()
+                    .and_then(|status| StatusCode::from_u16(status).ok())
+                    .unwrap_or(StatusCode::INTERNAL_SERVER_ERROR));
+            }
+            return Ok(line.clone());
+        }
+
+        // Corrections follow the decision prompt, which holds the options
+        match prompts.iter().rev().find(|prompt| prompt.contains("Keep options:")) {
+            None => Ok(REACTION.to_string()),
+            Some(_) if malformed => Ok(MALFORMED.to_string()),
+            Some(prompt) => Ok(rule_decision(prompt, self.options.bank_at).to_string()),
+        }
+    }
+}
+
+/// Decision of the rule for a decision prompt: the keep with the most points,
+/// counting 50 per die left to roll, and take the points from `bank_at`.
+pub fn rule_decision(prompt: &str, bank_at: u32) -> Value {
+    let mut turn_score = 0;
+    // (number, points, dice left)
+    let mut options = Vec::new();
+    for line in prompt.lines().map(str::trim) {
+        if let Some(score) = line.strip_prefix("- Turn score:") {
+            turn_score = score.trim().parse().unwrap_or(0);
+        } else if let Some(option) = parse_keep_option(line) {
+            options.push(option);
+        }
+    }
+
+    let value = |&(_, points, dice_left): &(usize, u32, u32)| {
+        points + 50 * if dice_left == 0 { 6 } else { dice_left }
+    };
+    let Some(&(keep, points, dice_left)) = options.iter().max_by_key(|option| value(option)) else {
+        return json!({ "keep": 1, "decision": "T", "explanation": "I could not read the options, taking the points." });
+    };
+    let total = turn_score + points;
+    let decision = if total >= bank_at { "T" } else { "R" };
+    json!({
+        "keep": keep,
+        "decision": decision,
+        "explanation": format!("Keeping {} points with {} dice left, {} this turn.", points, dice_left, total),
+    })
+}
+
+/// `N) keep [..] for P points, D dice left to roll` as (N, P, D).
+fn parse_keep_option(line: &str) -> Option<(usize, u32, u32)> {
+    let (number, rest) = line.split_once(") keep ")?;
+    let (_, rest) = rest.split_once(" for ")?;
+    let (points, rest) = rest.split_once(" points, ")?;
+    let (dice_left, _) = rest.split_once(" dice left")?;
+    Some((number.parse().ok()?, points.parse().ok()?, dice_left.parse().ok()?))
+}
@@ -0,0 +1,148 @@
+//! Monte Carlo rollout computer player.
+//!
+//! Every candidate action (each keep, or banking against rolling on) is
+//! rated by playing the rest of the turn many times with random dice and a
+//! simple rollout policy, through the game engine itself. Whatever the
+//! house rules (opening score, exact finish, bust penalty, custom scoring),
+//! the rollouts follow them, and nothing has to be solved beforehand.
+
+use std::time::{Duration, Instant};
+
+use crate::config::{GameConfig, RolloutBudget};
+use crate::dice::SeededDice;
+use crate::engine::{Action, GameState, NUM_DICE, TurnPhase};
+use crate::score::KeepOption;
+use crate::strategy::Strategy;
+
+/// Turn score from which rollouts bank, by dice left. Six dice always roll.
+const ROLLOUT_BANK_FROM: [u32; NUM_DICE - 1] = [300, 300, 400, 1000, 2500];
+
+/// Computer player rating its options with random rollouts.
+pub struct MonteCarloPlayer {
+    budget: RolloutBudget,
+    /// Seed of the next decision's rollouts, so a game replays identically
+    /// with a rollout-count budget.
+    next_seed: u64,
+}
+
+impl MonteCarloPlayer {
+    pub fn new(budget: RolloutBudget) -> Self {
+        MonteCarloPlayer { budget, next_seed: 0 }
+    }
+
+    pub fn from_config(config: &GameConfig) -> Self {
+        Self::new(config.rollout_budget)
+    }
+
+    /// Mean points each candidate earns over the turn, by candidate; with
+    /// `roll_first` every rollout starts with a roll.
+    ///
+    /// Candidates are played in turns so a time budget is shared fairly,
+    /// and rollout `i` uses the same dice for every candidate.
+    fn rate(&mut self, candidates: &[GameState], roll_first: bool) -> Vec<f64> {
+        let seed = self.next_seed;
+        self.next_seed = self.next_seed.wrapping_add(1 << 32);
+
+        let mut totals = vec![0.0; candidates.len()];
+        let mut rollouts = 0u64;
+        let deadline = match self.budget {
+            RolloutBudget::Millis(ms) => Some(Instant::now() + Duration::from_millis(ms)),
+            RolloutBudget::Rollouts(_) => None,
+        };
+        let per_candidate = match self.budget {
+            RolloutBudget::Rollouts(n) => (n as u64 / candidates.len().max(1) as u64).max(1),
+            RolloutBudget::Millis(_) => u64::MAX,
+        };
+        while rollouts < per_candidate {
+            for (total, candidate) in totals.iter_mut().zip(candidates) {
+                let mut dice = SeededDice::new(seed.wrapping_add(rollouts));
+                *total += rollout(candidate.clone(), &mut dice, roll_first);
+            }
+            rollouts += 1;
+            if deadline.is_some_and(|deadline| Instant::now() >= deadline) {
+                break;
+            }
+        }
+        totals.into_iter().map(|total| total / rollouts as f64).collect()
+    }
+}
+
+impl Strategy for MonteCarloPlayer {
+    fn choose_keep(&mut self, state: &GameState) -> Vec<usize> {
+        if state.keep_options.len() <= 1 {
+            return state
+                .keep_options
+                .first()
+                .map(|option| option.indices.clone())
+                .unwrap_or_default();
+        }
+        let candidates: Vec<GameState> = state
+            .keep_options
+            .iter()
+            .map(|option| {
+                let mut candidate = state.clone();
+                candidate
+                    .apply(Action::Keep(option.indices.clone()), &mut |_: usize| Vec::new())
+                    .expect("keep options are always valid");
+                candidate
+            })
+            .collect();
+        let values = self.rate(&candidates, false);
+        let best = (0..values.len())
+            .max_by(|&a, &b| values[a].total_cmp(&values[b]))
+            .unwrap_or(0);
+        state.keep_options[best].indices.clone()
+    }
+
+    fn should_bank(&mut self, state: &GameState) -> bool {
+        let turn_score = state.current_player().turn_score;
+        if state.must_roll() || state.bank_limits().check(turn_score).is_err() {
+            return false;
+        }
+        // Banking is certain, only rolling on needs rollouts
+        self.rate(std::slice::from_ref(state), true)[0] <= turn_score as f64
+    }
+}
+
+/// Play the rest of the turn with the rollout policy; points it earned.
+fn rollout(mut state: GameState, dice: &mut SeededDice, mut force_roll: bool) -> f64 {
+    let index = state.current_player_index;
+    let before = state.players[index].score as f64;
+    loop {
+        let action = match state.phase() {
+            TurnPhase::TurnOver | TurnPhase::GameOver => break,
+            TurnPhase::AwaitingKeep => Action::Keep(rollout_keep(&state).indices.clone()),
+            TurnPhase::AwaitingRoll => {
+                let player = state.current_player();
+                let bank_from = ROLLOUT_BANK_FROM.get(state.dice_count - 1).copied();
+                if force_roll
+                    || state.must_roll()
+                    || bank_from.is_none_or(|bank_from| player.turn_score < bank_from)
+                {
+                    Action::Roll
+                } else {
+                    Action::Bank
+                }
+            }
+        };
+        // A refused bank (opening score) rolls on
+        force_roll = state.apply(action, dice).is_err();
+    }
+    state.players[index].score as f64 - before
+}
+
+/// The keep with the most points, counting 50 per die left to roll.
+fn rollout_keep(state: &GameState) -> &KeepOption {
+    let limits = state.bank_limits();
+    let turn_score = state.current_player().turn_score;
+    let dice_left = |option: &KeepOption| match state.dice.len() - option.dice.len() {
+        0 => NUM_DICE as u32,
+        n => n as u32,
+    };
+    state
+        .keep_options
+        .iter()
+        .filter(|option| !limits.overshoots(turn_score + option.score))
+        .max_by_key(|option| option.score + 50 * dice_left(option))
+        .unwrap_or(&state.keep_options[0])
+}
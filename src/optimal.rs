@@ -0,0 +1,248 @@
+//! Expected-value optimal computer player.
+//!
+//! For every (turn score, dice left) state the solver computes the turn
+//! score the player ends up banking on average if it rolls, assuming
+//! optimal play afterwards. Scores only grow within a turn, so one pass
+//! from the highest turn score down solves the whole table.
+//!
+//! Tables depend on the scoring rules and the bank limits (opening score,
+//! exact finish); they are computed on first use and shared afterwards.
+
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex, OnceLock};
+
+use crate::config::BankLimits;
+use crate::engine::{GameState, NUM_DICE};
+use crate::rules::RuleSet;
+use crate::score::{KeepOption, keep_options};
+use crate::strategy::Strategy;
+
+/// Turn scores from which the player always banks when it may. Rolling six
+/// dice stops paying off well below this.
+const SCORE_CAP: u32 = 30_000;
+
+/// Every distinct roll of `n` dice with its probability and keeps.
+pub(crate) struct RollOutcome {
+    pub probability: f64,
+    pub options: Vec<KeepOption>,
+}
+
+pub struct EvTable {
+    /// Every turn score is a multiple of this.
+    step: u32,
+    cap: u32,
+    limits: BankLimits,
+    /// Expected banked score when rolling, by `[turn_score / step][dice - 1]`.
+    roll: Vec<[f64; NUM_DICE]>,
+}
+
+type TableKey = (RuleSet, u32, Option<u32>);
+
+fn tables() -> &'static Mutex<HashMap<TableKey, Arc<EvTable>>> {
+    static TABLES: OnceLock<Mutex<HashMap<TableKey, Arc<EvTable>>>> = OnceLock::new();
+    TABLES.get_or_init(Mutex::default)
+}
+
+impl EvTable {
+    /// Table for these rules and limits, solved on first use.
+    pub fn get(rules: &RuleSet, limits: BankLimits) -> Arc<EvTable> {
+        let key = (rules.clone(), limits.min, limits.max);
+        if let Some(table) = tables().lock().expect("table cache poisoned").get(&key) {
+            return table.clone();
+        }
+        // Solve outside the lock, another thread may solve the same table meanwhile
+        let table = Arc::new(Self::solve(rules, limits));
+        tables()
+            .lock()
+            .expect("table cache poisoned")
+            .entry(key)
+            .or_insert(table)
+            .clone()
+    }
+
+    pub fn solve(rules: &RuleSet, limits: BankLimits) -> EvTable {
+        let outcomes: Vec<Vec<RollOutcome>> = (1..=NUM_DICE).map(|n| roll_outcomes(n, rules)).collect();
+        let step = outcomes
+            .iter()
+            .flatten()
+            .flat_map(|outcome| &outcome.options)
+            .fold(0, |step, option| gcd(step, option.score))
+            .max(1);
+        let cap = match limits.max {
+            // Anything above the exact target busts
+            Some(max) => max - max % step,
+            None => SCORE_CAP - SCORE_CAP % step,
+        };
+
+        let mut table = EvTable {
+            step,
+            cap,
+            limits,
+            roll: vec![[0.0; NUM_DICE]; (cap / step) as usize + 1],
+        };
+        for index in (0..table.roll.len()).rev() {
+            let turn_score = index as u32 * step;
+            for dice in 1..=NUM_DICE {
+                // Busts are worth nothing, otherwise take the best keep
+                let value = outcomes[dice - 1]
+                    .iter()
+                    .map(|outcome| {
+                        let best = outcome
+                            .options
+                            .iter()
+                            .map(|option| table.keep_value(turn_score, dice, option))
+                            .fold(0.0, f64::max);
+                        outcome.probability * best
+                    })
+                    .sum();
+                table.roll[index][dice - 1] = value;
+            }
+        }
+        table
+    }
+
+    /// Expected banked score of a turn played optimally from the start.
+    pub fn expected_turn_score(&self) -> f64 {
+        self.roll_value(0, NUM_DICE)
+    }
+
+    /// Expected banked score when rolling `dice` dice with `turn_score` at stake.
+    pub fn roll_value(&self, turn_score: u32, dice: usize) -> f64 {
+        if turn_score > self.cap {
+            return self.bank_value(turn_score).unwrap_or(0.0);
+        }
+        self.roll[(turn_score / self.step) as usize][dice - 1]
+    }
+
+    /// Value of `turn_score` if banked now, None if banking is not allowed.
+    fn bank_value(&self, turn_score: u32) -> Option<f64> {
+        if self.limits.overshoots(turn_score) {
+            Some(0.0)
+        } else if turn_score > 0 && self.limits.check(turn_score).is_ok() {
+            Some(turn_score as f64)
+        } else {
+            None
+        }
+    }
+
+    /// Expected banked score after taking `option` from a roll of `dice` dice.
+    pub fn keep_value(&self, turn_score: u32, dice: usize, option: &KeepOption) -> f64 {
+        let turn_score = turn_score + option.score;
+        if self.limits.overshoots(turn_score) {
+            return 0.0;
+        }
+        if option.ends_turn {
+            // Banked right away, or lost under the opening score
+            return self.bank_value(turn_score).unwrap_or(0.0);
+        }
+        match dice - option.dice.len() {
+            // Hot dice must be rolled again
+            0 => self.roll_value(turn_score, NUM_DICE),
+            left => self.state_value(turn_score, left),
+        }
+    }
+
+    /// Expected banked score with `dice` dice to roll, choosing the best of
+    /// banking and rolling.
+    pub fn state_value(&self, turn_score: u32, dice: usize) -> f64 {
+        let roll = self.roll_value(turn_score, dice);
+        match self.bank_value(turn_score) {
+            Some(bank) if turn_score > self.cap => bank,
+            Some(bank) => bank.max(roll),
+            None => roll,
+        }
+    }
+
+    /// True when rolling beats banking.
+    pub fn should_roll(&self, turn_score: u32, dice: usize) -> bool {
+        match self.bank_value(turn_score) {
+            Some(bank) => turn_score <= self.cap && self.roll_value(turn_score, dice) > bank,
+            None => true,
+        }
+    }
+}
+
+/// Every multiset of `n` dice, with its probability and keeps.
+pub(crate) fn roll_outcomes(n: usize, rules: &RuleSet) -> Vec<RollOutcome> {
+    let total = 6f64.powi(n as i32);
+    let mut outcomes = Vec::new();
+    let mut roll = Vec::with_capacity(n);
+    collect_rolls(n, 1, &mut roll, &mut |roll| {
+        outcomes.push(RollOutcome {
+            probability: permutations(roll) / total,
+            options: keep_options(roll, rules),
+        });
+    });
+    outcomes
+}
+
+fn collect_rolls(n: usize, face: u8, roll: &mut Vec<u8>, visit: &mut dyn FnMut(&[u8])) {
+    if roll.len() == n {
+        visit(roll);
+        return;
+    }
+    for next in face..=6 {
+        roll.push(next);
+        collect_rolls(n, next, roll, visit);
+        roll.pop();
+    }
+}
+
+/// Number of ordered rolls that give this sorted roll.
+fn permutations(roll: &[u8]) -> f64 {
+    let factorial = |n: usize| (1..=n).map(|i| i as f64).product::<f64>();
+    let mut counts = [0usize; 7];
+    for &d in roll {
+        counts[d as usize] += 1;
+    }
+    counts.iter().fold(factorial(roll.len()), |acc, &c| acc / factorial(c))
+}
+
+pub(crate) fn gcd(a: u32, b: u32) -> u32 {
+    if b == 0 { a } else { gcd(b, a % b) }
+}
+
+/// Computer player that maximises the expected score of each turn.
+#[derive(Default)]
+pub struct OptimalPlayer {
+    table: Option<Arc<EvTable>>,
+}
+
+impl OptimalPlayer {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn table(&mut self, state: &GameState) -> Arc<EvTable> {
+        let limits = state.bank_limits();
+        match &self.table {
+            Some(table) if table.limits == limits => table.clone(),
+            _ => {
+                let table = EvTable::get(&state.rules, limits);
+                self.table = Some(table.clone());
+                table
+            }
+        }
+    }
+}
+
+impl Strategy for OptimalPlayer {
+    fn choose_keep(&mut self, state: &GameState) -> Vec<usize> {
+        let table = self.table(state);
+        let turn_score = state.current_player().turn_score;
+        let mut best: Option<(&KeepOption, f64)> = None;
+        for option in &state.keep_options {
+            let value = table.keep_value(turn_score, state.dice.len(), option);
+            if best.is_none_or(|(_, best)| value > best) {
+                best = Some((option, value));
+            }
+        }
+        best.map(|(option, _)| option.indices.clone()).unwrap_or_default()
+    }
+
+    fn should_bank(&mut self, state: &GameState) -> bool {
+        !self
+            .table(state)
+            .should_roll(state.current_player().turn_score, state.dice_count)
+    }
+}
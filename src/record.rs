@@ -0,0 +1,206 @@
+//! Game records: a finished (or interrupted) game as JSON Lines.
+//!
+//! The first line is a [`RecordHeader`] with the starting state, then one
+//! engine [`Event`] per line. Replaying a record runs the events back
+//! through the engine, so a record that does not follow the rules is
+//! reported instead of being shown.
+
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::Path;
+
+use crate::dice::ScriptedDice;
+use crate::engine::{Action, Event, GameState};
+
+/// Bumped whenever the record format changes in an incompatible way.
+pub const RECORD_VERSION: u32 = 1;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RecordHeader {
+    pub version: u32,
+    /// Dice seed the game was played with, when known.
+    pub seed: Option<u64>,
+    /// State before the first roll.
+    pub state: GameState,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GameRecord {
+    pub header: RecordHeader,
+    pub events: Vec<Event>,
+}
+
+#[derive(Debug)]
+pub enum RecordError {
+    Io(io::Error),
+    Empty,
+    /// A line (1-based) is not valid JSON for its position.
+    Parse { line: usize, error: serde_json::Error },
+    UnsupportedVersion(u32),
+    /// The event at this index does not follow from the previous ones.
+    Diverged { index: usize, event: Event },
+}
+
+impl fmt::Display for RecordError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RecordError::Io(e) => write!(f, "{}", e),
+            RecordError::Empty => write!(f, "the record is empty"),
+            RecordError::Parse { line, error } => write!(f, "line {}: {}", line, error),
+            RecordError::UnsupportedVersion(version) => write!(
+                f,
+                "record version {} is not supported (expected {})",
+                version, RECORD_VERSION
+            ),
+            RecordError::Diverged { index, event } => {
+                write!(f, "event {} ({:?}) does not follow the rules", index + 1, event)
+            }
+        }
+    }
+}
+
+impl std::error::Error for RecordError {}
+
+impl From<io::Error> for RecordError {
+    fn from(e: io::Error) -> Self {
+        RecordError::Io(e)
+    }
+}
+
+impl GameRecord {
+    /// Start a record from the state before the first roll.
+    pub fn new(state: &GameState, seed: Option<u64>) -> Self {
+        GameRecord {
+            header: RecordHeader {
+                version: RECORD_VERSION,
+                seed,
+                state: state.clone(),
+            },
+            events: Vec::new(),
+        }
+    }
+
+    pub fn push(&mut self, event: Event) {
+        self.events.push(event);
+    }
+
+    pub fn extend(&mut self, events: impl IntoIterator<Item = Event>) {
+        self.events.extend(events);
+    }
+
+    pub fn to_jsonl(&self) -> String {
+        let mut out = serde_json::to_string(&self.header).expect("records always serialize");
+        out.push('\n');
+        for event in &self.events {
+            out.push_str(&serde_json::to_string(event).expect("records always serialize"));
+            out.push('\n');
+        }
+        out
+    }
+
+    pub fn from_jsonl(text: &str) -> Result<Self, RecordError> {
+        let mut lines = text
+            .lines()
+            .enumerate()
+            .filter(|(_, line)| !line.trim().is_empty());
+
+        let (_, first) = lines.next().ok_or(RecordError::Empty)?;
+        // Check the version before the rest of the header, which may have changed
+        let version: serde_json::Value =
+            serde_json::from_str(first).map_err(|error| RecordError::Parse { line: 1, error })?;
+        let version = version["version"].as_u64().unwrap_or(0) as u32;
+        if version != RECORD_VERSION {
+            return Err(RecordError::UnsupportedVersion(version));
+        }
+        let header: RecordHeader =
+            serde_json::from_str(first).map_err(|error| RecordError::Parse { line: 1, error })?;
+
+        let events = lines
+            .map(|(i, line)| {
+                serde_json::from_str(line).map_err(|error| RecordError::Parse { line: i + 1, error })
+            })
+            .collect::<Result<Vec<Event>, _>>()?;
+
+        Ok(GameRecord { header, events })
+    }
+
+    pub fn save(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        if let Some(dir) = path.as_ref().parent() {
+            fs::create_dir_all(dir)?;
+        }
+        fs::write(path, self.to_jsonl())
+    }
+
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, RecordError> {
+        Self::from_jsonl(&fs::read_to_string(path)?)
+    }
+
+    /// Play the record back through the engine, calling `on_event` with the
+    /// state right after each event. Returns the final state.
+    pub fn replay(
+        &self,
+        on_event: &mut dyn FnMut(&GameState, &Event),
+    ) -> Result<GameState, RecordError> {
+        let mut state = self.header.state.clone();
+        let mut dice = ScriptedDice::new(
+            self.events
+                .iter()
+                .filter_map(|event| match event {
+                    Event::Rolled { dice, .. } => Some(dice.clone()),
+                    _ => None,
+                })
+                .collect(),
+        );
+        // Events the engine produced that the record has not reached yet
+        let mut pending: VecDeque<Event> = VecDeque::new();
+
+        for (index, event) in self.events.iter().enumerate() {
+            let diverged = || RecordError::Diverged {
+                index,
+                event: event.clone(),
+            };
+
+            // Front-end notes, nothing to check
+            if !matches!(
+                event,
+                Event::BankRejected { .. } | Event::KeepRejected { .. } | Event::Explanation { .. }
+            ) {
+                if pending.is_empty() {
+                    let action = match event {
+                        Event::Rolled { .. } => Action::Roll,
+                        Event::Kept { dice: kept, .. } => {
+                            Action::Keep(keep_indices(&state.dice, kept).ok_or_else(diverged)?)
+                        }
+                        Event::Banked { .. } => Action::Bank,
+                        Event::TurnStarted { .. } | Event::FinalRound { .. } | Event::GameOver { .. } => {
+                            Action::NextPlayer
+                        }
+                        _ => return Err(diverged()),
+                    };
+                    pending.extend(state.apply(action, &mut dice).map_err(|_| diverged())?);
+                }
+                if pending.pop_front().as_ref() != Some(event) {
+                    return Err(diverged());
+                }
+            }
+            on_event(&state, event);
+        }
+
+        Ok(state)
+    }
+}
+
+/// Positions in `roll` of the kept dice values.
+fn keep_indices(roll: &[u8], kept: &[u8]) -> Option<Vec<usize>> {
+    let mut used = vec![false; roll.len()];
+    kept.iter()
+        .map(|value| {
+            let i = (0..roll.len()).find(|&i| !used[i] && roll[i] == *value)?;
+            used[i] = true;
+            Some(i)
+        })
+        .collect()
+}
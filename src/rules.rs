@@ -0,0 +1,201 @@
+use serde::{Deserialize, Serialize};
+
+/// How an N-of-a-kind combination is scored.
+///
+/// In YAML: `!face_times 1000`, `!triple_times 2` or `!fixed 2000`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum KindScore {
+    /// Face value × n.
+    FaceTimes(u32),
+    /// The three-of-a-kind value of the same face × n (doubled triples).
+    TripleTimes(u32),
+    /// A flat number of points.
+    Fixed(u32),
+}
+
+/// Scoring rule set, loaded from the `game.rules` section of the config.
+///
+/// Missing keys fall back to the classic 6000 rules, so an empty section
+/// (or no section at all) plays exactly like the original table.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RuleSet {
+    /// Points for 1-2-3-4-5-6, or None to disable the straight.
+    pub straight: Option<u32>,
+    /// When true, scoring a straight banks the turn immediately.
+    pub straight_ends_turn: bool,
+    /// Points for three pairs, or None to disable them.
+    pub three_pairs: Option<u32>,
+    /// Points for three 1s.
+    pub three_ones: u32,
+    /// Three of 2-6 score face value × this multiplier.
+    pub three_of_a_kind_multiplier: u32,
+    pub four_of_a_kind: Option<KindScore>,
+    pub five_of_a_kind: Option<KindScore>,
+    pub six_of_a_kind: Option<KindScore>,
+    pub single_one: u32,
+    pub single_five: u32,
+}
+
+impl Default for RuleSet {
+    fn default() -> Self {
+        RuleSet {
+            straight: Some(2000),
+            straight_ends_turn: false,
+            three_pairs: Some(1500),
+            three_ones: 1000,
+            three_of_a_kind_multiplier: 100,
+            four_of_a_kind: None,
+            five_of_a_kind: None,
+            six_of_a_kind: Some(KindScore::FaceTimes(1000)),
+            single_one: 100,
+            single_five: 50,
+        }
+    }
+}
+
+/// Score of a set of dice under a rule set.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RuleScore {
+    pub score: u32,
+    /// Count of non-scoring dice per face (index 0 unused).
+    pub remaining_counts: [u32; 7],
+    /// True when the dice include a combination that ends the turn.
+    pub ends_turn: bool,
+}
+
+impl RuleSet {
+    /// Value of three dice showing `face`.
+    pub fn triple_value(&self, face: u32) -> u32 {
+        if face == 1 {
+            self.three_ones
+        } else {
+            face * self.three_of_a_kind_multiplier
+        }
+    }
+
+    fn kind_rule(&self, size: u32) -> Option<KindScore> {
+        match size {
+            3 => Some(KindScore::TripleTimes(1)),
+            4 => self.four_of_a_kind,
+            5 => self.five_of_a_kind,
+            6 => self.six_of_a_kind,
+            _ => None,
+        }
+    }
+
+    fn kind_value(&self, rule: KindScore, face: u32) -> u32 {
+        match rule {
+            KindScore::FaceTimes(n) => face * n,
+            KindScore::TripleTimes(n) => self.triple_value(face) * n,
+            KindScore::Fixed(n) => n,
+        }
+    }
+
+    fn single_value(&self, face: u32) -> u32 {
+        match face {
+            1 => self.single_one,
+            5 => self.single_five,
+            _ => 0,
+        }
+    }
+
+    /// Score dice the classic way: whole-roll combinations (straight, three
+    /// pairs) first, then the largest enabled N-of-a-kinds per face, then
+    /// single 1s and 5s.
+    pub fn score(&self, dice: &[u8]) -> RuleScore {
+        let mut counts: [u32; 7] = [0; 7];
+        for &d in dice {
+            counts[d as usize] += 1;
+        }
+        self.score_counts(counts)
+    }
+
+    /// Same as [`RuleSet::score`], from the number of dice showing each
+    /// face (index 0 unused).
+    pub fn score_counts(&self, counts: [u32; 7]) -> RuleScore {
+        let is_straight = counts[1..=6] == [1, 1, 1, 1, 1, 1];
+        if let (Some(points), true) = (self.straight, is_straight) {
+            return RuleScore {
+                score: points,
+                remaining_counts: [0; 7],
+                ends_turn: self.straight_ends_turn,
+            };
+        }
+
+        let is_three_pairs = counts.iter().filter(|&&c| c == 2).count() == 3;
+        if let (Some(points), true) = (self.three_pairs, is_three_pairs) {
+            return RuleScore {
+                score: points,
+                remaining_counts: [0; 7],
+                ends_turn: false,
+            };
+        }
+
+        let mut score = 0;
+        let mut remaining_counts = counts;
+        for face in 1..=6u32 {
+            let count = &mut remaining_counts[face as usize];
+            // Six of a face with no six-of-a-kind rule still make two triples
+            while let Some((size, rule)) = (3..=6)
+                .rev()
+                .filter(|&size| size <= *count)
+                .find_map(|size| self.kind_rule(size).map(|rule| (size, rule)))
+            {
+                score += self.kind_value(rule, face);
+                *count -= size;
+            }
+
+            let single = self.single_value(face);
+            if single > 0 {
+                score += *count * single;
+                *count = 0;
+            }
+        }
+
+        RuleScore {
+            score,
+            remaining_counts,
+            ends_turn: false,
+        }
+    }
+
+    /// Human-readable scoring table, one combination per line.
+    pub fn describe(&self) -> Vec<String> {
+        let mut lines = Vec::new();
+        if let Some(points) = self.straight {
+            let suffix = if self.straight_ends_turn {
+                ", all dice used, ends the turn"
+            } else {
+                ", all dice used"
+            };
+            lines.push(format!("Straight (1-2-3-4-5-6): {} points{}", points, suffix));
+        }
+        if let Some(points) = self.three_pairs {
+            lines.push(format!("Three pairs: {} points, all dice used", points));
+        }
+        for (size, name) in [(6, "Six"), (5, "Five"), (4, "Four")] {
+            if let Some(rule) = self.kind_rule(size) {
+                lines.push(format!("{} of a kind: {}", name, describe_kind(rule)));
+            }
+        }
+        lines.push(format!("Three 1s: {} points", self.three_ones));
+        lines.push(format!(
+            "Three of 2-6: face value × {} points",
+            self.three_of_a_kind_multiplier
+        ));
+        lines.push(format!("Single 1: {} points each", self.single_one));
+        lines.push(format!("Single 5: {} points each", self.single_five));
+        lines
+    }
+}
+
+fn describe_kind(rule: KindScore) -> String {
+    match rule {
+        KindScore::FaceTimes(n) => format!("face value × {} points", n),
+        KindScore::TripleTimes(2) => "double the three-of-a-kind value".to_string(),
+        KindScore::TripleTimes(n) => format!("three-of-a-kind value × {}", n),
+        KindScore::Fixed(n) => format!("{} points", n),
+    }
+}
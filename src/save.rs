@@ -0,0 +1,59 @@
+//! Saved CLI games, written after every turn so `play --resume` can pick
+//! a game up where it stopped.
+
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::io;
+use std::path::Path;
+
+use crate::dice::SeededDice;
+use crate::engine::GameState;
+
+/// Bumped whenever the save format changes in an incompatible way.
+pub const SAVE_VERSION: u32 = 1;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SavedGame {
+    pub version: u32,
+    /// Same serialization as the web API's game state.
+    pub state: GameState,
+    /// Dice generator, so the upcoming rolls are the ones the game would have had.
+    pub dice: SeededDice,
+}
+
+impl SavedGame {
+    pub fn new(state: &GameState, dice: &SeededDice) -> Self {
+        SavedGame {
+            version: SAVE_VERSION,
+            state: state.clone(),
+            dice: dice.clone(),
+        }
+    }
+
+    /// Write the save atomically, so an interrupted write keeps the previous one.
+    pub fn save(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        let path = path.as_ref();
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        let json = serde_json::to_string_pretty(self).map_err(io::Error::other)?;
+        let tmp = path.with_extension("json.tmp");
+        fs::write(&tmp, json)?;
+        fs::rename(tmp, path)
+    }
+
+    pub fn load(path: impl AsRef<Path>) -> io::Result<Self> {
+        let saved: SavedGame = serde_json::from_str(&fs::read_to_string(path)?)
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+        if saved.version != SAVE_VERSION {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!(
+                    "save version {} is not supported (expected {})",
+                    saved.version, SAVE_VERSION
+                ),
+            ));
+        }
+        Ok(saved)
+    }
+}
@@ -1,84 +1,269 @@
-use rand::Rng;
+use serde::{Deserialize, Serialize};
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::fmt;
+use std::rc::Rc;
 
-pub fn roll_dice(n: usize) -> Vec<u8> {
-    let mut rng = rand::thread_rng();
-    (0..n).map(|_| rng.gen_range(1..=6)).collect()
-}
+use crate::rules::{RuleScore, RuleSet};
 
-/// Calculate the score for a given roll of dice.
-/// Returns a tuple (score, remaining_dice).
-///
-/// Scoring rules:
-/// - Straight (1-2-3-4-5-6): 2000 points, all dice used.
-/// - Three pairs: 1500 points, all dice used.
-/// - Six of a kind: face value * 1000 points, all dice used.
-/// - Three 1s: 1000 points.
-/// - Three of 2–6: face value * 100 points.
-/// - Each remaining 1: 100 points.
-/// - Each remaining 5: 50 points.
+/// Calculate the score for a given roll of dice under a rule set.
+/// Returns a tuple (score, remaining_dice, remaining_dice_values, remaining_indices).
 ///
+/// See `RuleSet::score` for the order in which combinations are applied.
 /// Remaining dice are those that did not contribute to the score.
 /// If no scoring dice, score is 0 and all dice remain.
-pub fn calculate_score(dice: &[u8]) -> (u32, u32, Vec<u8>, Vec<usize>) {
-    let mut counts: [u32; 7] = [0; 7];
+pub fn calculate_score(dice: &[u8], rules: &RuleSet) -> (u32, u32, Vec<u8>, Vec<usize>) {
+    let RuleScore {
+        score,
+        remaining_counts,
+        ..
+    } = rules.score(dice);
+
+    // Calculate total remaining dice count
+    let remaining_dice_count = remaining_counts.iter().skip(1).sum::<u32>();
+
+    // Build the vector of remaining dice values and their indices in the original roll
+    let mut remaining_dice_values = Vec::new();
+    let mut remaining_indices = Vec::new();
+    let mut restants = remaining_counts;
+    for (i, &die) in dice.iter().enumerate() {
+        if die > 0 && restants[die as usize] > 0 {
+            remaining_dice_values.push(die);
+            remaining_indices.push(i);
+            restants[die as usize] -= 1;
+        }
+    }
+    (score, remaining_dice_count, remaining_dice_values, remaining_indices)
+}
+
+/// A legal way to set aside scoring dice from a roll.
+///
+/// `indices` are positions in the original roll, `dice` the matching values.
+/// Every kept die contributes to `score`, so the subset can be banked as is.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct KeepOption {
+    pub indices: Vec<usize>,
+    pub dice: Vec<u8>,
+    pub score: u32,
+    /// Keeping these dice banks the turn immediately (house rules).
+    pub ends_turn: bool,
+}
+
+/// Outcome of setting aside a chosen subset of a roll.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct KeepResult {
+    pub score: u32,
+    pub kept: Vec<u8>,
+    pub reroll: Vec<u8>,
+    pub reroll_indices: Vec<usize>,
+    pub ends_turn: bool,
+}
+
+impl KeepResult {
+    /// Number of dice left to roll again (0 means every die scored).
+    pub fn dice_left(&self) -> usize {
+        self.reroll.len()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum KeepError {
+    Empty,
+    OutOfRange(usize),
+    Duplicate(usize),
+    NotScoring(Vec<u8>),
+}
+
+impl fmt::Display for KeepError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            KeepError::Empty => write!(f, "at least one scoring die must be kept"),
+            KeepError::OutOfRange(i) => write!(f, "die index {} is not part of the roll", i),
+            KeepError::Duplicate(i) => write!(f, "die index {} is selected twice", i),
+            KeepError::NotScoring(dice) => {
+                write!(f, "dice {:?} do not all contribute to the score", dice)
+            }
+        }
+    }
+}
+
+impl std::error::Error for KeepError {}
+
+/// Score of a set of kept dice, or `None` if any of them does not score.
+pub fn score_kept(dice: &[u8], rules: &RuleSet) -> Option<RuleScore> {
+    let mut counts = [0u32; 7];
     for &d in dice {
         counts[d as usize] += 1;
     }
+    score_kept_counts(counts, rules)
+}
 
-    let mut score = 0;
-    let mut remaining_counts = counts.clone();
-
-    if counts[1..=6] == [1, 1, 1, 1, 1, 1] {
-        return (2000, 0, Vec::new(), Vec::new()); // Straight, all dice used
+fn score_kept_counts(counts: [u32; 7], rules: &RuleSet) -> Option<RuleScore> {
+    if counts.iter().all(|&c| c == 0) {
+        return None;
     }
+    let scored = rules.score_counts(counts);
+    if scored.score > 0 && scored.remaining_counts.iter().all(|&c| c == 0) {
+        Some(scored)
+    } else {
+        None
+    }
+}
+
+/// Keeps (indices left empty) by face counts of a roll, for one rule set.
+type KeepCache = HashMap<[u32; 7], Rc<[KeepOption]>>;
+
+thread_local! {
+    /// One cache per rule set in use. There are only a few hundred distinct rolls, and simulations ask
+    /// for millions.
+    static KEEP_CACHE: RefCell<Vec<(RuleSet, KeepCache)>> = RefCell::default();
+}
 
-    if counts.iter().filter(|&&c| c == 2).count() == 3 {
-        return (1500, 0, Vec::new(), Vec::new()); // Three pairs, all dice used
+/// Enumerate every legal subset of scoring dice for a roll.
+///
+/// Subsets are compared by value, so a roll with two 5s yields a single
+/// "keep one 5" option. Options are sorted by score (best first), then by
+/// the number of dice kept (fewest first). An empty list means a bust.
+pub fn keep_options(roll: &[u8], rules: &RuleSet) -> Vec<KeepOption> {
+    let mut counts = [0u32; 7];
+    for &d in roll {
+        counts[d as usize] += 1;
     }
 
-    for (i, &count) in counts.iter().enumerate().skip(1) {
-        if count == 6 {
-            return ((i as u32) * 1000, 0, Vec::new(), Vec::new()); // Six of a kind, all dice used
+    let keeps = KEEP_CACHE.with(|cache| {
+        let mut cache = cache.borrow_mut();
+        let index = match cache.iter().position(|(cached, _)| cached == rules) {
+            Some(index) => index,
+            None => {
+                cache.push((rules.clone(), HashMap::new()));
+                cache.len() - 1
+            }
+        };
+        cache[index]
+            .1
+            .entry(counts)
+            .or_insert_with(|| count_keeps(rules, &counts).into())
+            .clone()
+    });
+
+    keeps
+        .iter()
+        .map(|keep| {
+            let mut left = [0u32; 7];
+            for &d in &keep.dice {
+                left[d as usize] += 1;
+            }
+            let indices = roll
+                .iter()
+                .enumerate()
+                .filter(|&(_, &d)| {
+                    let d = d as usize;
+                    if left[d] > 0 {
+                        left[d] -= 1;
+                        true
+                    } else {
+                        false
+                    }
+                })
+                .map(|(i, _)| i)
+                .collect();
+            KeepOption {
+                indices,
+                ..keep.clone()
+            }
+        })
+        .collect()
+}
+
+/// Keeps for a roll with these face counts, in `keep_options` order.
+fn count_keeps(rules: &RuleSet, counts: &[u32; 7]) -> Vec<KeepOption> {
+    let mut options = Vec::new();
+    let mut kept_counts = [0u32; 7];
+    collect_keeps(rules, counts, 1, &mut kept_counts, &mut options);
+
+    options.sort_by(|a, b| {
+        b.score
+            .cmp(&a.score)
+            .then(a.dice.len().cmp(&b.dice.len()))
+            .then(a.dice.cmp(&b.dice))
+    });
+    options
+}
+
+fn collect_keeps(
+    rules: &RuleSet,
+    counts: &[u32; 7],
+    face: usize,
+    kept_counts: &mut [u32; 7],
+    options: &mut Vec<KeepOption>,
+) {
+    if face > 6 {
+        if let Some(scored) = score_kept_counts(*kept_counts, rules) {
+            let mut dice = Vec::new();
+            for (value, &count) in kept_counts.iter().enumerate().skip(1) {
+                dice.extend(std::iter::repeat_n(value as u8, count as usize));
+            }
+            options.push(KeepOption {
+                indices: Vec::new(),
+                dice,
+                score: scored.score,
+                ends_turn: scored.ends_turn,
+            });
         }
+        return;
     }
 
-    // Handle three of a kind for 2-6
-    for (i, count) in remaining_counts.iter_mut().enumerate().skip(2) {
-        if *count >= 3 {
-            score += (i as u32) * 100;
-            *count -= 3;
-        }
+    for count in 0..=counts[face] {
+        kept_counts[face] = count;
+        collect_keeps(rules, counts, face + 1, kept_counts, options);
+    }
+    kept_counts[face] = 0;
+}
+
+/// The highest-scoring keep for a roll, i.e. what `calculate_score` counts.
+pub fn best_keep(roll: &[u8], rules: &RuleSet) -> Option<KeepOption> {
+    keep_options(roll, rules).into_iter().next()
+}
+
+/// Validate a chosen subset of a roll (by position) and return its score
+/// together with the dice left to reroll.
+pub fn validate_keep(
+    roll: &[u8],
+    indices: &[usize],
+    rules: &RuleSet,
+) -> Result<KeepResult, KeepError> {
+    if indices.is_empty() {
+        return Err(KeepError::Empty);
     }
-    
-    // Handle three 1s (special case: 1000 points)
-    if remaining_counts[1] >= 3 {
-        score += 1000;
-        remaining_counts[1] -= 3;
+
+    let mut selected = vec![false; roll.len()];
+    for &i in indices {
+        if i >= roll.len() {
+            return Err(KeepError::OutOfRange(i));
+        }
+        if selected[i] {
+            return Err(KeepError::Duplicate(i));
+        }
+        selected[i] = true;
     }
-    
-    // Handle remaining 1s (100 points each)
-    let remaining_ones = remaining_counts[1].min(2);
-    score += remaining_ones * 100;
-    remaining_counts[1] -= remaining_ones;
-    
-    // Handle remaining 5s (50 points each)
-    let remaining_fives = remaining_counts[5].min(2);
-    score += remaining_fives * 50;
-    remaining_counts[5] -= remaining_fives;
-    
-    // Calculate total remaining dice count
-    let remaining_dice_count = remaining_counts.iter().skip(1).sum::<u32>();
-    
-    // Build the vector of remaining dice values and their indices in the original roll
-    let mut remaining_dice_values = Vec::new();
-    let mut remaining_indices = Vec::new();
-    let mut restants = remaining_counts.clone();
-    for (i, &die) in dice.iter().enumerate() {
-        if die > 0 && restants[die as usize] > 0 {
-            remaining_dice_values.push(die);
-            remaining_indices.push(i);
-            restants[die as usize] -= 1;
+
+    let kept: Vec<u8> = indices.iter().map(|&i| roll[i]).collect();
+    let scored = score_kept(&kept, rules).ok_or_else(|| KeepError::NotScoring(kept.clone()))?;
+
+    let mut reroll = Vec::new();
+    let mut reroll_indices = Vec::new();
+    for (i, &die) in roll.iter().enumerate() {
+        if !selected[i] {
+            reroll.push(die);
+            reroll_indices.push(i);
         }
     }
-    (score, remaining_dice_count, remaining_dice_values, remaining_indices)
+
+    Ok(KeepResult {
+        score: scored.score,
+        kept,
+        reroll,
+        reroll_indices,
+        ends_turn: scored.ends_turn,
+    })
 }
@@ -0,0 +1,270 @@
+//! Headless games between computer strategies, for benchmarking.
+
+use colored::Colorize;
+use std::thread;
+
+use crate::config::Config;
+use crate::dice::SeededDice;
+use crate::engine::{Action, Event, GameState, Player, play_turn};
+use crate::strategy;
+
+/// Games longer than this many turns per player are called a draw
+/// (an exact finish can go on for a long time).
+const MAX_TURNS_PER_PLAYER: u32 = 1000;
+
+/// z-score of a 95% confidence interval.
+const Z_95: f64 = 1.96;
+
+#[derive(Debug, Clone)]
+pub struct SimulationOptions {
+    pub games: u64,
+    /// Strategy name of each seat.
+    pub strategies: Vec<String>,
+    pub threads: usize,
+    /// Game `i` is played with the dice seed `seed + i`.
+    pub seed: u64,
+}
+
+/// Totals for one seat.
+#[derive(Debug, Clone, Default)]
+pub struct SeatStats {
+    pub strategy: String,
+    pub wins: u64,
+    pub turns: u64,
+    pub busts: u64,
+    /// Points banked minus bust penalties, and its sum of squares per turn.
+    pub points: f64,
+    pub points_sq: f64,
+    /// Own turns taken in the games this seat won.
+    pub win_turns: f64,
+    pub win_turns_sq: f64,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct SimulationReport {
+    pub games: u64,
+    pub draws: u64,
+    pub seats: Vec<SeatStats>,
+}
+
+/// Mean and half-width of its 95% confidence interval.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Estimate {
+    pub mean: f64,
+    pub margin: f64,
+}
+
+impl Estimate {
+    fn from_sums(n: u64, sum: f64, sum_sq: f64) -> Self {
+        if n == 0 {
+            return Estimate { mean: 0.0, margin: 0.0 };
+        }
+        let n = n as f64;
+        let mean = sum / n;
+        let variance = if n > 1.0 {
+            ((sum_sq - n * mean * mean) / (n - 1.0)).max(0.0)
+        } else {
+            0.0
+        };
+        Estimate {
+            mean,
+            margin: Z_95 * (variance / n).sqrt(),
+        }
+    }
+
+    fn proportion(n: u64, hits: u64) -> Self {
+        Self::from_sums(n, hits as f64, hits as f64)
+    }
+}
+
+impl SeatStats {
+    pub fn win_rate(&self, games: u64) -> Estimate {
+        Estimate::proportion(games, self.wins)
+    }
+
+    pub fn bust_rate(&self) -> Estimate {
+        Estimate::proportion(self.turns, self.busts)
+    }
+
+    pub fn points_per_turn(&self) -> Estimate {
+        Estimate::from_sums(self.turns, self.points, self.points_sq)
+    }
+
+    /// Own turns needed to win, over the games this seat won.
+    pub fn turns_to_win(&self) -> Estimate {
+        Estimate::from_sums(self.wins, self.win_turns, self.win_turns_sq)
+    }
+
+    fn merge(&mut self, other: &SeatStats) {
+        self.wins += other.wins;
+        self.turns += other.turns;
+        self.busts += other.busts;
+        self.points += other.points;
+        self.points_sq += other.points_sq;
+        self.win_turns += other.win_turns;
+        self.win_turns_sq += other.win_turns_sq;
+    }
+}
+
+impl SimulationReport {
+    fn new(strategies: &[String]) -> Self {
+        SimulationReport {
+            games: 0,
+            draws: 0,
+            seats: strategies
+                .iter()
+                .map(|strategy| SeatStats {
+                    strategy: strategy.clone(),
+                    ..SeatStats::default()
+                })
+                .collect(),
+        }
+    }
+
+    fn merge(&mut self, other: &SimulationReport) {
+        self.games += other.games;
+        self.draws += other.draws;
+        for (seat, other) in self.seats.iter_mut().zip(&other.seats) {
+            seat.merge(other);
+        }
+    }
+}
+
+/// Play `options.games` games across `options.threads` threads.
+pub fn simulate(options: &SimulationOptions, config: &Config) -> Result<SimulationReport, String> {
+    if options.strategies.len() < 2 {
+        return Err("a simulation needs at least two strategies".to_string());
+    }
+    if let Some(unknown) = options
+        .strategies
+        .iter()
+        .find(|name| strategy::create(name, config).is_none())
+    {
+        return Err(format!("unknown strategy '{}'", unknown));
+    }
+
+    let threads = options.threads.max(1) as u64;
+    let reports: Vec<SimulationReport> = thread::scope(|scope| {
+        let handles: Vec<_> = (0..threads)
+            .map(|t| {
+                // Every thread plays the games whose index is t modulo threads
+                scope.spawn(move || {
+                    let mut report = SimulationReport::new(&options.strategies);
+                    let mut game = t;
+                    while game < options.games {
+                        play_game(game, options, config, &mut report);
+                        game += threads;
+                    }
+                    report
+                })
+            })
+            .collect();
+        handles
+            .into_iter()
+            .map(|handle| handle.join().expect("simulation thread panicked"))
+            .collect()
+    });
+
+    let mut total = SimulationReport::new(&options.strategies);
+    for report in &reports {
+        total.merge(report);
+    }
+    Ok(total)
+}
+
+/// Play one game and add it to `report`. Seats rotate from game to game so
+/// that no strategy always starts.
+fn play_game(game: u64, options: &SimulationOptions, config: &Config, report: &mut SimulationReport) {
+    let seats = options.strategies.len();
+    let first = (game % seats as u64) as usize;
+    // Turn order -> seat
+    let order: Vec<usize> = (0..seats).map(|i| (first + i) % seats).collect();
+
+    let players = order
+        .iter()
+        .map(|&seat| Player {
+            // Unique even when a strategy plays itself
+            name: format!("{} #{}", options.strategies[seat], seat + 1),
+            score: 0,
+            is_human: false,
+            ai_type: Some(options.strategies[seat].clone()),
+            turn_score: 0,
+            roll_score: 0,
+            opened: false,
+            bust_streak: 0,
+        })
+        .collect();
+    let mut state = GameState::with_players(format!("sim-{}", game), players, &config.game);
+    let mut controllers: Vec<_> = order
+        .iter()
+        .map(|&seat| strategy::create(&options.strategies[seat], config).expect("strategies are checked"))
+        .collect();
+    let mut dice = SeededDice::new(options.seed.wrapping_add(game));
+    let mut turns = vec![0u32; seats];
+
+    report.games += 1;
+    while !state.game_over {
+        let index = state.current_player_index;
+        if turns[index] >= MAX_TURNS_PER_PLAYER {
+            report.draws += 1;
+            return;
+        }
+        turns[index] += 1;
+
+        let mut points = 0.0;
+        let mut busted = false;
+        play_turn(&mut state, &mut controllers[index], &mut dice, &mut |_, event| match event {
+            Event::Banked { points: banked, .. } => points += *banked as f64,
+            Event::BustPenalty { points: lost, .. } => points -= *lost as f64,
+            Event::Busted { .. } => busted = true,
+            _ => {}
+        })
+        .expect("play_turn replaces illegal keeps and refused banks");
+
+        let stats = &mut report.seats[order[index]];
+        stats.turns += 1;
+        stats.busts += busted as u64;
+        stats.points += points;
+        stats.points_sq += points * points;
+
+        state
+            .apply(Action::NextPlayer, &mut dice)
+            .expect("the turn is over");
+    }
+
+    if let Some(winner) = state.winner_index {
+        let stats = &mut report.seats[order[winner]];
+        let won_in = turns[winner] as f64;
+        stats.wins += 1;
+        stats.win_turns += won_in;
+        stats.win_turns_sq += won_in * won_in;
+    }
+}
+
+pub fn print_report(report: &SimulationReport) {
+    println!(
+        "{}",
+        format!("{} games, {} draws (95% confidence intervals)", report.games, report.draws)
+            .bold()
+            .blue()
+    );
+    println!(
+        "{:<4} {:<16} {:>18} {:>18} {:>18} {:>20}",
+        "Seat", "Strategy", "Win rate", "Turns to win", "Bust rate", "Points per turn"
+    );
+    for (i, seat) in report.seats.iter().enumerate() {
+        let win = seat.win_rate(report.games);
+        let turns = seat.turns_to_win();
+        let bust = seat.bust_rate();
+        let points = seat.points_per_turn();
+        println!(
+            "{:<4} {:<16} {:>18} {:>18} {:>18} {:>20}",
+            i + 1,
+            seat.strategy,
+            format!("{:.2}% ±{:.2}", win.mean * 100.0, win.margin * 100.0),
+            format!("{:.2} ±{:.2}", turns.mean, turns.margin),
+            format!("{:.2}% ±{:.2}", bust.mean * 100.0, bust.margin * 100.0),
+            format!("{:.1} ±{:.1}", points.mean, points.margin),
+        );
+    }
+}
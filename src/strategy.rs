@@ -0,0 +1,167 @@
+//! Bots as named strategies.
+//!
+//! A [`Strategy`] makes the keep and bank decisions of a bot from the
+//! observable game state. Strategies are created by name from a global
+//! registry, which holds the built-in bots and anything registered with
+//! [`register`], so a crate can add its own bots and seat them from the
+//! `players` list of the configuration:
+//!
+//! ```no_run
+//! use dice6000::engine::GameState;
+//! use dice6000::strategy::{self, Strategy};
+//!
+//! struct KeepAll;
+//!
+//! impl Strategy for KeepAll {
+//!     fn choose_keep(&mut self, state: &GameState) -> Vec<usize> {
+//!         state.keep_options[0].indices.clone()
+//!     }
+//!
+//!     fn should_bank(&mut self, state: &GameState) -> bool {
+//!         state.current_player().turn_score >= 350
+//!     }
+//! }
+//!
+//! strategy::register("keep_all", |_config| Box::new(KeepAll));
+//! ```
+
+use std::collections::BTreeMap;
+use std::sync::{Arc, OnceLock, RwLock};
+
+use crate::ai::LlmPlayer;
+use crate::computer::{COMPUTER_STRATEGIES, controller_from_config, strategy_controller};
+use crate::config::Config;
+use crate::engine::{Event, GameState, TurnController};
+
+/// Keep and bank decisions of a bot.
+///
+/// Every strategy is a [`TurnController`], so it can play turns with
+/// [`play_turn`](crate::engine::play_turn).
+pub trait Strategy: Send {
+    /// Indices of the pending roll (`state.dice`) to set aside, one of
+    /// `state.keep_options`.
+    fn choose_keep(&mut self, state: &GameState) -> Vec<usize>;
+
+    /// After a keep: true to bank the turn score, false to roll again.
+    fn should_bank(&mut self, state: &GameState) -> bool;
+
+    /// Decision and reasoning behind the last keep, for bots that give one.
+    fn explain(&mut self) -> Option<(String, String)> {
+        None
+    }
+
+    /// Called with every event of the bot's turns.
+    fn observe(&mut self, _state: &GameState, _event: &Event) {}
+}
+
+impl<S: Strategy + ?Sized> Strategy for Box<S> {
+    fn choose_keep(&mut self, state: &GameState) -> Vec<usize> {
+        (**self).choose_keep(state)
+    }
+
+    fn should_bank(&mut self, state: &GameState) -> bool {
+        (**self).should_bank(state)
+    }
+
+    fn explain(&mut self) -> Option<(String, String)> {
+        (**self).explain()
+    }
+
+    fn observe(&mut self, state: &GameState, event: &Event) {
+        (**self).observe(state, event)
+    }
+}
+
+impl<S: Strategy + ?Sized> TurnController for S {
+    fn choose_keep(&mut self, state: &GameState) -> Vec<usize> {
+        Strategy::choose_keep(self, state)
+    }
+
+    fn roll_again(&mut self, state: &GameState) -> bool {
+        !self.should_bank(state)
+    }
+
+    fn explain(&mut self) -> Option<(String, String)> {
+        Strategy::explain(self)
+    }
+
+    fn observe(&mut self, state: &GameState, event: &Event) {
+        Strategy::observe(self, state, event)
+    }
+}
+
+/// Builds a strategy for a game played with this configuration.
+pub type StrategyFactory = Arc<dyn Fn(&Config) -> Box<dyn Strategy> + Send + Sync>;
+
+fn registry() -> &'static RwLock<BTreeMap<String, StrategyFactory>> {
+    static REGISTRY: OnceLock<RwLock<BTreeMap<String, StrategyFactory>>> = OnceLock::new();
+    REGISTRY.get_or_init(|| RwLock::new(builtins()))
+}
+
+fn builtins() -> BTreeMap<String, StrategyFactory> {
+    let mut strategies: BTreeMap<String, StrategyFactory> = BTreeMap::new();
+    // The configured `computer_strategy`
+    strategies.insert("computer".to_string(), Arc::new(|config| controller_from_config(config)));
+    for name in COMPUTER_STRATEGIES {
+        strategies.insert(
+            name.to_string(),
+            Arc::new(move |config| strategy_controller(name, &config.game).expect("built-in strategy")),
+        );
+    }
+    for name in ["openai", "anthropic", "ollama"] {
+        strategies.insert(
+            name.to_string(),
+            Arc::new(move |config| {
+                Box::new(LlmPlayer::for_strategy(name, config).expect("vendor sections are always set"))
+            }),
+        );
+    }
+    strategies
+}
+
+/// Register a strategy under `name` (case-insensitive), replacing any
+/// strategy of that name.
+pub fn register<F>(name: &str, factory: F)
+where
+    F: Fn(&Config) -> Box<dyn Strategy> + Send + Sync + 'static,
+{
+    registry()
+        .write()
+        .expect("strategy registry poisoned")
+        .insert(name.to_lowercase(), Arc::new(factory));
+}
+
+/// Strategy registered under `name` (case-insensitive), or None if unknown.
+/// `llm:<name>` is the LLM player of that entry of the `providers` config,
+/// and the rule-based computer plays the parameter sets of `personalities`.
+pub fn create(name: &str, config: &Config) -> Option<Box<dyn Strategy>> {
+    if name.starts_with("llm:") {
+        return Some(Box::new(LlmPlayer::for_strategy(name, config)?));
+    }
+    let factory = registry()
+        .read()
+        .expect("strategy registry poisoned")
+        .get(&name.to_lowercase())
+        .cloned();
+    match factory {
+        Some(factory) => Some(factory(config)),
+        None => strategy_controller(name, &config.game),
+    }
+}
+
+pub fn is_registered(name: &str) -> bool {
+    registry()
+        .read()
+        .expect("strategy registry poisoned")
+        .contains_key(&name.to_lowercase())
+}
+
+/// Names of the registered strategies, sorted.
+pub fn names() -> Vec<String> {
+    registry()
+        .read()
+        .expect("strategy registry poisoned")
+        .keys()
+        .cloned()
+        .collect()
+}
@@ -0,0 +1,112 @@
+//! Self-play tuning of the rule-based computer's parameters.
+//!
+//! A hill climb: every generation mutates the best parameters found so far
+//! and plays the candidate against them with [`simulate`]. The candidate
+//! takes over only when its win rate is above one half by more than the 95%
+//! margin, so a lucky run of dice does not replace a better incumbent.
+
+use std::collections::BTreeMap;
+
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+
+use crate::computer::PersonalityParams;
+use crate::config::Config;
+use crate::simulate::{Estimate, SimulationOptions, simulate};
+
+const CANDIDATE: &str = "candidate";
+const INCUMBENT: &str = "incumbent";
+
+pub struct TuneOptions {
+    pub generations: u32,
+    /// Games of each candidate against the incumbent.
+    pub games: u64,
+    pub threads: usize,
+    pub seed: u64,
+    /// Parameters the search starts from.
+    pub start: PersonalityParams,
+}
+
+/// Outcome of one generation.
+#[derive(Debug, Clone)]
+pub struct Generation {
+    pub index: u32,
+    pub candidate: PersonalityParams,
+    /// Candidate's win rate against the incumbent.
+    pub win_rate: Estimate,
+    pub accepted: bool,
+}
+
+/// Search from `options.start`, reporting every generation; returns the best
+/// parameters found.
+pub fn tune(
+    options: &TuneOptions,
+    config: &Config,
+    on_generation: &mut dyn FnMut(&Generation),
+) -> Result<PersonalityParams, String> {
+    let mut rng = ChaCha8Rng::seed_from_u64(options.seed);
+    let mut best = options.start;
+    let mut config = config.clone();
+
+    for index in 1..=options.generations {
+        let candidate = mutate(&best, &mut rng);
+        config.game.personalities.insert(CANDIDATE.to_string(), candidate);
+        config.game.personalities.insert(INCUMBENT.to_string(), best);
+        let simulation = SimulationOptions {
+            games: options.games,
+            strategies: vec![CANDIDATE.to_string(), INCUMBENT.to_string()],
+            threads: options.threads,
+            // Fresh dice every generation
+            seed: options.seed.wrapping_add(index as u64 * options.games),
+        };
+        let report = simulate(&simulation, &config)?;
+        let win_rate = report.seats[0].win_rate(report.games);
+        let accepted = win_rate.mean - win_rate.margin > 0.5;
+        if accepted {
+            best = candidate;
+        }
+        on_generation(&Generation {
+            index,
+            candidate,
+            win_rate,
+            accepted,
+        });
+    }
+    Ok(best)
+}
+
+/// Nudge one or two parameters by a step or two, within sensible bounds.
+fn mutate(params: &PersonalityParams, rng: &mut impl Rng) -> PersonalityParams {
+    loop {
+        let mut candidate = *params;
+        for _ in 0..rng.gen_range(1..=2) {
+            let (value, step, max) = match rng.gen_range(0..9) {
+                0 => (&mut candidate.min_score, 50, 2000),
+                1 => (&mut candidate.max_score, 50, 5000),
+                2 => (&mut candidate.single_die_bank, 50, 2000),
+                3 => (&mut candidate.safe_score, 50, 2000),
+                4 => (&mut candidate.margin, 50, 1000),
+                5 => (&mut candidate.catch_up_gap, 250, 6000),
+                6 => (&mut candidate.lead_gap, 250, 6000),
+                7 => (&mut candidate.lead_dice, 1, 6),
+                _ => (&mut candidate.die_value, 10, 300),
+            };
+            let change = step * rng.gen_range(1..=2);
+            *value = if rng.gen_bool(0.5) {
+                value.saturating_sub(change)
+            } else {
+                (*value + change).min(max)
+            };
+        }
+        if candidate != *params {
+            return candidate;
+        }
+    }
+}
+
+/// A `personalities` section holding `params` under `name`, to copy under
+/// `game:` in the config.
+pub fn personalities_yaml(name: &str, params: &PersonalityParams) -> Result<String, serde_yaml::Error> {
+    let section = BTreeMap::from([("personalities", BTreeMap::from([(name, params)]))]);
+    serde_yaml::to_string(&section)
+}
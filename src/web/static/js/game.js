@@ -13,11 +13,12 @@ const translations = {
         yourTurn: 'Lancez les dés pour commencer.',
         aiThinking: 'L\'IA réfléchit... 🤔',
         youRolled: 'Vous avez lancé',
-        selectDice: 'Les dés qui rapportent des points sont sélectionnés automatiquement',
+        selectDice: 'Cliquez sur les dés qui rapportent des points pour choisir ceux à garder',
         youWon: 'Vous avez gagné',
         points: 'points',
         rulesTitle: '📋 Règles du jeu 6000',
         bust: 'Tous les points sont perdu!',
+        bustPenalty: 'Trop d\'échecs d\'affilée : pénalité appliquée !',
         banks: 'sécurise',
         decides: 'décide de continuer...',
         scores: 'marque',
@@ -28,6 +29,8 @@ const translations = {
         aiClaude: '🧠 IA (Claude)',
         aiOllama: '🧠 IA (Ollama)',
         creating: 'Création du jeu...',
+        inviteCode: 'En attente des joueurs, code d\'invitation',
+        joinPrompt: 'Votre nom pour rejoindre la partie :',
         error: 'Erreur'
     },
     en: {
@@ -43,11 +46,12 @@ const translations = {
         yourTurn: 'Roll the dice to begin.',
         aiThinking: 'AI is thinking... 🤔',
         youRolled: 'You rolled',
-        selectDice: 'Scoring dice are automatically selected',
+        selectDice: 'Click scoring dice to choose which ones to keep',
         youWon: 'You won',
         points: 'points',
         rulesTitle: '📋 Game 6000 Rules',
         bust: 'busted! No points scored.',
+        bustPenalty: 'Too many busts in a row: penalty applied!',
         banks: 'banks',
         decides: 'decides to continue...',
         scores: 'scores',
@@ -58,6 +62,8 @@ const translations = {
         aiClaude: '🧠 AI (Claude)',
         aiOllama: '🧠 AI (Ollama)',
         creating: 'Creating game...',
+        inviteCode: 'Waiting for players, invite code',
+        joinPrompt: 'Your name to join the game:',
         error: 'Error'
     }
 };
@@ -65,8 +71,12 @@ const translations = {
 let currentLanguage = 'fr';
 let gameState = null;
 let currentGameId = null;
-let aiPollingInterval = null;
-let aiPollingTimeout = null;
+let gameEvents = null;
+// Jetons des sièges que ce navigateur occupe, par index de joueur
+let seatTokens = {};
+let inviteCode = null;
+let selectedKeep = [];
+let selectedKeepRoll = '';
 
 function toggleRules() {
     const list = document.getElementById("rules-list");
@@ -99,13 +109,15 @@ function renderGameState() {
         playerDiv.className = 'player-score';
         playerDiv.id = `player-${index}-score`;
         let playerName = player.name;
-        if (player.is_human) playerName = t.you;
+        // « Vous » quand ce navigateur n'occupe qu'un siège
+        if (index in seatTokens && Object.keys(seatTokens).length === 1) playerName = t.you;
         if (index === gameState.current_player_index) playerDiv.classList.add('current-player');
         playerDiv.innerHTML = `
             <div class="player-name">${playerName}</div>
             <div class="total-score">${player.score}</div>
             <div class="turn-score">${t.turn}: ${player.turn_score ?? 0}</div>
             <div class="roll-score">${t.roll}: ${player.roll_score ?? 0}</div>
+            ${player.bust_streak ? `<div class="bust-streak">💥 ${player.bust_streak}</div>` : ''}
         `;
         scoresContainer.appendChild(playerDiv);
     });
@@ -113,16 +125,30 @@ function renderGameState() {
     // Dés
     const diceContainer = document.getElementById('dice-container');
     diceContainer.innerHTML = '';
-    // Correction : on utilise gameState.rerollable_dice pour colorer en ROUGE (non-scorant), sinon VERT (scorant)
+    const keepOptions = Array.isArray(gameState.game_state.keep_options) ? gameState.game_state.keep_options : [];
+    const pendingRoll = keepOptions.length > 0;
+    // Seuls les sièges de ce navigateur jouent ici
+    const currentIsHuman = gameState.game_state.current_player_index in seatTokens;
+    // Nouveau lancer : on présélectionne la meilleure combinaison
+    const rollKey = JSON.stringify(gameState.game_state.dice) + gameState.game_state.turn_number;
+    if (pendingRoll && rollKey !== selectedKeepRoll) {
+        selectedKeep = keepOptions[0].indices.slice();
+        selectedKeepRoll = rollKey;
+    }
+    const scoringIndices = new Set(keepOptions.flatMap(option => option.indices));
     if (gameState.game_state.dice && Array.isArray(gameState.game_state.dice) && gameState.game_state.dice.length > 0) {
-        // Si rerollable_dice n'est pas défini ou vide, tous les dés sont scorants
         const rerollable = Array.isArray(gameState.game_state.rerollable_dice) ? gameState.game_state.rerollable_dice : [];
         gameState.game_state.dice.forEach((die, i) => {
             const dieDiv = document.createElement('div');
-            // Correction : si rerollable_dice est vide, tous verts
-            if (!gameState.game_state.rerollable_dice || gameState.game_state.rerollable_dice.length === 0) {
-                dieDiv.className = 'die scoring-die'; // tous scorants = vert
-            } else if (gameState.game_state.rerollable_dice.includes(i)) {
+            if (pendingRoll && currentIsHuman) {
+                // Choix des dés à garder : vert = gardé, blanc = relancé, rouge = non scorant
+                if (!scoringIndices.has(i)) {
+                    dieDiv.className = 'die non-scoring';
+                } else {
+                    dieDiv.className = selectedKeep.includes(i) ? 'die selected' : 'die';
+                    dieDiv.onclick = () => toggleKeep(i);
+                }
+            } else if (rerollable.includes(i)) {
                 dieDiv.className = 'die non-scoring'; // non scorant = rouge
             } else {
                 dieDiv.className = 'die scoring-die'; // scorant = vert
@@ -140,13 +166,14 @@ function renderGameState() {
     if (turnEndReason) {
         rollBtn.disabled = true;
         bankBtn.disabled = true;
-        nextBtn.disabled = false;
+        // Les bots passent la main d'eux-mêmes
+        nextBtn.disabled = !currentIsHuman;
     } else {
         nextBtn.disabled = true;
-        if (currentPlayer.is_human) {
+        if (currentIsHuman) {
             rollBtn.disabled = false;
             bankBtn.disabled = false;
-            if (currentPlayer.turn_score == 0) {
+            if (!pendingRoll) {
                 bankBtn.disabled = true;
             }
         } else {
@@ -156,48 +183,100 @@ function renderGameState() {
     }
 
     // Message générique
-    if (gameState.ai_decision && gameState.ai_explanation) {
+    if (inviteCode) {
+        msg = `${t.inviteCode}: ${inviteCode}`;
+    } else if (gameState.ai_decision && gameState.ai_explanation) {
         msg = `${t.aiThinking}: ${gameState.ai_explanation}`;
     } else if (turnEndReason === 'busted') {
         msg = t.bust;
+    } else if (turnEndReason === 'bust_penalty') {
+        msg = t.bustPenalty;
     } else if (turnEndReason === 'banked') {
         msg = `${currentPlayer.name} ${t.banks} ${currentPlayer.turn_score} ${t.points}.`;
-    } else if (typeof gameState.current_roll_score === 'number' && gameState.current_roll_score > 0) {
-        msg = `${currentPlayer.name} a gagné ${gameState.current_roll_score} points avec ce lancer.`;
+    } else if (pendingRoll && currentIsHuman) {
+        msg = t.selectDice;
     } else {
         msg = `${t.yourTurn}`;
     }
     document.getElementById('game-info').textContent = msg;
 }
 
-// Polling générique
-async function pollGameStatus() {
+function toggleKeep(index) {
+    if (selectedKeep.includes(index)) {
+        selectedKeep = selectedKeep.filter(i => i !== index);
+    } else {
+        selectedKeep.push(index);
+    }
+    renderGameState();
+}
+
+// Corps de requête pour /roll et /bank : dés gardés du lancer en cours
+function keepRequestBody() {
+    const keepOptions = gameState && gameState.game_state && gameState.game_state.keep_options;
+    if (keepOptions && keepOptions.length > 0) {
+        return JSON.stringify({ keep: selectedKeep });
+    }
+    return JSON.stringify({});
+}
+
+// Erreur d'une réponse refusée, avec le message JSON du serveur s'il y en a un
+async function responseError(response) {
+    const data = await response.json().catch(() => ({}));
+    return new Error(data.message || `HTTP error! status: ${response.status}`);
+}
+
+// En-têtes d'une action du joueur courant, avec le jeton de son siège
+function seatHeaders() {
+    const headers = { 'Content-Type': 'application/json' };
+    const token = gameState && seatTokens[gameState.game_state.current_player_index];
+    if (token) headers['Authorization'] = `Bearer ${token}`;
+    return headers;
+}
+
+// Événements du jeu poussés par le serveur (les bots jouent côté serveur)
+const GAME_EVENT_TYPES = [
+    'state', 'rolled', 'kept', 'hot_dice', 'turn_ending_combination', 'busted', 'bust_penalty',
+    'banked', 'bank_rejected', 'keep_rejected', 'explanation', 'final_round', 'turn_started', 'game_over'
+];
+
+function subscribeToGame() {
+    if (gameEvents) gameEvents.close();
     if (!currentGameId) return;
-    try {
-        const response = await fetch(`/api/game/${currentGameId}/status`, {
-            method: 'POST',
-            headers: {
-                'Content-Type': 'application/json',
-            }
-        });
-        if (!response.ok) {
-            throw new Error(`HTTP error! status: ${response.status}`);
-        }
-        const data = await response.json();
-        if (data && data.game_state) {
-            gameState = data;
-            renderGameState();
-        }
-        if (aiPollingTimeout) clearTimeout(aiPollingTimeout);
-        aiPollingTimeout = setTimeout(pollGameStatus, 3000);
-    } catch (error) {
-        console.error('Error polling game status:', error);
-        const t = translations[currentLanguage];
-        document.getElementById('game-info').textContent = `${t.error}: ${error.message}`;
+    gameEvents = new EventSource(`/api/game/${currentGameId}/events`);
+    GAME_EVENT_TYPES.forEach(type => gameEvents.addEventListener(type, onGameEvent));
+    gameEvents.onerror = () => {
+        // EventSource se reconnecte tout seul
+        console.error('Game event stream interrupted');
+    };
+}
+
+function onGameEvent(message) {
+    const update = JSON.parse(message.data);
+    const event = update.event;
+    // L'explication de l'IA reste affichée jusqu'au tour suivant
+    let aiDecision = gameState ? gameState.ai_decision : null;
+    let aiExplanation = gameState ? gameState.ai_explanation : null;
+    if (event) {
+        // La partie a commencé : tous les sièges sont pris
+        inviteCode = null;
     }
+    if (event && event.type === 'explanation') {
+        aiDecision = event.decision;
+        aiExplanation = event.explanation;
+    } else if (event && event.type === 'turn_started') {
+        aiDecision = null;
+        aiExplanation = null;
+    }
+    gameState = {
+        success: true,
+        game_state: update.game_state,
+        ai_decision: aiDecision,
+        ai_explanation: aiExplanation
+    };
+    renderGameState();
 }
 
-// Démarre le polling dès la création du jeu
+// S'abonne aux événements dès la création du jeu
 async function createGame() {
     try {
         const t = translations[currentLanguage];
@@ -210,13 +289,17 @@ async function createGame() {
             body: JSON.stringify({ language: currentLanguage })
         });
         if (!response.ok) {
-            throw new Error(`HTTP error! status: ${response.status}`);
+            throw await responseError(response);
         }
         const data = await response.json();
         if (data.success && data.game_state) {
-            gameState = data.game_state;
+            gameState = data;
             currentGameId = data.game_state.id;
-            await pollGameStatus();
+            seatTokens = {};
+            (data.seats || []).forEach(seat => { seatTokens[seat.seat] = seat.token; });
+            inviteCode = data.invite_code || null;
+            renderGameState();
+            subscribeToGame();
         } else {
             throw new Error(data.message || 'Failed to create game');
         }
@@ -252,12 +335,10 @@ async function nextPlayer() {
     try {
         const response = await fetch(`/api/game/${currentGameId}/next`, {
             method: 'POST',
-            headers: {
-                'Content-Type': 'application/json',
-            }
+            headers: seatHeaders()
         });
         if (!response.ok) {
-            throw new Error(`HTTP error! status: ${response.status}`);
+            throw await responseError(response);
         }
         const data = await response.json();
         if (data.success) {
@@ -279,10 +360,8 @@ async function rollDice() {
     try {
         const response = await fetch(`/api/game/${currentGameId}/roll`, {
             method: 'POST',
-            headers: {
-                'Content-Type': 'application/json',
-            },
-            body: JSON.stringify({}) // Ajoute un body JSON vide pour éviter l'erreur EOF
+            headers: seatHeaders(),
+            body: keepRequestBody()
         });
         let data;
         let rawText = '';
@@ -299,7 +378,8 @@ async function rollDice() {
             throw new Error(`HTTP error! status: ${response.status} - ${data.message || JSON.stringify(data)}`);
         }
         if (data.success && data.game_state) {
-            await pollGameStatus();
+            gameState = data;
+            renderGameState();
         } else {
             console.error('Unexpected /roll response:', data);
             const t = translations[currentLanguage];
@@ -318,16 +398,16 @@ async function bankPoints() {
     try {
         const response = await fetch(`/api/game/${currentGameId}/bank`, {
             method: 'POST',
-            headers: {
-                'Content-Type': 'application/json',
-            }
+            headers: seatHeaders(),
+            body: keepRequestBody()
         });
         if (!response.ok) {
-            throw new Error(`HTTP error! status: ${response.status}`);
+            throw await responseError(response);
         }
         const data = await response.json();
         if (data.success && data.game_state) {
-            await pollGameStatus();
+            gameState = data;
+            renderGameState();
         } else {
             throw new Error(data.message || 'Failed to bank points');
         }
@@ -336,4 +416,38 @@ async function bankPoints() {
         const t = translations[currentLanguage];
         document.getElementById('game-info').textContent = `${t.error}: ${error.message}`;
     }
-}
\ No newline at end of file
+}
+
+// Rejoindre une partie avec un lien ?join=CODE
+async function joinGame(code) {
+    const t = translations[currentLanguage];
+    const name = prompt(t.joinPrompt);
+    if (!name) return;
+    try {
+        const response = await fetch(`/api/lobby/${encodeURIComponent(code)}/join`, {
+            method: 'POST',
+            headers: {
+                'Content-Type': 'application/json',
+            },
+            body: JSON.stringify({ name })
+        });
+        if (!response.ok) {
+            throw await responseError(response);
+        }
+        const data = await response.json();
+        seatTokens = { [data.seat.seat]: data.seat.token };
+        inviteCode = null;
+        currentGameId = data.game_id;
+        gameState = { success: true, game_state: data.game_state };
+        renderGameState();
+        subscribeToGame();
+    } catch (error) {
+        console.error('Error joining game:', error);
+        document.getElementById('game-info').textContent = `${t.error}: ${error.message}`;
+    }
+}
+
+window.addEventListener('load', () => {
+    const code = new URLSearchParams(window.location.search).get('join');
+    if (code) joinGame(code);
+});
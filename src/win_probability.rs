@@ -0,0 +1,458 @@
+//! Computer player that maximises its chance to win the game.
+//!
+//! Like the classic Pig solutions, the solver computes W(me, opponent): the
+//! probability to win when starting a turn with these scores. Banking `t`
+//! points is worth 1 - W(opponent, me + t), busting 1 - W(opponent, me).
+//! Pairs with a higher score sum are solved first; a pair and its mirror
+//! depend on each other through busts and are iterated to a fixed point.
+//!
+//! The model is a two-player game: with more players the best opponent
+//! stands for all of them. A player without points is taken as not on the
+//! board yet (opening score). Exact finishes are modelled, a final round
+//! plays like a plain race to the target and bust penalties are ignored.
+
+use std::collections::HashMap;
+use std::ops::Range;
+use std::sync::{Arc, Mutex, OnceLock};
+use std::thread;
+
+use crate::config::{EndMode, GameConfig};
+use crate::engine::{GameState, NUM_DICE};
+use crate::optimal::{gcd, roll_outcomes};
+use crate::rules::RuleSet;
+use crate::strategy::Strategy;
+
+/// Fixed-point tolerance on win probabilities.
+const EPSILON: f64 = 1e-9;
+const MAX_ITERATIONS: usize = 100;
+
+/// A win probability as a function of the bust value β: `fixed + bust × β`.
+///
+/// Keeping β symbolic lets the solver find the scores pair and its mirror
+/// in agreement by solving a linear system instead of replaying turns.
+#[derive(Debug, Clone, Copy, Default)]
+struct Linear {
+    fixed: f64,
+    bust: f64,
+}
+
+impl Linear {
+    const BUST: Linear = Linear { fixed: 0.0, bust: 1.0 };
+
+    fn fixed(value: f64) -> Self {
+        Linear { fixed: value, bust: 0.0 }
+    }
+
+    fn at(self, bust_value: f64) -> f64 {
+        self.fixed + self.bust * bust_value
+    }
+}
+
+/// What a keep leads to; only the best keep per (dice left, ends turn)
+/// can be chosen, so the others are dropped.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+struct Keep {
+    /// Points kept, in score steps.
+    steps: u32,
+    /// Dice rolled again, 0 for hot dice.
+    left: u8,
+    ends_turn: bool,
+}
+
+/// Rolls that leave the same choice of keeps.
+struct OutcomeGroup {
+    probability: f64,
+    /// Range in `WinTable::keeps`.
+    keeps: Range<usize>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+struct TableKey {
+    rules: RuleSet,
+    target: u32,
+    exact: bool,
+    opening: u32,
+}
+
+pub struct WinTable {
+    key: TableKey,
+    /// Every turn score is a multiple of this.
+    step: u32,
+    /// Score slots per player, scores at or above the target end the game.
+    size: usize,
+    /// Roll outcomes by number of dice, and the chance of busting.
+    groups: Vec<Vec<OutcomeGroup>>,
+    keeps: Vec<Keep>,
+    bust: [f64; NUM_DICE],
+    /// W by `[me / step * size + opponent / step]`.
+    win: Vec<f64>,
+}
+
+fn tables() -> &'static Mutex<HashMap<TableKey, Arc<WinTable>>> {
+    static TABLES: OnceLock<Mutex<HashMap<TableKey, Arc<WinTable>>>> = OnceLock::new();
+    TABLES.get_or_init(Mutex::default)
+}
+
+impl WinTable {
+    /// Table for the game's rules, target and end mode, solved on first use.
+    pub fn get(config: &GameConfig) -> Arc<WinTable> {
+        Self::get_for(
+            &config.rules,
+            config.target_score,
+            config.end_mode,
+            config.opening_score.unwrap_or(0),
+        )
+    }
+
+    pub fn get_for(rules: &RuleSet, target: u32, end_mode: EndMode, opening: u32) -> Arc<WinTable> {
+        let key = TableKey {
+            rules: rules.clone(),
+            target,
+            exact: end_mode == EndMode::Exact,
+            opening,
+        };
+        if let Some(table) = tables().lock().expect("table cache poisoned").get(&key) {
+            return table.clone();
+        }
+        // Solve outside the lock, another thread may solve the same table meanwhile
+        let table = Arc::new(Self::solve(key.clone()));
+        tables()
+            .lock()
+            .expect("table cache poisoned")
+            .entry(key)
+            .or_insert(table)
+            .clone()
+    }
+
+    fn solve(key: TableKey) -> WinTable {
+        let outcomes: Vec<_> = (1..=NUM_DICE).map(|n| roll_outcomes(n, &key.rules)).collect();
+        let step = outcomes
+            .iter()
+            .flatten()
+            .flat_map(|outcome| &outcome.options)
+            .fold(0, |step, option| gcd(step, option.score))
+            .max(1);
+
+        let mut groups = Vec::new();
+        let mut all_keeps = Vec::new();
+        let mut bust = [0.0; NUM_DICE];
+        for (n, outcomes) in (1..=NUM_DICE).zip(outcomes) {
+            let mut by_keeps: HashMap<Vec<Keep>, f64> = HashMap::new();
+            for outcome in outcomes {
+                if outcome.options.is_empty() {
+                    bust[n - 1] += outcome.probability;
+                    continue;
+                }
+                let mut keeps: Vec<Keep> = Vec::new();
+                for option in &outcome.options {
+                    let keep = Keep {
+                        steps: option.score / step,
+                        left: (n - option.dice.len()) as u8,
+                        ends_turn: option.ends_turn,
+                    };
+                    match keeps
+                        .iter_mut()
+                        .find(|k| k.left == keep.left && k.ends_turn == keep.ends_turn)
+                    {
+                        Some(k) => k.steps = k.steps.max(keep.steps),
+                        None => keeps.push(keep),
+                    }
+                }
+                keeps.sort();
+                *by_keeps.entry(keeps).or_default() += outcome.probability;
+            }
+            groups.push(
+                by_keeps
+                    .into_iter()
+                    .map(|(keeps, probability)| {
+                        let start = all_keeps.len();
+                        all_keeps.extend(keeps);
+                        OutcomeGroup {
+                            probability,
+                            keeps: start..all_keeps.len(),
+                        }
+                    })
+                    .collect(),
+            );
+        }
+
+        let size = key.target.div_ceil(step) as usize;
+        let mut table = WinTable {
+            key,
+            step,
+            size,
+            groups,
+            keeps: all_keeps,
+            bust,
+            win: vec![0.0; size * size],
+        };
+        // Pairs with the same score sum only depend on higher sums, solve them in parallel
+        let threads = thread::available_parallelism().map_or(1, |n| n.get());
+        for sum in (0..=2 * (size - 1)).rev() {
+            let mes: Vec<usize> = (sum.saturating_sub(size - 1)..=sum / 2).collect();
+            let table_ref = &table;
+            let solved: Vec<(usize, f64, f64)> = thread::scope(|scope| {
+                let handles: Vec<_> = mes
+                    .chunks(mes.len().div_ceil(threads))
+                    .map(|chunk| {
+                        scope.spawn(move || {
+                            chunk
+                                .iter()
+                                .map(|&me| {
+                                    let (x, y) = table_ref.solve_pair(me, sum - me);
+                                    (me, x, y)
+                                })
+                                .collect::<Vec<_>>()
+                        })
+                    })
+                    .collect();
+                handles
+                    .into_iter()
+                    .flat_map(|handle| handle.join().expect("solver thread panicked"))
+                    .collect()
+            });
+            for (me, x, y) in solved {
+                table.win[me * size + sum - me] = x;
+                table.win[(sum - me) * size + me] = y;
+            }
+        }
+        table
+    }
+
+    /// W(me, opponent) and W(opponent, me), by score slot. They depend on
+    /// each other through busts: x = a(1 - y) and y = b(1 - x) with a and b
+    /// linear for a fixed policy. Solve, then redo with the policy for the
+    /// solution until it no longer changes.
+    fn solve_pair(&self, me: usize, opponent: usize) -> (f64, f64) {
+        // Start from the neighbouring pair, already solved
+        let guess = match opponent + 1 < self.size {
+            true => (
+                self.win[me * self.size + opponent + 1],
+                self.win[(opponent + 1) * self.size + me],
+            ),
+            false => (0.5, 0.5),
+        };
+        let (me, opponent) = (me as u32 * self.step, opponent as u32 * self.step);
+        let (mut x, mut y) = guess;
+        for _ in 0..MAX_ITERATIONS {
+            let a = self.turn(me, opponent, 1.0 - y).start_linear();
+            let (next_x, next_y) = if me == opponent {
+                let x = (a.fixed + a.bust) / (1.0 + a.bust);
+                (x, x)
+            } else {
+                let b = self.turn(opponent, me, 1.0 - x).start_linear();
+                let x = (a.fixed + a.bust - a.bust * (b.fixed + b.bust)) / (1.0 - a.bust * b.bust);
+                (x, b.at(1.0 - x))
+            };
+            let converged = (next_x - x).abs() < EPSILON && (next_y - y).abs() < EPSILON;
+            (x, y) = (next_x, next_y);
+            if converged {
+                break;
+            }
+        }
+        (x, y)
+    }
+
+    /// Probability to win when starting a turn with these scores.
+    pub fn start_value(&self, me: u32, opponent: u32) -> f64 {
+        if me >= self.key.target {
+            return 1.0;
+        }
+        if opponent >= self.key.target {
+            return 0.0;
+        }
+        self.win[(me / self.step) as usize * self.size + (opponent / self.step) as usize]
+    }
+
+    /// Solve the current turn, given the scores at its start and the
+    /// probability to win after a bust.
+    pub fn turn(&self, me: u32, opponent: u32, bust_value: f64) -> Turn {
+        let len = (self.key.target.saturating_sub(me) / self.step) as usize + 1;
+        let max_steps = self.keeps.iter().map(|keep| keep.steps as usize).max().unwrap_or(0);
+        let bank = (0..len + max_steps)
+            .map(|index| {
+                let turn_score = index as u32 * self.step;
+                let total = me + turn_score;
+                if self.key.exact && total > self.key.target {
+                    Some(Linear::BUST)
+                } else if total >= self.key.target {
+                    Some(Linear::fixed(1.0))
+                } else if turn_score == 0 || (me == 0 && turn_score < self.key.opening) {
+                    None
+                } else {
+                    Some(Linear::fixed(1.0 - self.start_value(opponent, total)))
+                }
+            })
+            .collect::<Vec<_>>();
+        // Past the target the game is won, or the turn lost on an exact finish
+        let after = bank
+            .iter()
+            .map(|bank| [bank.unwrap_or(Linear::BUST); NUM_DICE])
+            .collect();
+        let mut turn = Turn {
+            step: self.step,
+            finish: self.key.target.saturating_sub(me).div_ceil(self.step) as usize,
+            bust_value,
+            bank,
+            roll: vec![[Linear::default(); NUM_DICE]; len],
+            after,
+        };
+
+        for index in (0..len).rev() {
+            for dice in 1..=NUM_DICE {
+                let mut value = Linear {
+                    fixed: 0.0,
+                    bust: self.bust[dice - 1],
+                };
+                for group in &self.groups[dice - 1] {
+                    let mut best = Linear::BUST;
+                    let mut best_value = f64::NEG_INFINITY;
+                    for keep in &self.keeps[group.keeps.clone()] {
+                        let index = index + keep.steps as usize;
+                        let after = turn.after_keep(index, keep.left as usize, keep.ends_turn);
+                        let after_value = after.at(bust_value);
+                        if after_value > best_value {
+                            (best, best_value) = (after, after_value);
+                        }
+                    }
+                    value.fixed += group.probability * best.fixed;
+                    value.bust += group.probability * best.bust;
+                }
+                turn.roll[index][dice - 1] = value;
+            }
+
+            if turn.finishes(index) {
+                continue;
+            }
+            turn.after[index][0] = turn.roll[index][NUM_DICE - 1];
+            for left in 1..NUM_DICE {
+                let roll = turn.roll[index][left - 1];
+                turn.after[index][left] = turn.bank(index).map_or(roll, |bank| turn.better(bank, roll));
+            }
+        }
+        turn
+    }
+
+    /// Turn for the current player of a game, against the best opponent.
+    pub fn turn_for(&self, state: &GameState) -> Turn {
+        let me = state.current_player().score;
+        let opponent = state.other_scores().into_iter().max().unwrap_or(0);
+        self.turn(me, opponent, 1.0 - self.start_value(opponent, me))
+    }
+}
+
+/// Win probabilities within one turn, by turn score in score steps.
+pub struct Turn {
+    step: u32,
+    /// First turn score that reaches the target.
+    finish: usize,
+    bust_value: f64,
+    /// When banking, None if not allowed.
+    bank: Vec<Option<Linear>>,
+    /// When rolling, by `[turn score][dice - 1]`.
+    roll: Vec<[Linear; NUM_DICE]>,
+    /// Right after a keep, by `[turn score][dice left]` (0 for hot dice).
+    after: Vec<[Linear; NUM_DICE]>,
+}
+
+impl Turn {
+    fn better(&self, a: Linear, b: Linear) -> Linear {
+        if b.at(self.bust_value) > a.at(self.bust_value) { b } else { a }
+    }
+
+    fn finishes(&self, index: usize) -> bool {
+        index >= self.finish
+    }
+
+    fn bank(&self, index: usize) -> Option<Linear> {
+        self.bank.get(index).copied().flatten()
+    }
+
+    fn after_keep(&self, index: usize, left: usize, ends_turn: bool) -> Linear {
+        match self.after.get(index) {
+            Some(after) if !ends_turn => after[left],
+            // Banked right away, or lost under the opening score
+            _ => self.bank(index).unwrap_or(Linear::BUST),
+        }
+    }
+
+    fn start_linear(&self) -> Linear {
+        self.roll[0][NUM_DICE - 1]
+    }
+
+    /// Probability to win at the start of the turn.
+    pub fn start(&self) -> f64 {
+        self.start_linear().at(self.bust_value)
+    }
+
+    /// Probability to win after keeping `score` points from a roll and
+    /// leaving `left` dice (0 for hot dice).
+    pub fn keep_value(&self, turn_score: u32, score: u32, left: usize, ends_turn: bool) -> f64 {
+        let index = ((turn_score + score) / self.step) as usize;
+        self.after_keep(index, left, ends_turn).at(self.bust_value)
+    }
+
+    /// True when rolling gives a better chance to win than banking.
+    pub fn should_roll(&self, turn_score: u32, dice: usize) -> bool {
+        let index = (turn_score / self.step) as usize;
+        match (self.bank(index), self.roll.get(index)) {
+            (None, _) => true,
+            (Some(_), _) if self.finishes(index) => false,
+            (Some(bank), Some(roll)) => roll[dice - 1].at(self.bust_value) > bank.at(self.bust_value),
+            (Some(_), None) => false,
+        }
+    }
+}
+
+/// Computer player that maximises its probability to win the game.
+#[derive(Default)]
+pub struct WinProbabilityPlayer {
+    /// The current turn, solved for these scores.
+    turn: Option<(Arc<WinTable>, u32, u32, Turn)>,
+}
+
+impl WinProbabilityPlayer {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn turn(&mut self, state: &GameState) -> &Turn {
+        let table = WinTable::get_for(
+            &state.rules,
+            state.target_score,
+            state.end_mode,
+            state.opening_score.unwrap_or(0),
+        );
+        let me = state.current_player().score;
+        let opponent = state.other_scores().into_iter().max().unwrap_or(0);
+        let solved = self.turn.as_ref().is_some_and(|(solved_table, solved_me, solved_opponent, _)| {
+            Arc::ptr_eq(solved_table, &table) && (*solved_me, *solved_opponent) == (me, opponent)
+        });
+        if !solved {
+            let turn = table.turn(me, opponent, 1.0 - table.start_value(opponent, me));
+            self.turn = Some((table, me, opponent, turn));
+        }
+        &self.turn.as_ref().expect("solved above").3
+    }
+}
+
+impl Strategy for WinProbabilityPlayer {
+    fn choose_keep(&mut self, state: &GameState) -> Vec<usize> {
+        let turn_score = state.current_player().turn_score;
+        let turn = self.turn(state);
+        let mut best: Option<(&[usize], f64)> = None;
+        for option in &state.keep_options {
+            let left = state.dice.len() - option.dice.len();
+            let value = turn.keep_value(turn_score, option.score, left, option.ends_turn);
+            if best.is_none_or(|(_, best)| value > best) {
+                best = Some((&option.indices, value));
+            }
+        }
+        best.map(|(indices, _)| indices.to_vec()).unwrap_or_default()
+    }
+
+    fn should_bank(&mut self, state: &GameState) -> bool {
+        let turn_score = state.current_player().turn_score;
+        !self.turn(state).should_roll(turn_score, state.dice_count)
+    }
+}
@@ -1,5 +1,9 @@
 use dice6000::api::Status;
 use dice6000::api::create_router;
+use dice6000::config::{Config, ProviderConfig, ProviderKind};
+use dice6000::engine::Event;
+use dice6000::record::GameRecord;
+use std::sync::Arc;
 
 use axum::body::to_bytes;
 use axum::{
@@ -10,7 +14,7 @@ use tower::util::ServiceExt;
 
 #[tokio::test]
 async fn test_status_endpoint() {
-    let app = create_router();
+    let app = create_router(Arc::new(Config::init()));
 
     let response = app
         .oneshot(
@@ -35,3 +39,498 @@ async fn test_status_endpoint() {
         }
     );
 }
+
+async fn post(app: &axum::Router, uri: &str, body: &str) -> (StatusCode, serde_json::Value) {
+    post_as(app, uri, None, body).await
+}
+
+/// POST with the seat token `token`, if any.
+async fn post_as(app: &axum::Router, uri: &str, token: Option<&str>, body: &str) -> (StatusCode, serde_json::Value) {
+    let mut request = Request::builder()
+        .uri(uri)
+        .method("POST")
+        .header("Content-Type", "application/json");
+    if let Some(token) = token {
+        request = request.header("Authorization", format!("Bearer {}", token));
+    }
+    let response = app
+        .clone()
+        .oneshot(request.body(Body::from(body.to_string())).unwrap())
+        .await
+        .unwrap();
+    let status = response.status();
+    let body_bytes = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    (status, serde_json::from_slice(&body_bytes).unwrap_or(serde_json::Value::Null))
+}
+
+async fn post_json(app: &axum::Router, uri: &str, body: &str) -> serde_json::Value {
+    post(app, uri, body).await.1
+}
+
+/// `action` (roll, bank, next) in a `created` game, as its first seat.
+async fn play(app: &axum::Router, created: &serde_json::Value, action: &str, body: &str) -> serde_json::Value {
+    let uri = format!("/api/game/{}/{}", created["game_state"]["id"].as_str().unwrap(), action);
+    post_as(app, &uri, created["seats"][0]["token"].as_str(), body).await.1
+}
+
+#[tokio::test]
+async fn test_bank_below_opening_score_is_rejected() {
+    use futures_util::StreamExt;
+
+    let mut config = Config::init();
+    config.game.opening_score = Some(100_000);
+    let app = create_router(Arc::new(config));
+
+    // Retry until the human's first roll neither busts nor scores all six
+    // dice (hot dice must be rolled again before banking)
+    let (created, rolled) = loop {
+        let created = post_json(&app, "/api/game", "{}").await;
+        let rolled = play(&app, &created, "roll", "{}").await;
+        let hot_dice = rolled["game_state"]["keep_options"][0]["dice"]
+            .as_array()
+            .is_some_and(|dice| dice.len() == 6);
+        if rolled["game_state"]["turn_end_reason"].is_null() && !hot_dice {
+            break (created, rolled);
+        }
+    };
+    assert!(!rolled["game_state"]["keep_options"].as_array().unwrap().is_empty());
+
+    let events = get(&app, &format!("/api/game/{}/events", created["game_state"]["id"].as_str().unwrap())).await;
+    let mut events = events.into_body().into_data_stream();
+    events.next().await; // The current state
+
+    let banked = play(&app, &created, "bank", "{}").await;
+    assert_eq!(banked["success"], false);
+    assert!(banked["message"].as_str().unwrap().contains("100000"));
+    assert_eq!(banked["game_state"]["players"][0]["score"], 0);
+    // Nothing of the refused bank reaches the subscribers
+    assert!(tokio::time::timeout(std::time::Duration::from_millis(100), events.next()).await.is_err());
+}
+
+#[tokio::test]
+async fn test_seeded_games_roll_the_same_dice() {
+    let app = create_router(Arc::new(Config::init()));
+
+    let mut first_rolls = Vec::new();
+    for _ in 0..2 {
+        let created = post_json(&app, "/api/game", r#"{"seed": 1234}"#).await;
+        let rolled = play(&app, &created, "roll", "{}").await;
+        first_rolls.push(rolled["game_state"]["dice"].clone());
+    }
+    assert_eq!(first_rolls[0].as_array().unwrap().len(), 6);
+    assert_eq!(first_rolls[0], first_rolls[1]);
+}
+
+#[tokio::test]
+async fn test_game_record_endpoint() {
+    let app = create_router(Arc::new(Config::init()));
+    let created = post_json(&app, "/api/game", r#"{"seed": 7}"#).await;
+    let game_id = created["game_state"]["id"].as_str().unwrap().to_string();
+    play(&app, &created, "roll", "{}").await;
+
+    let response = app
+        .oneshot(
+            Request::builder()
+                .uri(format!("/api/game/{}/record", game_id))
+                .method("GET")
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+
+    let body_bytes = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    let record = GameRecord::from_jsonl(std::str::from_utf8(&body_bytes).unwrap()).unwrap();
+    assert_eq!(record.header.seed, Some(7));
+    assert!(matches!(record.events[0], Event::Rolled { player: 0, .. }));
+    assert!(record.replay(&mut |_, _| {}).is_ok());
+}
+
+#[tokio::test]
+async fn test_create_game_with_a_roster() {
+    let mut config = Config::init();
+    config.providers = vec![ProviderConfig {
+        name: "Local".to_string(),
+        kind: ProviderKind::OpenaiCompatible,
+        url: None,
+        model: "qwen".to_string(),
+        api_key_env: None,
+        timeout: None,
+        retries: None,
+    }];
+    let app = create_router(Arc::new(config));
+
+    let created = post_json(
+        &app,
+        "/api/game",
+        r#"{
+            "target_score": 3000,
+            "language": "en",
+            "players": [
+                { "type": "human", "name": "Alice" },
+                { "type": "computer", "strategy": "optimal" },
+                { "type": "llm", "provider": "Local", "model": "llama", "personality": "academic" },
+                { "type": "llm", "name": "Grumpy", "provider": "anthropic" }
+            ]
+        }"#,
+    )
+    .await;
+    assert_eq!(created["success"], true);
+    let state = &created["game_state"];
+    assert_eq!(state["target_score"], 3000);
+    let seats: Vec<(&str, bool, &str)> = state["players"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|p| {
+            (
+                p["name"].as_str().unwrap(),
+                p["is_human"].as_bool().unwrap(),
+                p["ai_type"].as_str().unwrap_or(""),
+            )
+        })
+        .collect();
+    assert_eq!(
+        seats,
+        [
+            ("Alice", true, ""),
+            ("Computer (optimal)", false, "optimal"),
+            ("Local", false, "llm:Local"),
+            ("Grumpy", false, "anthropic"),
+        ]
+    );
+}
+
+#[tokio::test]
+async fn test_invalid_roster_is_reported() {
+    let app = create_router(Arc::new(Config::init()));
+
+    let (status, body) = post(
+        &app,
+        "/api/game",
+        r#"{
+            "target_score": 0,
+            "players": [
+                { "type": "human", "name": "Bob" },
+                { "type": "computer", "name": "bob" },
+                { "type": "computer", "strategy": "psychic" },
+                { "type": "llm", "provider": "nowhere" },
+                { "type": "llm", "provider": "openai", "personality": "sleepy" }
+            ]
+        }"#,
+    )
+    .await;
+    assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+    assert_eq!(body["success"], false);
+    let errors: Vec<&str> = body["errors"].as_array().unwrap().iter().map(|e| e.as_str().unwrap()).collect();
+    assert_eq!(errors.len(), 5, "{:?}", errors);
+    assert!(errors[0].contains("target_score"));
+    assert!(errors[1].starts_with("seat 2: the name \"bob\" is already taken"));
+    assert!(errors[2].starts_with("seat 3: unknown strategy \"psychic\""));
+    assert!(errors[3].starts_with("seat 4: unknown provider \"nowhere\""));
+    assert!(errors[4].starts_with("seat 5: unknown personality \"sleepy\""));
+
+    let (_, body) = post(&app, "/api/game", r#"{"players": [{ "type": "human", "name": "Solo" }]}"#).await;
+    assert!(body["errors"][0].as_str().unwrap().contains("2 to 8 seats"));
+
+    // Malformed requests are reported the same way
+    let (status, body) = post(&app, "/api/game", r#"{"players": [{ "type": "robot" }]}"#).await;
+    assert!(status.is_client_error());
+    assert_eq!(body["success"], false);
+    assert!(body["errors"][0].as_str().unwrap().contains("robot"));
+}
+
+/// A router whose bots play without pausing.
+fn quick_bots_router() -> axum::Router {
+    let mut config = Config::init();
+    config.server.bot_delay_ms = 0;
+    create_router(Arc::new(config))
+}
+
+async fn get(app: &axum::Router, uri: &str) -> axum::response::Response {
+    app.clone()
+        .oneshot(Request::builder().uri(uri).method("GET").body(Body::empty()).unwrap())
+        .await
+        .unwrap()
+}
+
+#[tokio::test]
+async fn test_bots_play_on_their_own() {
+    let app = quick_bots_router();
+    let created = post_json(
+        &app,
+        "/api/game",
+        r#"{"seed": 3, "target_score": 1000, "players": [
+            { "type": "computer", "name": "A", "strategy": "conservative" },
+            { "type": "computer", "name": "B", "strategy": "aggressive" }
+        ]}"#,
+    )
+    .await;
+    let game_id = created["game_state"]["id"].as_str().unwrap().to_string();
+
+    // Humans cannot play a bot's turn
+    let (status, _) = post(&app, &format!("/api/game/{}/roll", game_id), "{}").await;
+    assert_eq!(status, StatusCode::CONFLICT);
+
+    let record = loop {
+        tokio::time::sleep(std::time::Duration::from_millis(20)).await;
+        let response = get(&app, &format!("/api/game/{}/record", game_id)).await;
+        let bytes = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let record = GameRecord::from_jsonl(std::str::from_utf8(&bytes).unwrap()).unwrap();
+        if record.events.iter().any(|event| matches!(event, Event::GameOver { .. })) {
+            break record;
+        }
+    };
+    assert!(record.events.iter().any(|event| matches!(event, Event::TurnStarted { player: 1, .. })));
+    assert!(record.events.iter().any(|event| matches!(event, Event::Explanation { .. })));
+}
+
+#[tokio::test]
+async fn test_event_stream() {
+    use futures_util::StreamExt;
+
+    let app = quick_bots_router();
+    let created = post_json(
+        &app,
+        "/api/game",
+        r#"{"seed": 5, "players": [
+            { "type": "human", "name": "Alice" },
+            { "type": "computer", "name": "Bot" }
+        ]}"#,
+    )
+    .await;
+    let game_id = created["game_state"]["id"].as_str().unwrap().to_string();
+
+    let response = get(&app, &format!("/api/game/{}/events", game_id)).await;
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(response.headers()["content-type"], "text/event-stream");
+    let mut body = response.into_body().into_data_stream();
+    let mut next_event = async || {
+        let frame = body.next().await.unwrap().unwrap();
+        let text = String::from_utf8(frame.to_vec()).unwrap();
+        let name = text.lines().find_map(|line| line.strip_prefix("event: ")).unwrap().to_string();
+        let data = text.lines().find_map(|line| line.strip_prefix("data: ")).unwrap();
+        (name, serde_json::from_str::<serde_json::Value>(data).unwrap())
+    };
+
+    let (name, update) = next_event().await;
+    assert_eq!(name, "state");
+    assert_eq!(update["game_state"]["id"], game_id.as_str());
+
+    play(&app, &created, "roll", "{}").await;
+    let (name, update) = next_event().await;
+    assert_eq!(name, "rolled");
+    assert_eq!(update["event"]["player"], 0);
+    assert_eq!(update["game_state"]["dice"].as_array().unwrap().len(), 6);
+}
+
+#[tokio::test]
+async fn test_seat_tokens_guard_the_turns() {
+    let app = create_router(Arc::new(Config::init()));
+    let created = post_json(
+        &app,
+        "/api/game",
+        r#"{"players": [{ "type": "human", "name": "Alice" }, { "type": "human", "name": "Bob" }]}"#,
+    )
+    .await;
+    // The creator holds both seats, and nobody else can join
+    assert_eq!(created["seats"].as_array().unwrap().len(), 2);
+    assert!(created["invite_code"].is_null());
+    let roll = format!("/api/game/{}/roll", created["game_state"]["id"].as_str().unwrap());
+
+    let (status, _) = post_as(&app, &roll, None, "{}").await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+    let (status, _) = post_as(&app, &roll, created["seats"][1]["token"].as_str(), "{}").await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+    let (status, _) = post_as(&app, &roll, Some("not-a-token"), "{}").await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+    let (status, _) = post_as(&app, &roll, created["seats"][0]["token"].as_str(), "{}").await;
+    assert_eq!(status, StatusCode::OK);
+}
+
+#[tokio::test]
+async fn test_join_a_lobby() {
+    let app = quick_bots_router();
+    let created = post_json(
+        &app,
+        "/api/game",
+        r#"{"players": [
+            { "type": "human", "name": "Alice" },
+            { "type": "open" },
+            { "type": "open", "name": "Carol" },
+            { "type": "computer", "name": "Bot" }
+        ]}"#,
+    )
+    .await;
+    let code = created["invite_code"].as_str().unwrap().to_string();
+    assert_eq!(code.len(), 6);
+    assert_eq!(created["seats"].as_array().unwrap().len(), 1);
+    assert_eq!(created["game_state"]["players"][1]["name"], "Seat 2");
+    let roll = format!("/api/game/{}/roll", created["game_state"]["id"].as_str().unwrap());
+
+    let lobby = json_of(get(&app, &format!("/api/lobby/{}", code.to_lowercase())).await).await;
+    assert_eq!(lobby["open_seats"], serde_json::json!([1, 2]));
+    assert_eq!(get(&app, "/api/lobby/NOPE42").await.status(), StatusCode::NOT_FOUND);
+
+    // Nobody plays while seats are open
+    let (status, _) = post_as(&app, &roll, created["seats"][0]["token"].as_str(), "{}").await;
+    assert_eq!(status, StatusCode::CONFLICT);
+
+    let join = format!("/api/lobby/{}/join", code);
+    let (status, _) = post(&app, &join, r#"{"name": "alice"}"#).await;
+    assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+    let (status, _) = post(&app, &join, r#"{"name": "Dave", "seat": 0}"#).await;
+    assert_eq!(status, StatusCode::CONFLICT);
+
+    let (status, bob) = post(&app, &join, r#"{"name": "Bob"}"#).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(bob["seat"]["seat"], 1);
+    assert_eq!(bob["game_state"]["players"][1]["name"], "Bob");
+    let (_, carol) = post(&app, &join, r#"{"name": "Carol", "seat": 2}"#).await;
+    assert_eq!(carol["seat"]["seat"], 2);
+    let (status, _) = post(&app, &join, r#"{"name": "Eve"}"#).await;
+    assert_eq!(status, StatusCode::CONFLICT);
+
+    // Full: the game starts with Alice
+    let (status, _) = post_as(&app, &roll, bob["seat"]["token"].as_str(), "{}").await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+    let (status, _) = post_as(&app, &roll, created["seats"][0]["token"].as_str(), "{}").await;
+    assert_eq!(status, StatusCode::OK);
+}
+
+async fn json_of(response: axum::response::Response) -> serde_json::Value {
+    let bytes = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    serde_json::from_slice(&bytes).unwrap()
+}
+
+#[tokio::test]
+async fn test_read_endpoints() {
+    let app = create_router(Arc::new(Config::init()));
+    let alice = post_json(
+        &app,
+        "/api/game",
+        r#"{"players": [{ "type": "human", "name": "Alice" }, { "type": "computer", "name": "Bot" }]}"#,
+    )
+    .await;
+    let alice_id = alice["game_state"]["id"].as_str().unwrap().to_string();
+    post_json(
+        &app,
+        "/api/game",
+        r#"{"players": [{ "type": "human", "name": "Bob" }, { "type": "open" }]}"#,
+    )
+    .await;
+
+    // Reading a game twice gives the same game
+    let rolled = play(&app, &alice, "roll", "{}").await;
+    let first = json_of(get(&app, &format!("/api/game/{}", alice_id)).await).await;
+    let second = json_of(get(&app, &format!("/api/game/{}", alice_id)).await).await;
+    assert_eq!(first["game_state"]["dice"], rolled["game_state"]["dice"]);
+    assert_eq!(first, second);
+
+    let scores = json_of(get(&app, &format!("/api/game/{}/scores", alice_id)).await).await;
+    assert_eq!(scores["current_player"], "Alice");
+    assert_eq!(scores["players"][0]["is_current"], true);
+    assert_eq!(scores["players"][0]["turn_score"], rolled["game_state"]["players"][0]["turn_score"]);
+    assert_eq!(scores["players"][1]["name"], "Bot");
+
+    let list = |query: &'static str| {
+        let app = app.clone();
+        async move {
+            let games = json_of(get(&app, &format!("/api/games{}", query)).await).await;
+            let mut players: Vec<String> = games["games"]
+                .as_array()
+                .unwrap()
+                .iter()
+                .map(|game| game["players"][0].as_str().unwrap().to_string())
+                .collect();
+            players.sort();
+            players
+        }
+    };
+    assert_eq!(list("").await, ["Alice", "Bob"]);
+    assert_eq!(list("?status=active").await, ["Alice", "Bob"]);
+    assert!(list("?status=finished").await.is_empty());
+    assert_eq!(list("?player=bot").await, ["Alice"]);
+    assert_eq!(list("?status=active&player=Bob").await, ["Bob"]);
+
+    let response = get(&app, "/api/games?status=paused").await;
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    assert_eq!(json_of(response).await["code"], "invalid_request");
+}
+
+#[tokio::test]
+async fn test_errors_are_json() {
+    let app = create_router(Arc::new(Config::init()));
+
+    let response = get(&app, "/api/game/nowhere").await;
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    let body = json_of(response).await;
+    assert_eq!(body["success"], false);
+    assert_eq!(body["code"], "game_not_found");
+    assert!(body["message"].as_str().unwrap().contains("nowhere"));
+
+    let created = post_json(&app, "/api/game", "{}").await;
+    let roll = format!("/api/game/{}/roll", created["game_state"]["id"].as_str().unwrap());
+    let (status, body) = post(&app, &roll, "{}").await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+    assert_eq!(body["code"], "missing_token");
+    let (status, body) = post_as(&app, &roll, created["seats"][0]["token"].as_str(), r#"{"keep": "all"}"#).await;
+    assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+    assert_eq!(body["code"], "invalid_request");
+
+    // Passing before rolling breaks the rules
+    let (status, body) = post_as(
+        &app,
+        &format!("/api/game/{}/next", created["game_state"]["id"].as_str().unwrap()),
+        created["seats"][0]["token"].as_str(),
+        "",
+    )
+    .await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert_eq!(body["code"], "invalid_action");
+    assert!(!body["message"].as_str().unwrap().is_empty());
+
+    let (_, body) = post(&app, "/api/game", r#"{"target_score": 0}"#).await;
+    assert_eq!(body["code"], "invalid_game");
+}
+
+#[tokio::test]
+async fn test_illegal_keeps_do_not_stop_the_bots() {
+    struct Cheater;
+
+    impl dice6000::strategy::Strategy for Cheater {
+        fn choose_keep(&mut self, state: &dice6000::engine::GameState) -> Vec<usize> {
+            vec![state.dice.len()]
+        }
+
+        fn should_bank(&mut self, _state: &dice6000::engine::GameState) -> bool {
+            true
+        }
+    }
+    dice6000::strategy::register("cheater", |_| Box::new(Cheater));
+
+    let app = quick_bots_router();
+    let created = post_json(
+        &app,
+        "/api/game",
+        r#"{"seed": 9, "target_score": 500, "players": [
+            { "type": "computer", "name": "A", "strategy": "cheater" },
+            { "type": "computer", "name": "B", "strategy": "cheater" }
+        ]}"#,
+    )
+    .await;
+    let game_id = created["game_state"]["id"].as_str().unwrap().to_string();
+
+    let record = loop {
+        tokio::time::sleep(std::time::Duration::from_millis(20)).await;
+        let response = get(&app, &format!("/api/game/{}/record", game_id)).await;
+        let bytes = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let record = GameRecord::from_jsonl(std::str::from_utf8(&bytes).unwrap()).unwrap();
+        if record.events.iter().any(|event| matches!(event, Event::GameOver { .. })) {
+            break record;
+        }
+    };
+    assert!(record.events.iter().any(|event| matches!(event, Event::KeepRejected { .. })));
+    assert!(record.replay(&mut |_, _| {}).is_ok());
+}
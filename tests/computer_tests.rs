@@ -1,7 +1,10 @@
 use dice6000::computer::{
-    AIPersonality, ComputerAI, DecisionContext, GamePhase, PerformanceTracker,
+    AIPersonality, ComputerAI, DecisionContext, GamePhase, PerformanceTracker, computer_step,
 };
 use dice6000::config::Config;
+use dice6000::dice::{ScriptedDice, SeededDice};
+use dice6000::engine::{Action, Event, GameState, play_turn};
+use dice6000::strategy::{self, Strategy};
 
 #[test]
 fn test_ai_personalities() {
@@ -147,3 +150,80 @@ fn test_adaptive_behavior_detailed() {
         initial_risk
     );
 }
+
+#[test]
+fn test_only_adaptive_adapts() {
+    let mut ai = ComputerAI::new(3000, &[3000], AIPersonality::Balanced);
+    for _ in 0..10 {
+        ai.add_test_decision(true, 400, 1);
+    }
+    let mut performance = PerformanceTracker::new();
+    for _ in 0..5 {
+        performance.record_bust(300, 1);
+    }
+    assert_eq!(performance.bust_rate(), 1.0);
+    assert_eq!(performance.busts_with(1), 5);
+
+    let context = DecisionContext {
+        remaining_dice: 1,
+        turn_score: 0,
+        total_score: 3000,
+        roll_count: 3,
+        last_roll: &[2],
+        performance: &performance,
+    };
+    ai.adapt_from_history(&context);
+    assert_eq!(ai.risk_tolerance(), 0.5);
+    assert_eq!(ai.decision_history_len(), 10);
+}
+
+#[test]
+fn test_adaptive_is_a_computer_strategy() {
+    assert!(strategy::create("adaptive", &Config::init()).is_some());
+    assert_eq!(AIPersonality::from_name("Adaptive").map(|p| format!("{:?}", p)).as_deref(), Some("Adaptive"));
+    assert_eq!(Config::init().game.computer_strategy, "adaptive");
+}
+
+#[test]
+fn test_adaptive_remembers_its_turns() {
+    let config = Config::init();
+    let mut state = GameState::new("adaptive".to_string(), false, false, &config);
+    state.current_player_index = 1; // the computer
+    let mut adaptive = ComputerAI::new(0, &[0], AIPersonality::Adaptive);
+    let mut opponent = ComputerAI::new(0, &[0], AIPersonality::Balanced);
+    let mut dice = SeededDice::new(9);
+    for _ in 0..3 {
+        play_turn(&mut state, &mut adaptive, &mut dice, &mut |_, _| {}).unwrap();
+        state.apply(Action::NextPlayer, &mut dice).unwrap();
+        play_turn(&mut state, &mut opponent, &mut dice, &mut |_, _| {}).unwrap();
+        state.apply(Action::NextPlayer, &mut dice).unwrap();
+    }
+    // Every banked or busted turn counts, not just the last one
+    assert_eq!(adaptive.performance().turns(), 3);
+}
+
+/// Keeps a die that is not there.
+struct OutOfRange;
+
+impl Strategy for OutOfRange {
+    fn choose_keep(&mut self, _state: &GameState) -> Vec<usize> {
+        vec![42]
+    }
+
+    fn should_bank(&mut self, _state: &GameState) -> bool {
+        false
+    }
+}
+
+#[test]
+fn test_computer_step_replaces_an_illegal_keep() {
+    let config = Config::init();
+    let mut state = GameState::new("step".to_string(), false, false, &config);
+    state.current_player_index = 1;
+    let mut dice = ScriptedDice::new(vec![vec![1, 5, 2, 3, 4, 4]]);
+
+    let summary = computer_step(&mut state, &mut OutOfRange, &mut dice);
+    assert!(summary.events.iter().any(|e| matches!(e, Event::KeepRejected { player: 1, .. })));
+    assert!(summary.events.iter().any(|e| matches!(e, Event::Kept { dice, .. } if dice == &vec![1, 5])));
+    assert_eq!(state.players[1].turn_score, 150);
+}
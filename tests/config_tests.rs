@@ -0,0 +1,59 @@
+use dice6000::config::{BankLimits, Config, EndMode};
+use dice6000::engine::GameState;
+
+/// Bank limits the engine enforces for the first player of a game.
+fn bank_limits(config: &Config, opened: bool, score: u32) -> BankLimits {
+    let mut state = GameState::new("limits".to_string(), false, false, config);
+    state.players[0].opened = opened;
+    state.players[0].score = score;
+    state.bank_limits()
+}
+
+#[test]
+fn test_no_opening_score_by_default() {
+    let config = Config::init();
+    assert_eq!(bank_limits(&config, false, 0).min, 0);
+    assert!(bank_limits(&config, false, 0).check(50).is_ok());
+}
+
+#[test]
+fn test_opening_score_applies_until_on_the_board() {
+    let mut config = Config::init();
+    config.game.opening_score = Some(500);
+
+    assert_eq!(bank_limits(&config, false, 0).min, 500);
+    assert_eq!(bank_limits(&config, true, 0).min, 0);
+    assert!(bank_limits(&config, false, 0).check(500).is_ok());
+    assert!(bank_limits(&config, true, 0).check(50).is_ok());
+
+    let reason = bank_limits(&config, false, 0).check(350).unwrap_err();
+    assert!(reason.contains("500"));
+    assert!(reason.contains("350"));
+}
+
+#[test]
+fn test_default_target_and_end_mode() {
+    let config = Config::init();
+    assert_eq!(config.game.target_score, 6000);
+    assert_eq!(config.game.end_mode, EndMode::Reach);
+    assert_eq!(bank_limits(&config, true, 5900).max, None);
+}
+
+#[test]
+fn test_exact_finish_limits() {
+    let mut config = Config::init();
+    config.game.end_mode = EndMode::Exact;
+    config.game.target_score = 3000;
+
+    let limits = bank_limits(&config, true, 2650);
+    assert_eq!(limits.max, Some(350));
+    assert!(!limits.overshoots(350));
+    assert!(limits.overshoots(400));
+}
+
+#[test]
+fn test_shipped_config_writes_no_files() {
+    let config = Config::load("config.yaml").unwrap();
+    assert_eq!(config.game.record_dir, None);
+    assert_eq!(config.game.save_dir, None);
+}
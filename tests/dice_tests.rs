@@ -0,0 +1,77 @@
+use dice6000::computer::controller_from_config;
+use dice6000::config::Config;
+use dice6000::dice::{DiceSource, ScriptedDice, SeededDice};
+use dice6000::engine::{Event, GameState, TurnPhase, play_turn};
+
+#[test]
+fn test_same_seed_same_rolls() {
+    let mut a = SeededDice::new(42);
+    let mut b = SeededDice::new(42);
+    for n in [6, 5, 3, 1] {
+        let roll = a.roll(n);
+        assert_eq!(roll.len(), n);
+        assert!(roll.iter().all(|d| (1..=6).contains(d)));
+        assert_eq!(roll, b.roll(n));
+    }
+
+    let mut c = SeededDice::new(43);
+    let a_rolls: Vec<Vec<u8>> = (0..5).map(|_| a.roll(6)).collect();
+    let c_rolls: Vec<Vec<u8>> = (0..5).map(|_| c.roll(6)).collect();
+    assert_ne!(a_rolls, c_rolls);
+}
+
+#[test]
+fn test_seeded_dice_resume_after_serialization() {
+    let mut dice = SeededDice::new(7);
+    dice.roll(6);
+    let saved = serde_json::to_string(&dice).unwrap();
+    let mut resumed: SeededDice = serde_json::from_str(&saved).unwrap();
+    assert_eq!(resumed.seed(), 7);
+    assert_eq!(resumed.roll(6), dice.roll(6));
+}
+
+#[test]
+fn test_scripted_dice_in_order() {
+    let mut dice = ScriptedDice::new(vec![vec![1, 2, 3], vec![5]]);
+    assert_eq!(dice.roll(3), vec![1, 2, 3]);
+    assert_eq!(dice.remaining(), 1);
+    assert_eq!(dice.roll(1), vec![5]);
+    assert!(dice.roll(6).is_empty());
+}
+
+/// The configured computer's turn on `dice`, and its events.
+fn computer_turn(dice: &mut ScriptedDice) -> (GameState, Vec<Event>) {
+    let config = Config::init();
+    let mut state = GameState::new("test".to_string(), false, false, &config);
+    state.current_player_index = 1;
+    let mut computer = controller_from_config(&config);
+    let mut events = Vec::new();
+    play_turn(&mut state, &mut computer, dice, &mut |_, event| events.push(event.clone())).unwrap();
+    (state, events)
+}
+
+#[test]
+fn test_computer_turn_bust() {
+    let (state, events) = computer_turn(&mut ScriptedDice::new(vec![vec![2, 3, 4, 6, 2, 3]]));
+    assert!(matches!(&events[0], Event::Rolled { dice, .. } if dice == &[2, 3, 4, 6, 2, 3]));
+    assert!(events.iter().any(|event| matches!(event, Event::Busted { .. })));
+    assert_eq!(state.phase(), TurnPhase::TurnOver);
+}
+
+#[test]
+fn test_computer_turn_hot_dice() {
+    // Hot dice must be rolled again, here to a bust
+    let mut dice = ScriptedDice::new(vec![vec![1, 1, 5, 5, 2, 2], vec![2, 3, 4, 6, 2, 3]]);
+    let (state, events) = computer_turn(&mut dice);
+    assert!(events.iter().any(|event| matches!(event, Event::HotDice { .. })));
+    let rolls: Vec<usize> = events
+        .iter()
+        .filter_map(|event| match event {
+            Event::Rolled { dice, .. } => Some(dice.len()),
+            _ => None,
+        })
+        .collect();
+    assert_eq!(rolls, [6, 6]);
+    assert_eq!(dice.remaining(), 0);
+    assert_eq!(state.phase(), TurnPhase::TurnOver);
+}
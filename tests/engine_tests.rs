@@ -0,0 +1,172 @@
+use dice6000::config::{Config, EndMode};
+use dice6000::dice::ScriptedDice;
+use dice6000::engine::{
+    Action, BustReason, EngineError, Event, GameState, TurnController, TurnPhase, play_turn,
+};
+
+fn game(config: &Config) -> GameState {
+    GameState::new("test".to_string(), false, false, config)
+}
+
+fn scripted(rolls: &[&[u8]]) -> ScriptedDice {
+    ScriptedDice::new(rolls.iter().map(|r| r.to_vec()).collect())
+}
+
+fn no_dice(_: usize) -> Vec<u8> {
+    panic!("no roll expected")
+}
+
+/// Keeps every scoring die and rolls again until the turn reaches `bank_at`.
+struct Greedy {
+    bank_at: u32,
+}
+
+impl TurnController for Greedy {
+    fn choose_keep(&mut self, state: &GameState) -> Vec<usize> {
+        state.default_keep()
+    }
+
+    fn roll_again(&mut self, state: &GameState) -> bool {
+        state.current_player().turn_score < self.bank_at
+    }
+}
+
+#[test]
+fn test_roll_keep_bank() {
+    let mut state = game(&Config::init());
+    let mut roll = scripted(&[&[1, 5, 2, 3, 4, 4]]);
+
+    let events = state.apply(Action::Roll, &mut roll).unwrap();
+    assert_eq!(events, vec![Event::Rolled { player: 0, dice: vec![1, 5, 2, 3, 4, 4] }]);
+    assert_eq!(state.phase(), TurnPhase::AwaitingKeep);
+    assert_eq!(state.apply(Action::Bank, &mut no_dice), Err(EngineError::MustKeep));
+
+    state.apply(Action::Keep(vec![0]), &mut no_dice).unwrap();
+    assert_eq!(state.dice_count, 5);
+    assert_eq!(state.current_player().turn_score, 100);
+
+    let events = state.apply(Action::Bank, &mut no_dice).unwrap();
+    assert_eq!(events, vec![Event::Banked { player: 0, points: 100, total: 100 }]);
+    assert_eq!(state.phase(), TurnPhase::TurnOver);
+    assert_eq!(state.apply(Action::Roll, &mut no_dice), Err(EngineError::TurnOver));
+
+    let events = state.apply(Action::NextPlayer, &mut no_dice).unwrap();
+    assert_eq!(events, vec![Event::TurnStarted { player: 1, turn_number: 2 }]);
+    assert_eq!(state.phase(), TurnPhase::AwaitingRoll);
+}
+
+#[test]
+fn test_hot_dice_must_roll_six_again() {
+    let mut state = game(&Config::init());
+    let mut roll = scripted(&[&[1, 1, 1, 2, 3, 4], &[5, 5, 5], &[2, 3, 4, 6, 2, 6]]);
+
+    state.apply(Action::Roll, &mut roll).unwrap();
+    state.apply(Action::Keep(vec![0, 1, 2]), &mut no_dice).unwrap();
+    state.apply(Action::Roll, &mut roll).unwrap();
+    let events = state.apply(Action::Keep(vec![0, 1, 2]), &mut no_dice).unwrap();
+    assert!(events.contains(&Event::HotDice { player: 0 }));
+    assert_eq!(state.dice_count, 6);
+    assert!(state.must_roll());
+    assert_eq!(state.apply(Action::Bank, &mut no_dice), Err(EngineError::MustRoll));
+
+    let events = state.apply(Action::Roll, &mut roll).unwrap();
+    assert!(events.contains(&Event::Busted { player: 0, reason: BustReason::NoScore, lost: 1500 }));
+    assert_eq!(state.players[0].score, 0);
+}
+
+#[test]
+fn test_invalid_actions_leave_state_untouched() {
+    let mut state = game(&Config::init());
+    assert_eq!(state.apply(Action::Bank, &mut no_dice), Err(EngineError::NothingToBank));
+    assert_eq!(state.apply(Action::Keep(vec![0]), &mut no_dice), Err(EngineError::NothingToKeep));
+    assert_eq!(state.apply(Action::NextPlayer, &mut no_dice), Err(EngineError::TurnInProgress));
+    assert!(matches!(
+        state.apply(Action::Roll, &mut |_| vec![1, 7, 2]),
+        Err(EngineError::InvalidRoll(_))
+    ));
+
+    state.apply(Action::Roll, &mut |_| vec![1, 2, 3, 4, 6, 6]).unwrap();
+    assert!(matches!(
+        state.apply(Action::Keep(vec![1]), &mut no_dice),
+        Err(EngineError::InvalidKeep(_))
+    ));
+    assert_eq!(state.phase(), TurnPhase::AwaitingKeep);
+    assert_eq!(state.current_player().turn_score, 0);
+}
+
+#[test]
+fn test_opening_score_rejects_bank() {
+    let mut config = Config::init();
+    config.game.opening_score = Some(500);
+    let mut state = game(&config);
+
+    state.apply(Action::Roll, &mut |_| vec![1, 2, 3, 4, 6, 6]).unwrap();
+    state.apply(Action::Keep(vec![0]), &mut no_dice).unwrap();
+    let rejected = state.apply(Action::Bank, &mut no_dice);
+    assert!(matches!(rejected, Err(EngineError::BankRejected(reason)) if reason.contains("500")));
+    assert_eq!(state.phase(), TurnPhase::AwaitingRoll);
+}
+
+#[test]
+fn test_exact_finish_overshoot_busts() {
+    let mut config = Config::init();
+    config.game.end_mode = EndMode::Exact;
+    config.game.target_score = 1000;
+    let mut state = game(&config);
+    state.players[0].score = 900;
+    state.players[0].opened = true;
+
+    state.apply(Action::Roll, &mut |_| vec![1, 5, 2, 3, 4, 4]).unwrap();
+    let events = state.apply(Action::Keep(vec![0, 1]), &mut no_dice).unwrap();
+    assert!(events.contains(&Event::Busted { player: 0, reason: BustReason::Overshoot, lost: 150 }));
+    assert_eq!(state.players[0].score, 900);
+}
+
+#[test]
+fn test_play_turn_retries_refused_bank() {
+    let mut config = Config::init();
+    config.game.opening_score = Some(300);
+    let mut state = game(&config);
+    let mut roll = scripted(&[&[1, 2, 3, 4, 6, 6], &[1, 1, 2, 3, 4]]);
+    let mut events = Vec::new();
+
+    play_turn(&mut state, &mut Greedy { bank_at: 0 }, &mut roll, &mut |_, e| events.push(e.clone()))
+        .unwrap();
+
+    assert!(events.iter().any(|e| matches!(e, Event::BankRejected { .. })));
+    assert_eq!(events.last(), Some(&Event::Banked { player: 0, points: 300, total: 300 }));
+    assert!(state.players[0].opened);
+}
+
+#[test]
+fn test_winner_is_a_seat_not_a_name() {
+    let mut config = Config::init();
+    config.game.target_score = 1000;
+    let mut state = game(&config);
+    for player in &mut state.players {
+        player.name = "Twin".to_string();
+    }
+    state.apply(Action::Roll, &mut |_| vec![2, 3, 4, 6, 2, 3]).unwrap();
+    state.apply(Action::NextPlayer, &mut no_dice).unwrap();
+
+    state.players[1].score = 1000;
+    state.players[1].turn_score = 0;
+    state.apply(Action::Roll, &mut |_| vec![2, 3, 4, 6, 2, 3]).unwrap();
+    let events = state.apply(Action::NextPlayer, &mut no_dice).unwrap();
+    assert_eq!(events, vec![Event::GameOver { winner: 1, score: 1000 }]);
+    assert_eq!(state.winner_index, Some(1));
+}
+
+#[test]
+fn test_game_over_after_next_player() {
+    let mut config = Config::init();
+    config.game.target_score = 1000;
+    let mut state = game(&config);
+    let mut roll = scripted(&[&[1, 1, 1, 2, 3, 4]]);
+
+    play_turn(&mut state, &mut Greedy { bank_at: 1000 }, &mut roll, &mut |_, _| {}).unwrap();
+    let events = state.apply(Action::NextPlayer, &mut no_dice).unwrap();
+    assert_eq!(events, vec![Event::GameOver { winner: 0, score: 1000 }]);
+    assert_eq!(state.phase(), TurnPhase::GameOver);
+    assert_eq!(state.apply(Action::NextPlayer, &mut no_dice), Err(EngineError::GameOver));
+}
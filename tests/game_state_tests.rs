@@ -0,0 +1,120 @@
+use dice6000::api::models::GameState;
+use dice6000::config::{BustPenalty, Config, EndMode};
+use dice6000::engine::Action;
+
+const BUST_ROLL: [u8; 6] = [2, 3, 4, 6, 2, 3];
+
+fn roll(state: &mut GameState, dice: &[u8]) {
+    state.apply(Action::Roll, &mut |_| dice.to_vec()).unwrap();
+}
+
+/// Bust the human's turn, then the computer's, back to the human.
+fn bust_round(state: &mut GameState) {
+    for _ in 0..2 {
+        roll(state, &BUST_ROLL);
+        state.apply(Action::NextPlayer, &mut |_| Vec::new()).unwrap();
+    }
+}
+
+fn game(end_mode: EndMode) -> GameState {
+    let mut config = Config::init();
+    config.game.target_score = 1000;
+    config.game.end_mode = end_mode;
+    GameState::new("test".to_string(), false, false, &config)
+}
+
+#[test]
+fn test_reach_mode_ends_immediately() {
+    let mut state = game(EndMode::Reach);
+    state.players[0].score = 1050;
+    state.next_player();
+    state.check_winner();
+    assert!(state.game_over);
+    assert_eq!(state.winner.as_deref(), Some("Human"));
+}
+
+#[test]
+fn test_final_round_gives_others_a_last_turn() {
+    let mut state = game(EndMode::FinalRound);
+    state.players[0].score = 1050;
+    state.next_player();
+    state.check_winner();
+    assert!(!state.game_over);
+    assert_eq!(state.final_round_trigger, Some(0));
+
+    // The computer overtakes during its last turn
+    state.players[1].score = 1200;
+    state.next_player();
+    state.check_winner();
+    assert!(state.game_over);
+    assert_eq!(state.winner.as_deref(), Some("Computer"));
+}
+
+#[test]
+fn test_final_round_tie_goes_to_first_past_target() {
+    let mut state = game(EndMode::FinalRound);
+    state.players[0].score = 1100;
+    state.next_player();
+    state.check_winner();
+    state.players[1].score = 1100;
+    state.next_player();
+    state.check_winner();
+    assert_eq!(state.winner.as_deref(), Some("Human"));
+}
+
+#[test]
+fn test_bank_limits_follow_game_target() {
+    let mut state = game(EndMode::Exact);
+    state.players[0].score = 900;
+    assert_eq!(state.bank_limits().max, Some(100));
+}
+
+#[test]
+fn test_bust_streak_penalty() {
+    let mut config = Config::init();
+    config.game.bust_penalty = Some(BustPenalty {
+        streak: 3,
+        points: 500,
+    });
+    let mut state = GameState::new("test".to_string(), false, false, &config);
+    state.players[0].score = 1200;
+
+    for expected_streak in 1..=2 {
+        roll(&mut state, &BUST_ROLL);
+        assert_eq!(state.players[0].bust_streak, expected_streak);
+        assert_eq!(state.turn_end_reason.as_deref(), Some("busted"));
+        state.apply(Action::NextPlayer, &mut |_| Vec::new()).unwrap();
+        roll(&mut state, &BUST_ROLL);
+        state.apply(Action::NextPlayer, &mut |_| Vec::new()).unwrap();
+    }
+
+    roll(&mut state, &BUST_ROLL);
+    assert_eq!(state.players[0].score, 700);
+    assert_eq!(state.players[0].bust_streak, 0);
+    assert_eq!(state.turn_end_reason.as_deref(), Some("bust_penalty"));
+}
+
+#[test]
+fn test_bank_resets_bust_streak() {
+    let mut state = game(EndMode::Reach);
+    bust_round(&mut state);
+    bust_round(&mut state);
+    assert_eq!(state.players[0].bust_streak, 2);
+    roll(&mut state, &[5, 5, 5, 2, 3, 4]);
+    state.apply(Action::Keep(vec![0, 1, 2]), &mut |_| Vec::new()).unwrap();
+    state.apply(Action::Bank, &mut |_| Vec::new()).unwrap();
+    assert_eq!(state.players[0].bust_streak, 0);
+    assert_eq!(state.players[0].score, 500);
+}
+
+#[test]
+fn test_penalty_never_goes_below_zero() {
+    let mut player = game(EndMode::Reach).players[0].clone();
+    player.score = 200;
+    let penalty = Some(BustPenalty {
+        streak: 1,
+        points: 500,
+    });
+    assert_eq!(player.record_turn_result(true, penalty), Some(200));
+    assert_eq!(player.score, 0);
+}
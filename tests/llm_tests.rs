@@ -0,0 +1,329 @@
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::TcpListener;
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+
+use dice6000::ai::{LlmDecision, parse_decision};
+use dice6000::config::{Config, ProviderConfig, ProviderKind};
+use dice6000::dice::ScriptedDice;
+use dice6000::engine::{Action, GameState};
+use dice6000::game::setup_players;
+use dice6000::llm::{ChatRequest, LlmError, provider_from_config};
+use dice6000::strategy;
+
+/// Answer one HTTP request per response, in order, with that status and JSON
+/// body; the handle gives back the requests received (head and body).
+fn serve(responses: Vec<(u16, &'static str)>) -> (String, JoinHandle<Vec<String>>) {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let url = format!("http://{}", listener.local_addr().unwrap());
+    let handle = thread::spawn(move || {
+        let mut requests = Vec::new();
+        for (status, body) in responses {
+            let (mut stream, _) = listener.accept().unwrap();
+            let mut reader = BufReader::new(stream.try_clone().unwrap());
+            let mut request = String::new();
+            let mut length = 0;
+            loop {
+                let mut line = String::new();
+                reader.read_line(&mut line).unwrap();
+                if line == "\r\n" {
+                    break;
+                }
+                if let Some(value) = line.to_lowercase().strip_prefix("content-length:") {
+                    length = value.trim().parse().unwrap();
+                }
+                request.push_str(&line);
+            }
+            let mut content = vec![0; length];
+            reader.read_exact(&mut content).unwrap();
+            write!(
+                stream,
+                "HTTP/1.1 {} {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                status,
+                if status == 200 { "OK" } else { "Error" },
+                body.len(),
+                body
+            )
+            .unwrap();
+            requests.push(request + &String::from_utf8(content).unwrap());
+        }
+        requests
+    });
+    (url, handle)
+}
+
+fn serve_once(body: &'static str) -> (String, JoinHandle<String>) {
+    let (url, server) = serve(vec![(200, body)]);
+    (url, thread::spawn(move || server.join().unwrap().remove(0)))
+}
+
+fn provider(kind: ProviderKind, url: String) -> ProviderConfig {
+    ProviderConfig {
+        name: "Test".to_string(),
+        kind,
+        url: Some(url),
+        model: "test-model".to_string(),
+        api_key_env: None,
+        timeout: Some(5),
+        retries: Some(0),
+    }
+}
+
+fn request() -> ChatRequest {
+    ChatRequest::new("Be brief.", "Hello?").max_tokens(10)
+}
+
+#[test]
+fn test_openai_compatible_provider() {
+    let (url, server) = serve_once(r#"{"choices":[{"message":{"role":"assistant","content":"Hi!"}}]}"#);
+    let config = provider(ProviderKind::OpenaiCompatible, format!("{}/v1/chat/completions", url));
+    let answer = provider_from_config(&config).complete(&request()).unwrap();
+    assert_eq!(answer, "Hi!");
+
+    let received = server.join().unwrap();
+    assert!(received.starts_with("POST /v1/chat/completions"));
+    assert!(!received.to_lowercase().contains("authorization"));
+    assert!(received.contains(r#""role":"system""#));
+    assert!(received.contains(r#""model":"test-model""#));
+    assert!(received.contains(r#""max_tokens":10"#));
+}
+
+#[test]
+fn test_anthropic_provider() {
+    let (url, server) = serve_once(r#"{"content":[{"type":"text","text":"Bonjour"}]}"#);
+    // SAFETY: the variable is only read by this test
+    unsafe { std::env::set_var("DICE6000_TEST_ANTHROPIC_KEY", "secret") };
+    let mut config = provider(ProviderKind::Anthropic, url);
+    config.api_key_env = Some("DICE6000_TEST_ANTHROPIC_KEY".to_string());
+    let answer = provider_from_config(&config).complete(&request()).unwrap();
+    assert_eq!(answer, "Bonjour");
+
+    let received = server.join().unwrap();
+    assert!(received.contains("x-api-key: secret"));
+    assert!(received.contains(r#""system":"Be brief.""#));
+}
+
+#[test]
+fn test_ollama_chat_and_generate() {
+    let (url, server) = serve_once(r#"{"message":{"role":"assistant","content":" Salut \n"}}"#);
+    let config = provider(ProviderKind::Ollama, format!("{}/api/chat", url));
+    assert_eq!(provider_from_config(&config).complete(&request()).unwrap(), "Salut");
+    assert!(server.join().unwrap().contains(r#""num_predict":10"#));
+
+    let (url, server) = serve_once(r#"{"response":"Hello"}"#);
+    let config = provider(ProviderKind::Ollama, format!("{}/api/generate", url));
+    assert_eq!(provider_from_config(&config).complete(&request()).unwrap(), "Hello");
+    assert!(server.join().unwrap().contains(r#""prompt":"Hello?""#));
+}
+
+#[test]
+fn test_missing_api_key() {
+    let mut config = provider(ProviderKind::Openai, "http://127.0.0.1:9".to_string());
+    config.api_key_env = Some("DICE6000_TEST_UNSET_KEY".to_string());
+    let error = provider_from_config(&config).complete(&request()).unwrap_err();
+    assert_eq!(error, LlmError::MissingApiKey("DICE6000_TEST_UNSET_KEY".to_string()));
+}
+
+#[test]
+fn test_providers_list_gives_llm_players() {
+    let mut config = Config::init();
+    config.providers = vec![
+        provider(ProviderKind::OpenaiCompatible, "http://127.0.0.1:9".to_string()),
+        ProviderConfig {
+            name: "Claude".to_string(),
+            ..provider(ProviderKind::Anthropic, "http://127.0.0.1:9".to_string())
+        },
+    ];
+    // The providers list replaces the one-player-per-vendor setup
+    let players = setup_players(true, true, &config);
+    let llms: Vec<_> = players.iter().filter_map(|p| p.ai_type.as_deref()).collect();
+    assert_eq!(llms, ["computer", "llm:Test", "llm:Claude"]);
+
+    assert!(strategy::create("llm:Claude", &config).is_some());
+    assert!(strategy::create("llm:Nobody", &config).is_none());
+    assert_eq!(config.provider("ollama").unwrap().kind, ProviderKind::Ollama);
+}
+
+#[test]
+fn test_llm_player_follows_the_answer() {
+    let (url, server) =
+        serve_once(r#"{"choices":[{"message":{"content":"{\"keep\": 2, \"decision\": \"T\", \"explanation\": \"Safe.\"}"}}]}"#);
+    let mut config = Config::init();
+    config.providers = vec![provider(ProviderKind::OpenaiCompatible, url)];
+    let mut state = GameState::new("llm".to_string(), false, false, &config);
+    let mut dice = ScriptedDice::new(vec![vec![1, 5, 2, 3, 4, 4]]);
+    state.apply(Action::Roll, &mut dice).unwrap();
+
+    let mut player = strategy::create("llm:Test", &config).unwrap();
+    let keep = player.choose_keep(&state);
+    assert_eq!(keep, state.keep_options[1].indices);
+    assert_eq!(player.explain(), Some(("T".to_string(), "Safe.".to_string())));
+    assert!(player.should_bank(&state));
+    server.join().unwrap();
+}
+
+#[test]
+fn test_parse_decision_is_tolerant() {
+    let decision = |keep, bank, explanation: &str| LlmDecision {
+        keep,
+        bank,
+        explanation: explanation.to_string(),
+    };
+    assert_eq!(
+        parse_decision(r#"{"keep": 2, "decision": "R", "explanation": "Go."}"#, 3),
+        Ok(decision(2, false, "Go."))
+    );
+    // Code fence, prose around it and miscased keys
+    assert_eq!(
+        parse_decision("Sure!\n```json\n{\"Keep\": \"1\", \"DECISION\": \"take\", \"Explanation\": \"Safe.\"}\n```\nGood luck.", 3),
+        Ok(decision(1, true, "Safe."))
+    );
+    // The prompt's template comments, a raw line break and a cut-off end
+    assert_eq!(
+        parse_decision("{\n  \"keep\": 3, // option\n  \"decision\": \"T\",\n  \"explanation\": \"Three\nlines", 3),
+        Ok(decision(3, true, "Three lines"))
+    );
+    // A single option needs no keep
+    assert_eq!(parse_decision(r#"{"decision": "R"}"#, 1), Ok(decision(1, false, "")));
+}
+
+#[test]
+fn test_parse_decision_errors() {
+    assert!(parse_decision("I take the points.", 2).unwrap_err().contains("no JSON object"));
+    assert!(parse_decision(r#"{"keep": 1}"#, 2).unwrap_err().contains("'decision'"));
+    assert!(parse_decision(r#"{"keep": 1, "decision": "maybe"}"#, 2).unwrap_err().contains("\"maybe\""));
+    assert!(parse_decision(r#"{"decision": "T"}"#, 2).unwrap_err().contains("'keep'"));
+    assert!(parse_decision(r#"{"keep": 3, "decision": "T"}"#, 2).unwrap_err().contains("from 1 to 2"));
+}
+
+/// A game whose LLM player (provider "Test" at `url`) has a roll with two
+/// keep options to decide on.
+fn llm_decision_state(url: String, config: &mut Config) -> GameState {
+    config.providers = vec![provider(ProviderKind::OpenaiCompatible, url)];
+    let mut state = GameState::new("llm".to_string(), false, false, config);
+    let mut dice = ScriptedDice::new(vec![vec![1, 5, 2, 3, 4, 4]]);
+    state.apply(Action::Roll, &mut dice).unwrap();
+    state
+}
+
+#[test]
+fn test_prompt_describes_the_players() {
+    let (url, server) = serve_once(r#"{"choices":[{"message":{"content":"{\"keep\": 1, \"decision\": \"T\"}"}}]}"#);
+    let mut config = Config::init();
+    let mut state = llm_decision_state(url, &mut config);
+    state.players[0].name = "GPT".to_string();
+    state.players[0].is_human = false;
+    state.players[0].ai_type = Some("llm:Test".to_string());
+    state.players[1].name = "Alice".to_string();
+    state.players[1].is_human = true;
+    state.players[1].ai_type = None;
+
+    let mut player = strategy::create("llm:Test", &config).unwrap();
+    player.choose_keep(&state);
+    let request = server.join().unwrap();
+    assert!(request.contains("1) GPT (you): an AI language model, 0 points"));
+    assert!(request.contains("2) Alice: a human, 0 points"));
+    assert!(request.contains("Other players' scores: 2) Alice: 0"));
+    assert!(!request.contains("always a human"));
+}
+
+#[test]
+fn test_llm_player_is_asked_to_correct_its_answer() {
+    let (url, server) = serve(vec![
+        (200, r#"{"choices":[{"message":{"content":"Roll again, of course."}}]}"#),
+        (200, r#"{"choices":[{"message":{"content":"{\"keep\": 2, \"decision\": \"R\"}"}}]}"#),
+    ]);
+    let mut config = Config::init();
+    let state = llm_decision_state(url, &mut config);
+
+    let mut player = strategy::create("llm:Test", &config).unwrap();
+    assert_eq!(player.choose_keep(&state), state.keep_options[1].indices);
+    assert!(!player.should_bank(&state));
+
+    let requests = server.join().unwrap();
+    // The retry replays the conversation with the correction
+    assert!(requests[1].contains("Roll again, of course."));
+    assert!(requests[1].contains("no JSON object was found"));
+}
+
+#[test]
+fn test_llm_player_falls_back_after_the_retries() {
+    let (url, server) = serve(vec![
+        (200, r#"{"choices":[{"message":{"content":"{\"keep\": 9, \"decision\": \"R\"}"}}]}"#),
+        (200, r#"{"choices":[{"message":{"content":"{\"keep\": 9, \"decision\": \"R\"}"}}]}"#),
+    ]);
+    let mut config = Config::init();
+    config.game.llm_retries = 1;
+    config.game.llm_fallback = "conservative".to_string();
+    let state = llm_decision_state(url, &mut config);
+
+    let mut player = strategy::create("llm:Test", &config).unwrap();
+    let mut fallback = strategy::create("conservative", &config).unwrap();
+    assert_eq!(player.choose_keep(&state), fallback.choose_keep(&state));
+    let (_, explanation) = player.explain().unwrap();
+    assert!(explanation.contains("the conservative strategy plays instead"));
+    assert_eq!(server.join().unwrap().len(), 2);
+}
+
+#[test]
+fn test_transient_errors_are_retried() {
+    let (url, server) = serve(vec![
+        (503, "{}"),
+        (429, "{}"),
+        (200, r#"{"choices":[{"message":{"content":"Back."}}]}"#),
+    ]);
+    let mut config = provider(ProviderKind::OpenaiCompatible, url);
+    config.retries = Some(2);
+    assert_eq!(provider_from_config(&config).complete(&request()).unwrap(), "Back.");
+    assert_eq!(server.join().unwrap().len(), 3);
+}
+
+#[test]
+fn test_client_errors_are_not_retried() {
+    let (url, server) = serve(vec![(401, r#"{"error":"bad key"}"#)]);
+    let mut config = provider(ProviderKind::OpenaiCompatible, url);
+    config.retries = Some(2);
+    let error = provider_from_config(&config).complete(&request()).unwrap_err();
+    assert!(matches!(&error, LlmError::Request(e) if e.contains("401")));
+    assert_eq!(server.join().unwrap().len(), 1);
+}
+
+#[test]
+fn test_request_times_out() {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let url = format!("http://{}", listener.local_addr().unwrap());
+    // Accept the connection and never answer
+    thread::spawn(move || {
+        let _connection = listener.accept();
+        thread::sleep(Duration::from_secs(10));
+    });
+    let mut config = provider(ProviderKind::OpenaiCompatible, url);
+    config.timeout = Some(1);
+
+    let started = Instant::now();
+    let error = provider_from_config(&config).complete(&request()).unwrap_err();
+    assert_eq!(error, LlmError::Request("no answer within 1 s".to_string()));
+    assert!(started.elapsed() < Duration::from_secs(5));
+}
+
+#[test]
+fn test_unreachable_llm_player_falls_back() {
+    // Nothing listens on the discard port
+    let mut config = Config::init();
+    let state = llm_decision_state("http://127.0.0.1:9".to_string(), &mut config);
+
+    let mut player = strategy::create("llm:Test", &config).unwrap();
+    let mut fallback = strategy::create("balanced", &config).unwrap();
+    assert_eq!(player.choose_keep(&state), fallback.choose_keep(&state));
+    let (_, explanation) = player.explain().unwrap();
+    assert!(explanation.starts_with("Test could not be reached"));
+}
+
+#[test]
+fn test_vendor_sections_take_a_timeout() {
+    let mut config = Config::init();
+    config.openai.timeout = Some(15);
+    config.anthropic.timeout = Some(30);
+    assert_eq!(config.provider("openai").unwrap().timeout, Some(15));
+    assert_eq!(config.provider("anthropic").unwrap().timeout, Some(30));
+}
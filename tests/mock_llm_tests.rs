@@ -0,0 +1,162 @@
+use std::thread;
+
+use axum::body::{Body, to_bytes};
+use axum::http::{Request, StatusCode};
+use serde_json::{Value, json};
+use tower::util::ServiceExt;
+
+use dice6000::config::{Config, PlayerConfig, ProviderConfig, ProviderKind};
+use dice6000::dice::SeededDice;
+use dice6000::engine::{Action, GameState, play_turn};
+use dice6000::game::setup_players;
+use dice6000::mock_llm::{MockLlmOptions, create_router, rule_decision};
+use dice6000::strategy;
+
+const PROMPT: &str = "Current situation:\n\
+    - AI score: 1200\n\
+    - Turn score: 200\n\
+    - Dice rolled: 4\n\
+    - Current roll: [5, 1, 3, 4]\n\
+    - Keep options:\n  \
+    1) keep [5] for 50 points, 3 dice left to roll\n  \
+    2) keep [1] for 100 points, 3 dice left to roll\n";
+
+async fn post(options: MockLlmOptions, uri: &str, body: Value) -> (StatusCode, Value) {
+    let response = create_router(options)
+        .oneshot(
+            Request::builder()
+                .uri(uri)
+                .method("POST")
+                .header("content-type", "application/json")
+                .body(Body::from(body.to_string()))
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    let status = response.status();
+    let bytes = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    (status, serde_json::from_slice(&bytes).unwrap_or(Value::Null))
+}
+
+#[test]
+fn test_rule_decision() {
+    let decision = rule_decision(PROMPT, 350);
+    assert_eq!(decision["keep"], 2);
+    assert_eq!(decision["decision"], "R");
+
+    let decision = rule_decision(PROMPT, 300);
+    assert_eq!(decision["decision"], "T");
+}
+
+#[tokio::test]
+async fn test_vendor_answer_shapes() {
+    let user = json!([{ "role": "user", "content": PROMPT }]);
+
+    let (status, body) = post(MockLlmOptions::default(), "/v1/chat/completions", json!({ "messages": user })).await;
+    assert_eq!(status, StatusCode::OK);
+    let content = body["choices"][0]["message"]["content"].as_str().unwrap();
+    assert_eq!(serde_json::from_str::<Value>(content).unwrap()["keep"], 2);
+
+    let (_, body) = post(MockLlmOptions::default(), "/v1/messages", json!({ "messages": user })).await;
+    assert!(body["content"][0]["text"].as_str().unwrap().contains("\"decision\""));
+
+    let (_, body) = post(MockLlmOptions::default(), "/api/chat", json!({ "messages": user })).await;
+    assert!(body["message"]["content"].as_str().unwrap().contains("\"decision\""));
+
+    let (_, body) = post(MockLlmOptions::default(), "/api/generate", json!({ "prompt": "You just lost 300 points" })).await;
+    assert!(!body["response"].as_str().unwrap().contains("decision"));
+}
+
+#[tokio::test]
+async fn test_script_and_injected_faults() {
+    let script = |lines: &[&str]| MockLlmOptions {
+        script: lines.iter().map(|line| line.to_string()).collect(),
+        ..MockLlmOptions::default()
+    };
+    let body = json!({ "messages": [{ "role": "user", "content": PROMPT }] });
+
+    let (status, body_out) = post(script(&["```json {\"keep\": 2} ```"]), "/v1/chat/completions", body.clone()).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body_out["choices"][0]["message"]["content"], "```json {\"keep\": 2} ```");
+
+    let (status, _) = post(script(&["!429"]), "/v1/chat/completions", body.clone()).await;
+    assert_eq!(status, StatusCode::TOO_MANY_REQUESTS);
+
+    let errors = MockLlmOptions {
+        error_rate: 1.0,
+        ..MockLlmOptions::default()
+    };
+    let (status, _) = post(errors, "/v1/messages", body.clone()).await;
+    assert_eq!(status, StatusCode::SERVICE_UNAVAILABLE);
+
+    let malformed = MockLlmOptions {
+        malformed_rate: 1.0,
+        ..MockLlmOptions::default()
+    };
+    let (_, body_out) = post(malformed, "/api/chat", body).await;
+    assert!(!body_out["message"]["content"].as_str().unwrap().contains('{'));
+}
+
+/// Serve the mock on a free port from a thread of its own; its base URL.
+fn spawn_mock(options: MockLlmOptions) -> String {
+    let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+    let url = format!("http://{}", listener.local_addr().unwrap());
+    listener.set_nonblocking(true).unwrap();
+    thread::spawn(move || {
+        tokio::runtime::Runtime::new().unwrap().block_on(async {
+            let listener = tokio::net::TcpListener::from_std(listener).unwrap();
+            axum::serve(listener, create_router(options)).await.unwrap();
+        })
+    });
+    url
+}
+
+#[test]
+fn test_full_game_against_the_mock() {
+    let url = spawn_mock(MockLlmOptions {
+        malformed_rate: 0.2,
+        ..MockLlmOptions::default()
+    });
+    // SAFETY: the variable is only read by this test
+    unsafe { std::env::set_var("DICE6000_TEST_MOCK_KEY", "mock") };
+    let provider = |name: &str, kind, path: &str| ProviderConfig {
+        name: name.to_string(),
+        kind,
+        url: Some(format!("{}{}", url, path)),
+        model: "mock".to_string(),
+        api_key_env: Some("DICE6000_TEST_MOCK_KEY".to_string()),
+        timeout: Some(5),
+        retries: Some(0),
+    };
+    let mut config = Config::init();
+    config.game.target_score = 2000;
+    config.providers = vec![
+        provider("GPT", ProviderKind::Openai, "/v1/chat/completions"),
+        provider("Claude", ProviderKind::Anthropic, "/v1/messages"),
+        provider("Llama", ProviderKind::Ollama, "/api/chat"),
+    ];
+    config.game.players = ["GPT", "Claude", "Llama"]
+        .iter()
+        .map(|name| PlayerConfig {
+            name: name.to_string(),
+            strategy: Some(format!("llm:{}", name)),
+        })
+        .collect();
+
+    let players = setup_players(false, false, &config);
+    let mut controllers: Vec<_> = players
+        .iter()
+        .map(|player| strategy::create(player.ai_type.as_deref().unwrap(), &config).unwrap())
+        .collect();
+    let mut state = GameState::with_players("mock".to_string(), players, &config.game);
+    let mut dice = SeededDice::new(7);
+    for _ in 0..300 {
+        if state.game_over {
+            break;
+        }
+        let index = state.current_player_index;
+        play_turn(&mut state, &mut controllers[index], &mut dice, &mut |_, _| {}).unwrap();
+        state.apply(Action::NextPlayer, &mut dice).unwrap();
+    }
+    assert!(state.game_over);
+}
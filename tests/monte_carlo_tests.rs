@@ -0,0 +1,65 @@
+use std::time::{Duration, Instant};
+
+use dice6000::computer::controller_from_config;
+use dice6000::config::{Config, RolloutBudget};
+use dice6000::dice::{ScriptedDice, SeededDice};
+use dice6000::engine::{Action, GameState, TurnPhase, play_turn};
+use dice6000::monte_carlo::MonteCarloPlayer;
+use dice6000::strategy::{self, Strategy};
+
+/// A new game where the first player rolled `roll` and kept `keep`.
+fn after_keep(roll: Vec<u8>, keep: Vec<usize>) -> GameState {
+    let config = Config::init();
+    let mut state = GameState::new("monte_carlo".to_string(), false, false, &config);
+    let mut dice = ScriptedDice::new(vec![roll]);
+    state.apply(Action::Roll, &mut dice).unwrap();
+    state.apply(Action::Keep(keep), &mut dice).unwrap();
+    state
+}
+
+#[test]
+fn test_monte_carlo_roll_or_bank() {
+    let mut player = MonteCarloPlayer::new(RolloutBudget::Rollouts(1000));
+    // Three 1s, three dice left: 1000 points are worth banking
+    assert!(player.should_bank(&after_keep(vec![1, 1, 1, 2, 3, 4], vec![0, 1, 2])));
+    // A single 5 with five dice left: roll on
+    assert!(!player.should_bank(&after_keep(vec![5, 2, 3, 4, 6, 6], vec![0])));
+}
+
+#[test]
+fn test_monte_carlo_keeps_a_valid_option() {
+    let config = Config::init();
+    let mut state = GameState::new("monte_carlo".to_string(), false, false, &config);
+    let mut dice = ScriptedDice::new(vec![vec![1, 5, 2, 2, 3, 4]]);
+    state.apply(Action::Roll, &mut dice).unwrap();
+
+    let mut player = MonteCarloPlayer::new(RolloutBudget::Rollouts(300));
+    let keep = player.choose_keep(&state);
+    assert!(state.keep_options.iter().any(|option| option.indices == keep));
+    // Same budget, same decision
+    let mut again = MonteCarloPlayer::new(RolloutBudget::Rollouts(300));
+    assert_eq!(again.choose_keep(&state), keep);
+}
+
+#[test]
+fn test_monte_carlo_time_budget() {
+    let state = after_keep(vec![5, 2, 3, 4, 6, 6], vec![0]);
+    let mut player = MonteCarloPlayer::new(RolloutBudget::Millis(50));
+    let start = Instant::now();
+    player.should_bank(&state);
+    assert!(start.elapsed() < Duration::from_secs(1));
+}
+
+#[test]
+fn test_monte_carlo_is_a_computer_strategy() {
+    let mut config = Config::init();
+    config.game.computer_strategy = "monte_carlo".to_string();
+    config.game.rollout_budget = RolloutBudget::Rollouts(200);
+    assert!(strategy::create("monte_carlo", &config).is_some());
+
+    let mut state = GameState::new("monte_carlo".to_string(), false, false, &config);
+    state.current_player_index = 1; // the computer
+    let mut computer = controller_from_config(&config);
+    play_turn(&mut state, &mut computer, &mut SeededDice::new(7), &mut |_, _| {}).unwrap();
+    assert_eq!(state.phase(), TurnPhase::TurnOver);
+}
@@ -0,0 +1,78 @@
+use dice6000::computer::controller_from_config;
+use dice6000::config::{BankLimits, Config};
+use dice6000::dice::SeededDice;
+use dice6000::engine::{GameState, TurnPhase, play_turn};
+use dice6000::optimal::EvTable;
+use dice6000::rules::RuleSet;
+use dice6000::simulate::{SimulationOptions, simulate};
+use dice6000::strategy;
+
+#[test]
+fn test_expected_turn_score() {
+    let table = EvTable::get(&RuleSet::default(), BankLimits::default());
+    let ev = table.expected_turn_score();
+    assert!((500.0..600.0).contains(&ev), "expected turn score {}", ev);
+
+    // Stricter rules are worth less
+    let rules = RuleSet {
+        straight: None,
+        three_pairs: None,
+        ..RuleSet::default()
+    };
+    assert!(EvTable::get(&rules, BankLimits::default()).expected_turn_score() < ev);
+}
+
+#[test]
+fn test_optimal_roll_or_bank() {
+    let table = EvTable::get(&RuleSet::default(), BankLimits::default());
+    // Six dice always roll, few dice bank a decent score
+    assert!(table.should_roll(0, 6));
+    assert!(table.should_roll(3000, 6));
+    assert!(table.should_roll(200, 4));
+    assert!(!table.should_roll(1000, 2));
+    assert!(!table.should_roll(500, 1));
+}
+
+#[test]
+fn test_optimal_respects_bank_limits() {
+    let rules = RuleSet::default();
+    // Cannot bank under the opening score, whatever the risk
+    let opening = EvTable::get(&rules, BankLimits { min: 1000, max: None });
+    assert!(opening.should_roll(500, 1));
+    assert!(!opening.should_roll(1000, 1));
+
+    // Exact finish: bank on the target, everything above is lost
+    let exact = EvTable::get(&rules, BankLimits { min: 0, max: Some(300) });
+    assert!(!exact.should_roll(300, 6));
+    assert_eq!(exact.state_value(300, 6), 300.0);
+    assert!(exact.expected_turn_score() <= 300.0);
+}
+
+#[test]
+fn test_optimal_is_a_computer_strategy() {
+    let mut config = Config::init();
+    assert!(strategy::create("optimal", &config).is_some());
+    assert!(strategy::create("Optimal", &config).is_some());
+
+    config.game.computer_strategy = "optimal".to_string();
+    let mut state = GameState::new("optimal".to_string(), false, false, &config);
+    state.current_player_index = 1; // the computer
+    let mut computer = controller_from_config(&config);
+    play_turn(&mut state, &mut computer, &mut SeededDice::new(5), &mut |_, _| {}).unwrap();
+    assert_eq!(state.phase(), TurnPhase::TurnOver);
+}
+
+#[test]
+fn test_optimal_scores_more_per_turn() {
+    let config = Config::init();
+    let options = SimulationOptions {
+        games: 1000,
+        strategies: vec!["optimal".to_string(), "balanced".to_string()],
+        threads: 2,
+        seed: 11,
+    };
+    let report = simulate(&options, &config).unwrap();
+    let optimal = report.seats[0].points_per_turn();
+    let balanced = report.seats[1].points_per_turn();
+    assert!(optimal.mean > balanced.mean, "{:?} vs {:?}", optimal, balanced);
+}
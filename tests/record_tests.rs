@@ -0,0 +1,74 @@
+use dice6000::config::Config;
+use dice6000::dice::ScriptedDice;
+use dice6000::engine::{Action, Event, GameState};
+use dice6000::record::{GameRecord, RECORD_VERSION, RecordError};
+
+/// Human keeps 1-1-1 and banks, computer busts.
+fn recorded_game() -> (GameRecord, GameState) {
+    let config = Config::init();
+    let mut state = GameState::new("test".to_string(), false, false, &config);
+    let mut record = GameRecord::new(&state, Some(42));
+    let mut dice = ScriptedDice::new(vec![vec![1, 1, 1, 2, 3, 4], vec![2, 3, 4, 6, 2, 3]]);
+
+    for action in [
+        Action::Roll,
+        Action::Keep(vec![0, 1, 2]),
+        Action::Bank,
+        Action::NextPlayer,
+        Action::Roll,
+        Action::NextPlayer,
+    ] {
+        record.extend(state.apply(action, &mut dice).unwrap());
+    }
+    record.push(Event::Explanation {
+        player: 0,
+        decision: "T".to_string(),
+        explanation: "Safe".to_string(),
+    });
+    (record, state)
+}
+
+#[test]
+fn test_record_roundtrip_and_replay() {
+    let (record, state) = recorded_game();
+    let text = record.to_jsonl();
+    assert_eq!(text.lines().count(), record.events.len() + 1);
+    assert!(text.lines().next().unwrap().contains(&format!("\"version\":{}", RECORD_VERSION)));
+
+    let loaded = GameRecord::from_jsonl(&text).unwrap();
+    assert_eq!(loaded.header.seed, Some(42));
+    assert_eq!(loaded.events, record.events);
+
+    let mut seen = Vec::new();
+    let replayed = loaded.replay(&mut |_, event| seen.push(event.clone())).unwrap();
+    assert_eq!(seen, record.events);
+    assert_eq!(replayed.players[0].score, 1000);
+    assert_eq!(replayed.players[1].bust_streak, 1);
+    assert_eq!(replayed.turn_number, state.turn_number);
+}
+
+#[test]
+fn test_unsupported_version() {
+    let (record, _) = recorded_game();
+    let text = record
+        .to_jsonl()
+        .replacen(&format!("\"version\":{}", RECORD_VERSION), "\"version\":99", 1);
+    assert!(matches!(
+        GameRecord::from_jsonl(&text),
+        Err(RecordError::UnsupportedVersion(99))
+    ));
+}
+
+#[test]
+fn test_tampered_record_diverges() {
+    let (mut record, _) = recorded_game();
+    // Bank more than was kept
+    for event in record.events.iter_mut() {
+        if let Event::Banked { points, total, .. } = event {
+            *points = 5000;
+            *total = 5000;
+        }
+    }
+    let result = record.replay(&mut |_, _| {});
+    assert!(matches!(result, Err(RecordError::Diverged { index: 2, .. })));
+}
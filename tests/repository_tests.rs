@@ -0,0 +1,222 @@
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use axum::body::{Body, to_bytes};
+use axum::http::{Request, StatusCode};
+use serde_json::Value;
+use tower::util::ServiceExt;
+
+use dice6000::api::handlers::{GameSession, GameStore};
+use dice6000::api::repository::{
+    BotSpec, FileRepository, GameRepository, MemoryRepository, STORE_VERSION, SqliteRepository, StoredGame, now_secs,
+};
+use dice6000::api::routes::create_router_with_repository;
+use dice6000::config::Config;
+use dice6000::dice::SeededDice;
+use dice6000::engine::GameState;
+use dice6000::record::GameRecord;
+
+fn storage_path(name: &str) -> PathBuf {
+    std::env::temp_dir()
+        .join(format!("dice6000-repository-{}", std::process::id()))
+        .join(name)
+}
+
+fn stored_game(id: &str) -> StoredGame {
+    let state = GameState::new(id.to_string(), false, false, &Config::init());
+    StoredGame {
+        version: STORE_VERSION,
+        record: GameRecord::new(&state, Some(5)),
+        bots: vec![None, None],
+        state,
+        dice: SeededDice::new(5),
+        seat_tokens: vec![Some("token".to_string()), None],
+        invite_code: "ABC234".to_string(),
+        updated_at: now_secs(),
+    }
+}
+
+fn round_trip(repository: &dyn GameRepository) {
+    repository.save(&stored_game("game-1")).unwrap();
+    let mut second = stored_game("game-2");
+    repository.save(&second).unwrap();
+    second.state.turn_number = 4;
+    repository.save(&second).unwrap();
+
+    let mut games = repository.load_all().unwrap();
+    games.sort_by(|a, b| a.state.id.cmp(&b.state.id));
+    assert_eq!(games.len(), 2);
+    assert_eq!(games[1].state.turn_number, 4);
+    assert_eq!(games[0].seat_tokens, [Some("token".to_string()), None]);
+    assert_eq!(games[0].invite_code, "ABC234");
+
+    repository.delete("game-1").unwrap();
+    repository.delete("game-1").unwrap();
+    let games = repository.load_all().unwrap();
+    assert_eq!(games.len(), 1);
+    assert_eq!(games[0].state.id, "game-2");
+}
+
+#[test]
+fn test_memory_repository() {
+    round_trip(&MemoryRepository::default());
+}
+
+#[test]
+fn test_file_repository() {
+    let dir = storage_path("files");
+    let _ = std::fs::remove_dir_all(&dir);
+    round_trip(&FileRepository::new(&dir).unwrap());
+
+    // Unreadable files are skipped, and ids cannot leave the directory
+    std::fs::write(dir.join("broken.json"), "{").unwrap();
+    let repository = FileRepository::new(&dir).unwrap();
+    assert_eq!(repository.load_all().unwrap().len(), 1);
+    assert!(repository.save(&stored_game("../escape")).is_err());
+}
+
+#[test]
+fn test_sqlite_repository() {
+    round_trip(&SqliteRepository::in_memory().unwrap());
+
+    let path = storage_path("games.db");
+    let _ = std::fs::remove_file(&path);
+    SqliteRepository::open(&path).unwrap().save(&stored_game("kept")).unwrap();
+    let reopened = SqliteRepository::open(&path).unwrap();
+    assert_eq!(reopened.load_all().unwrap()[0].state.id, "kept");
+}
+
+async fn send(app: &axum::Router, method: &str, uri: &str, token: Option<&str>, body: &str) -> (StatusCode, Value) {
+    let mut request = Request::builder()
+        .uri(uri)
+        .method(method)
+        .header("Content-Type", "application/json");
+    if let Some(token) = token {
+        request = request.header("Authorization", format!("Bearer {}", token));
+    }
+    let response = app
+        .clone()
+        .oneshot(request.body(Body::from(body.to_string())).unwrap())
+        .await
+        .unwrap();
+    let status = response.status();
+    let bytes = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    (status, serde_json::from_slice(&bytes).unwrap_or(Value::Null))
+}
+
+#[tokio::test]
+async fn test_games_survive_a_restart() {
+    let repository: Arc<dyn GameRepository> = Arc::new(SqliteRepository::in_memory().unwrap());
+    let config = Arc::new(Config::init());
+    let app = create_router_with_repository(config.clone(), repository.clone());
+    let (_, created) = send(&app, "POST", "/api/game", None, r#"{"seed": 11}"#).await;
+    let game_id = created["game_state"]["id"].as_str().unwrap().to_string();
+    let token = created["seats"][0]["token"].as_str().unwrap().to_string();
+    let (_, rolled) = send(&app, "POST", &format!("/api/game/{}/roll", game_id), Some(&token), "{}").await;
+
+    // A new server on the same storage picks the game up where it was
+    let restarted = create_router_with_repository(config, repository);
+    let (status, state) = send(&restarted, "POST", &format!("/api/game/{}/status", game_id), None, "").await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(state["game_state"]["dice"], rolled["game_state"]["dice"]);
+    let (status, _) = send(&restarted, "POST", &format!("/api/game/{}/roll", game_id), Some(&token), "{}").await;
+    assert_eq!(status, StatusCode::OK);
+}
+
+#[tokio::test]
+async fn test_bots_keep_their_setup_across_a_restart() {
+    let repository: Arc<dyn GameRepository> = Arc::new(MemoryRepository::default());
+    let config = Config::init();
+    let app = create_router_with_repository(Arc::new(config.clone()), repository.clone());
+    let body = r#"{
+        "language": "fr",
+        "players": [
+            { "type": "human", "name": "Alice" },
+            { "type": "llm", "provider": "ollama", "model": "llama", "personality": "academic" },
+            { "type": "computer", "strategy": "optimal" }
+        ]
+    }"#;
+    let (_, created) = send(&app, "POST", "/api/game", None, body).await;
+    let game_id = created["game_state"]["id"].as_str().unwrap().to_string();
+
+    let llm = BotSpec {
+        strategy: "ollama".to_string(),
+        model: Some("llama".to_string()),
+        personality: Some("academic".to_string()),
+        language: Some("fr".to_string()),
+    };
+    let optimal = BotSpec {
+        strategy: "optimal".to_string(),
+        language: Some("fr".to_string()),
+        ..BotSpec::default()
+    };
+    let store = GameStore::new(repository, &config);
+    let games = store.lock().unwrap();
+    assert_eq!(games[&game_id].bot_specs, [None, Some(llm), Some(optimal)]);
+}
+
+#[tokio::test]
+async fn test_idle_games_expire() {
+    let repository: Arc<dyn GameRepository> = Arc::new(MemoryRepository::default());
+    let mut config = Config::init();
+    config.server.idle_ttl_secs = 0;
+    let app = create_router_with_repository(Arc::new(config), repository.clone());
+
+    let (_, first) = send(&app, "POST", "/api/game", None, "{}").await;
+    let first_id = first["game_state"]["id"].as_str().unwrap().to_string();
+    // Creating a game drops the expired ones
+    let (_, second) = send(&app, "POST", "/api/game", None, "{}").await;
+
+    let (status, _) = send(&app, "POST", &format!("/api/game/{}/status", first_id), None, "").await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+    let stored = repository.load_all().unwrap();
+    assert_eq!(stored.len(), 1);
+    assert_eq!(stored[0].state.id, second["game_state"]["id"].as_str().unwrap());
+}
+
+#[tokio::test]
+async fn test_games_expired_while_down_are_dropped() {
+    let repository: Arc<dyn GameRepository> = Arc::new(MemoryRepository::default());
+    let mut stale = stored_game("stale");
+    stale.updated_at = 0;
+    repository.save(&stale).unwrap();
+    repository.save(&stored_game("fresh")).unwrap();
+
+    let app = create_router_with_repository(Arc::new(Config::init()), repository.clone());
+    let (status, _) = send(&app, "GET", "/api/game/stale", None, "").await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+    let stored = repository.load_all().unwrap();
+    assert_eq!(stored.len(), 1);
+    assert_eq!(stored[0].state.id, "fresh");
+}
+
+#[test]
+fn test_only_the_latest_change_is_written() {
+    let repository: Arc<dyn GameRepository> = Arc::new(MemoryRepository::default());
+    let config = Config::init();
+    let store = GameStore::new(repository.clone(), &config);
+    let state = GameState::new("game".to_string(), false, false, &config);
+    let mut session = GameSession::new(state, SeededDice::new(5), &config);
+
+    let earlier = store.changed(&mut session);
+    session.state.turn_number = 2;
+    let later = store.changed(&mut session);
+    // Written in the other order, as two requests may
+    store.write(later).unwrap();
+    store.write(earlier).unwrap();
+    assert_eq!(repository.load_all().unwrap()[0].state.turn_number, 2);
+
+    // A change made before the game expired does not bring it back
+    let stale = store.changed(&mut session);
+    store.lock().unwrap().insert("game".to_string(), session);
+    store.expire(0, 0);
+    store.write(stale).unwrap();
+    assert!(repository.load_all().unwrap().is_empty());
+}
+
+#[test]
+fn test_router_is_built_outside_a_runtime() {
+    // The server runs the expiry sweep, not the router
+    let repository: Arc<dyn GameRepository> = Arc::new(MemoryRepository::default());
+    let _app = create_router_with_repository(Arc::new(Config::init()), repository);
+}
@@ -0,0 +1,108 @@
+use dice6000::config::Config;
+use dice6000::rules::{KindScore, RuleSet};
+use dice6000::score::{calculate_score, keep_options, validate_keep};
+
+fn house_rules() -> RuleSet {
+    RuleSet {
+        straight_ends_turn: true,
+        three_pairs: Some(1000),
+        four_of_a_kind: Some(KindScore::TripleTimes(2)),
+        five_of_a_kind: Some(KindScore::TripleTimes(4)),
+        six_of_a_kind: Some(KindScore::TripleTimes(8)),
+        ..RuleSet::default()
+    }
+}
+
+#[test]
+fn test_default_rules_match_classic_table() {
+    let rules = RuleSet::default();
+    assert_eq!(rules.score(&[1, 1, 1, 1, 1, 2]).score, 1200);
+    assert_eq!(rules.score(&[5, 5, 5, 5, 2, 3]).score, 550);
+    assert_eq!(rules.score(&[1, 1, 1, 1, 1, 1]).score, 1000);
+}
+
+#[test]
+fn test_doubled_triples() {
+    let rules = house_rules();
+    let (score, remaining, _, _) = calculate_score(&[4, 4, 4, 4, 2, 3], &rules);
+    assert_eq!(score, 800);
+    assert_eq!(remaining, 2);
+    assert_eq!(rules.score(&[1, 1, 1, 1, 1, 3]).score, 4000);
+    assert_eq!(rules.score(&[2, 2, 2, 2, 2, 2]).score, 1600);
+}
+
+#[test]
+fn test_house_three_pairs() {
+    let (score, remaining, _, _) = calculate_score(&[2, 2, 3, 3, 6, 6], &house_rules());
+    assert_eq!(score, 1000);
+    assert_eq!(remaining, 0);
+}
+
+#[test]
+fn test_disabled_straight_scores_singles() {
+    let rules = RuleSet {
+        straight: None,
+        ..RuleSet::default()
+    };
+    let (score, remaining, _, _) = calculate_score(&[1, 2, 3, 4, 5, 6], &rules);
+    assert_eq!(score, 150);
+    assert_eq!(remaining, 4);
+}
+
+#[test]
+fn test_straight_can_end_the_turn() {
+    let rules = house_rules();
+    let options = keep_options(&[6, 5, 4, 3, 2, 1], &rules);
+    assert!(options[0].ends_turn);
+    assert!(options.iter().skip(1).all(|o| !o.ends_turn));
+
+    let kept = validate_keep(&[6, 5, 4, 3, 2, 1], &[0, 1, 2, 3, 4, 5], &rules).unwrap();
+    assert!(kept.ends_turn);
+    assert_eq!(kept.score, 2000);
+}
+
+#[test]
+fn test_rules_load_from_game_config() {
+    let yaml = r#"
+server: { host: "0.0.0.0", port: 3000 }
+game:
+  human_player_name: "Human"
+  computer_player_name: "Computer"
+  computer_strategy: "balanced"
+  ai_output_language: "en"
+  ai_personality: "default"
+  rules:
+    three_pairs: 1000
+    four_of_a_kind: !triple_times 2
+openai: { url: "http://localhost", model: "gpt-4" }
+anthropic: { url: "http://localhost", model: "claude" }
+ollama: { enabled: false, url: "http://localhost", model: "llama3", timeout: 60 }
+"#;
+    let config: Config = serde_yaml::from_str(yaml).unwrap();
+    assert_eq!(config.game.rules.three_pairs, Some(1000));
+    assert_eq!(config.game.rules.four_of_a_kind, Some(KindScore::TripleTimes(2)));
+    assert_eq!(config.game.rules.straight, Some(2000));
+}
+
+#[test]
+fn test_describe_lists_enabled_combinations() {
+    let lines = house_rules().describe();
+    assert!(lines.iter().any(|l| l.contains("ends the turn")));
+    assert!(lines.iter().any(|l| l == "Four of a kind: double the three-of-a-kind value"));
+    assert!(lines.iter().any(|l| l == "Three pairs: 1000 points, all dice used"));
+}
+
+#[test]
+fn test_six_of_a_kind_disabled_scores_two_triples() {
+    let rules = RuleSet {
+        six_of_a_kind: None,
+        ..RuleSet::default()
+    };
+    let six_twos = rules.score(&[2, 2, 2, 2, 2, 2]);
+    assert_eq!(six_twos.score, 400);
+    assert_eq!(six_twos.remaining_counts, [0; 7]);
+    assert_eq!(rules.score(&[1, 1, 1, 1, 1, 1]).score, 2000);
+
+    let options = keep_options(&[2, 2, 2, 2, 2, 2], &rules);
+    assert!(options.iter().any(|option| option.dice.len() == 6 && option.score == 400));
+}
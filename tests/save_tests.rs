@@ -0,0 +1,58 @@
+use dice6000::config::Config;
+use dice6000::dice::{DiceSource, SeededDice};
+use dice6000::engine::{Action, GameState};
+use dice6000::save::SavedGame;
+use std::fs;
+
+fn save_path(name: &str) -> std::path::PathBuf {
+    std::env::temp_dir()
+        .join(format!("dice6000-{}", std::process::id()))
+        .join(format!("{}.json", name))
+}
+
+#[test]
+fn test_save_and_resume() {
+    let config = Config::init();
+    let mut state = GameState::new("saved".to_string(), false, false, &config);
+    let mut dice = SeededDice::new(99);
+
+    // Play the human's turn until it is over
+    while !state.turn_terminated {
+        if state.has_pending_roll() {
+            let keep = state.default_keep();
+            state.apply(Action::Keep(keep), &mut dice).unwrap();
+        } else if state.must_roll() || state.current_player().turn_score == 0 {
+            state.apply(Action::Roll, &mut dice).unwrap();
+        } else {
+            state.apply(Action::Bank, &mut dice).unwrap();
+        }
+    }
+    state.apply(Action::NextPlayer, &mut dice).unwrap();
+
+    let path = save_path("resume");
+    SavedGame::new(&state, &dice).save(&path).unwrap();
+    let mut resumed = SavedGame::load(&path).unwrap();
+
+    assert_eq!(
+        serde_json::to_value(&resumed.state).unwrap(),
+        serde_json::to_value(&state).unwrap()
+    );
+    assert_eq!(resumed.state.current_player_index, 1);
+    assert_eq!(resumed.state.turn_number, 2);
+    assert_eq!(resumed.dice.roll(6), dice.roll(6));
+    fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn test_unsupported_save_version() {
+    let config = Config::init();
+    let state = GameState::new("old".to_string(), false, false, &config);
+    let path = save_path("version");
+    let mut saved = SavedGame::new(&state, &SeededDice::new(1));
+    saved.version = 99;
+    saved.save(&path).unwrap();
+
+    let error = SavedGame::load(&path).unwrap_err();
+    assert!(error.to_string().contains("99"));
+    fs::remove_file(path).unwrap();
+}
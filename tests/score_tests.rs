@@ -1,57 +1,109 @@
-use dice6000::score::calculate_score;
+use dice6000::rules::RuleSet;
+use dice6000::score::{KeepError, best_keep, calculate_score, keep_options, validate_keep};
 
 #[test]
 fn test_score_single_1() {
-    let (score, remaining) = calculate_score(&[1, 2, 3, 4, 6, 6]);
+    let (score, remaining, _, _) = calculate_score(&[1, 2, 3, 4, 6, 6], &RuleSet::default());
     assert_eq!(score, 100);
     assert_eq!(remaining, 5);
 }
 
 #[test]
 fn test_score_three_ones() {
-    let (score, remaining) = calculate_score(&[1, 1, 1, 2, 3, 4]);
+    let (score, remaining, _, _) = calculate_score(&[1, 1, 1, 2, 3, 4], &RuleSet::default());
     assert_eq!(score, 1000);
     assert_eq!(remaining, 3);
 }
 
 #[test]
 fn test_score_straight() {
-    let (score, remaining) = calculate_score(&[1, 2, 3, 4, 5, 6]);
+    let (score, remaining, _, _) = calculate_score(&[1, 2, 3, 4, 5, 6], &RuleSet::default());
     assert_eq!(score, 2000);
     assert_eq!(remaining, 0);
 }
 
 #[test]
 fn test_score_three_pairs() {
-    let (score, remaining) = calculate_score(&[2, 2, 3, 3, 5, 5]);
+    let (score, remaining, _, _) = calculate_score(&[2, 2, 3, 3, 5, 5], &RuleSet::default());
     assert_eq!(score, 1500);
     assert_eq!(remaining, 0);
 }
 
 #[test]
 fn test_score_six_of_a_kind() {
-    let (score, remaining) = calculate_score(&[4, 4, 4, 4, 4, 4]);
+    let (score, remaining, _, _) = calculate_score(&[4, 4, 4, 4, 4, 4], &RuleSet::default());
     assert_eq!(score, 4000);
     assert_eq!(remaining, 0);
 }
 
 #[test]
 fn test_score_three_of_a_kind_and_singles() {
-    let (score, remaining) = calculate_score(&[3, 3, 3, 1, 5, 2]);
+    let (score, remaining, _, _) = calculate_score(&[3, 3, 3, 1, 5, 2], &RuleSet::default());
     assert_eq!(score, 450);
     assert_eq!(remaining, 1);
 }
 
 #[test]
 fn test_score_no_score() {
-    let (score, remaining) = calculate_score(&[2, 3, 4, 6, 6, 2]);
+    let (score, remaining, _, _) = calculate_score(&[2, 3, 4, 6, 6, 2], &RuleSet::default());
     assert_eq!(score, 0);
     assert_eq!(remaining, 6);
 }
 
 #[test]
 fn test_score_multiple_ones_and_fives() {
-    let (score, remaining) = calculate_score(&[1, 1, 5, 5, 2, 3]);
+    let (score, remaining, _, _) = calculate_score(&[1, 1, 5, 5, 2, 3], &RuleSet::default());
     assert_eq!(score, 300); // 2 ones * 100 + 2 fives * 50
     assert_eq!(remaining, 2);
 }
+
+#[test]
+fn test_keep_options_single_one_and_five() {
+    let options = keep_options(&[1, 5, 2, 3, 4, 4], &RuleSet::default());
+    let kept: Vec<Vec<u8>> = options.iter().map(|o| o.dice.clone()).collect();
+    assert_eq!(kept, vec![vec![1, 5], vec![1], vec![5]]);
+    assert_eq!(options[0].score, 150);
+    assert_eq!(options[1].indices, vec![0]);
+}
+
+#[test]
+fn test_keep_options_bust() {
+    assert!(keep_options(&[2, 3, 4, 6, 6, 2], &RuleSet::default()).is_empty());
+    assert!(best_keep(&[2, 3], &RuleSet::default()).is_none());
+}
+
+#[test]
+fn test_keep_options_straight_is_all_or_singles() {
+    let options = keep_options(&[1, 2, 3, 4, 5, 6], &RuleSet::default());
+    assert_eq!(options[0].score, 2000);
+    assert_eq!(options[0].dice.len(), 6);
+    assert!(options.iter().all(|o| o.dice.len() == 6 || o.dice.len() <= 2));
+}
+
+#[test]
+fn test_validate_keep_partial() {
+    let result = validate_keep(&[5, 1, 3, 3, 2, 6], &[1], &RuleSet::default()).unwrap();
+    assert_eq!(result.score, 100);
+    assert_eq!(result.kept, vec![1]);
+    assert_eq!(result.reroll, vec![5, 3, 3, 2, 6]);
+    assert_eq!(result.reroll_indices, vec![0, 2, 3, 4, 5]);
+    assert_eq!(result.dice_left(), 5);
+}
+
+#[test]
+fn test_validate_keep_rejects_non_scoring() {
+    assert_eq!(
+        validate_keep(&[1, 2, 3], &[0, 1], &RuleSet::default()),
+        Err(KeepError::NotScoring(vec![1, 2]))
+    );
+    assert_eq!(validate_keep(&[1, 2, 3], &[], &RuleSet::default()), Err(KeepError::Empty));
+    assert_eq!(validate_keep(&[1, 2, 3], &[3], &RuleSet::default()), Err(KeepError::OutOfRange(3)));
+    assert_eq!(validate_keep(&[1, 1, 3], &[0, 0], &RuleSet::default()), Err(KeepError::Duplicate(0)));
+}
+
+#[test]
+fn test_validate_keep_triple_plus_single() {
+    let result = validate_keep(&[3, 3, 3, 1, 5, 2], &[0, 1, 2, 3], &RuleSet::default()).unwrap();
+    assert_eq!(result.score, 400);
+    assert_eq!(result.reroll, vec![5, 2]);
+}
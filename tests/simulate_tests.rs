@@ -0,0 +1,86 @@
+use dice6000::config::Config;
+use dice6000::engine::GameState;
+use dice6000::simulate::{SimulationOptions, simulate};
+use dice6000::strategy::{self, Strategy};
+
+/// Keeps every scoring die and banks as soon as it may.
+struct BankFirst;
+
+impl Strategy for BankFirst {
+    fn choose_keep(&mut self, state: &GameState) -> Vec<usize> {
+        state.keep_options[0].indices.clone()
+    }
+
+    fn should_bank(&mut self, _state: &GameState) -> bool {
+        true
+    }
+}
+
+fn options(games: u64, threads: usize, strategies: &[&str]) -> SimulationOptions {
+    SimulationOptions {
+        games,
+        strategies: strategies.iter().map(|s| s.to_string()).collect(),
+        threads,
+        seed: 42,
+    }
+}
+
+#[test]
+fn test_simulation_totals() {
+    let config = Config::init();
+    let report = simulate(&options(200, 4, &["conservative", "aggressive"]), &config).unwrap();
+
+    assert_eq!(report.games, 200);
+    let wins: u64 = report.seats.iter().map(|s| s.wins).sum();
+    assert_eq!(wins + report.draws, 200);
+    for seat in &report.seats {
+        assert!(seat.turns > 0);
+        assert!(seat.busts <= seat.turns);
+        let win = seat.win_rate(report.games);
+        assert!((0.0..=1.0).contains(&win.mean));
+        assert!(win.margin > 0.0);
+        assert!(seat.points_per_turn().mean > 0.0);
+        // 6000 points take more than a couple of turns
+        assert!(seat.turns_to_win().mean >= 3.0);
+    }
+}
+
+#[test]
+fn test_mirror_match_credits_the_winning_seat() {
+    let config = Config::init();
+    let report = simulate(&options(200, 4, &["balanced", "balanced"]), &config).unwrap();
+
+    let wins: Vec<u64> = report.seats.iter().map(|s| s.wins).collect();
+    assert_eq!(wins.iter().sum::<u64>() + report.draws, 200);
+    // Not just the seat rotation, which splits the games evenly
+    assert_ne!(wins[0], wins[1]);
+}
+
+#[test]
+fn test_simulation_is_reproducible_across_thread_counts() {
+    let config = Config::init();
+    let one = simulate(&options(50, 1, &["balanced", "aggressive"]), &config).unwrap();
+    let many = simulate(&options(50, 3, &["balanced", "aggressive"]), &config).unwrap();
+    for (a, b) in one.seats.iter().zip(&many.seats) {
+        assert_eq!(a.wins, b.wins);
+        assert_eq!(a.turns, b.turns);
+        assert_eq!(a.busts, b.busts);
+    }
+}
+
+#[test]
+fn test_simulation_rejects_bad_strategies() {
+    let config = Config::init();
+    assert!(simulate(&options(10, 1, &["balanced"]), &config).is_err());
+    assert!(simulate(&options(10, 1, &["balanced", "reckless"]), &config).is_err());
+}
+
+#[test]
+fn test_registered_strategies_are_simulated() {
+    strategy::register("sim_bank_first", |_| Box::new(BankFirst));
+    let config = Config::init();
+    let report = simulate(&options(100, 2, &["sim_bank_first", "balanced"]), &config).unwrap();
+    assert_eq!(report.seats[0].strategy, "sim_bank_first");
+    assert!(report.seats[0].turns > 0);
+    assert_eq!(report.seats[0].wins + report.seats[1].wins + report.draws, 100);
+}
@@ -0,0 +1,94 @@
+use dice6000::config::{Config, PlayerConfig};
+use dice6000::dice::{ScriptedDice, SeededDice};
+use dice6000::engine::{Event, GameState, TurnPhase, play_turn};
+use dice6000::game::setup_players;
+use dice6000::strategy::{self, Strategy};
+
+/// Keeps every scoring die and banks as soon as it may.
+struct BankFirst;
+
+impl Strategy for BankFirst {
+    fn choose_keep(&mut self, state: &GameState) -> Vec<usize> {
+        state.keep_options[0].indices.clone()
+    }
+
+    fn should_bank(&mut self, _state: &GameState) -> bool {
+        true
+    }
+}
+
+/// Keeps a die that is not on the table.
+struct OffTheTable;
+
+impl Strategy for OffTheTable {
+    fn choose_keep(&mut self, state: &GameState) -> Vec<usize> {
+        vec![state.dice.len()]
+    }
+
+    fn should_bank(&mut self, _state: &GameState) -> bool {
+        true
+    }
+}
+
+#[test]
+fn test_builtin_strategies_are_registered() {
+    for name in ["computer", "balanced", "adaptive", "optimal", "win_probability", "openai", "ollama"] {
+        assert!(strategy::is_registered(name), "{}", name);
+    }
+    assert!(strategy::is_registered("Optimal"));
+    assert!(!strategy::is_registered("reckless"));
+    assert!(strategy::create("reckless", &Config::init()).is_none());
+}
+
+#[test]
+fn test_registered_strategy_plays_a_turn() {
+    strategy::register("bank_first", |_| Box::new(BankFirst));
+    assert!(strategy::names().contains(&"bank_first".to_string()));
+
+    let config = Config::init();
+    let mut bot = strategy::create("Bank_First", &config).unwrap();
+    let mut state = GameState::new("bot".to_string(), false, false, &config);
+    let mut dice = SeededDice::new(3);
+    let mut rolls = 0;
+    play_turn(&mut state, &mut bot, &mut dice, &mut |_, event| {
+        if let Event::Rolled { .. } = event {
+            rolls += 1;
+        }
+    })
+    .unwrap();
+
+    // Banked, or busted, after the first roll
+    assert_eq!(state.phase(), TurnPhase::TurnOver);
+    assert_eq!(rolls, 1);
+}
+
+#[test]
+fn test_players_are_configured_by_strategy() {
+    let mut config = Config::init();
+    config.game.players = vec![
+        PlayerConfig { name: "Alice".to_string(), strategy: None },
+        PlayerConfig { name: "Robot".to_string(), strategy: Some("optimal".to_string()) },
+        PlayerConfig { name: "Risky".to_string(), strategy: Some("aggressive".to_string()) },
+    ];
+    let players = setup_players(true, true, &config);
+    assert_eq!(players.len(), 3);
+    assert!(players[0].is_human);
+    assert_eq!(players[0].ai_type, None);
+    assert!(!players[1].is_human);
+    assert_eq!(players[1].ai_type.as_deref(), Some("optimal"));
+    assert_eq!(players[2].name, "Risky");
+}
+
+#[test]
+fn test_illegal_keep_plays_the_first_option() {
+    let config = Config::init();
+    let mut bot: Box<dyn Strategy> = Box::new(OffTheTable);
+    let mut state = GameState::new("bot".to_string(), false, false, &config);
+    let mut dice = ScriptedDice::new(vec![vec![1, 5, 2, 3, 4, 4]]);
+    let mut events = Vec::new();
+    play_turn(&mut state, &mut bot, &mut dice, &mut |_, event| events.push(event.clone())).unwrap();
+
+    assert!(events.iter().any(|event| matches!(event, Event::KeepRejected { player: 0, .. })));
+    assert!(events.iter().any(|event| matches!(event, Event::Kept { .. })));
+    assert_eq!(state.phase(), TurnPhase::TurnOver);
+}
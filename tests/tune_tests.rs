@@ -0,0 +1,77 @@
+use std::collections::BTreeMap;
+
+use dice6000::computer::{AIPersonality, PersonalityParams, controller_from_config};
+use dice6000::config::Config;
+use dice6000::dice::SeededDice;
+use dice6000::engine::{GameState, TurnPhase, play_turn};
+use dice6000::strategy;
+use dice6000::tune::{TuneOptions, personalities_yaml, tune};
+
+fn options(seed: u64) -> TuneOptions {
+    TuneOptions {
+        generations: 4,
+        games: 40,
+        threads: 2,
+        seed,
+        start: AIPersonality::Balanced.params(),
+    }
+}
+
+#[test]
+fn test_tune_reports_every_generation() {
+    let config = Config::init();
+    let mut generations = Vec::new();
+    let best = tune(&options(5), &config, &mut |g| generations.push(g.clone())).unwrap();
+
+    assert_eq!(generations.len(), 4);
+    for (i, generation) in generations.iter().enumerate() {
+        assert_eq!(generation.index, i as u32 + 1);
+        assert!((0.0..=1.0).contains(&generation.win_rate.mean));
+        // Only a win rate clearly above one half replaces the incumbent
+        let clear = generation.win_rate.mean - generation.win_rate.margin > 0.5;
+        assert_eq!(generation.accepted, clear);
+    }
+    // The best set is the start or the last kept candidate
+    let expected = generations
+        .iter()
+        .rev()
+        .find(|g| g.accepted)
+        .map_or(AIPersonality::Balanced.params(), |g| g.candidate);
+    assert_eq!(best, expected);
+
+    // Same seed, same search
+    let again = tune(&options(5), &config, &mut |_| {}).unwrap();
+    assert_eq!(again, best);
+}
+
+#[test]
+fn test_tuned_parameters_load_by_name() {
+    let params = PersonalityParams {
+        min_score: 350,
+        die_value: 70,
+        ..AIPersonality::Aggressive.params()
+    };
+    let yaml = personalities_yaml("tuned", &params).unwrap();
+    let section: BTreeMap<String, BTreeMap<String, PersonalityParams>> = serde_yaml::from_str(&yaml).unwrap();
+
+    let mut config = Config::init();
+    config.game.personalities = section["personalities"].clone();
+    assert_eq!(config.game.personalities["tuned"], params);
+    assert!(strategy::create("tuned", &config).is_some());
+    assert!(strategy::create("untuned", &config).is_none());
+
+    config.game.computer_strategy = "tuned".to_string();
+    let mut state = GameState::new("tuned".to_string(), false, false, &config);
+    state.current_player_index = 1; // the computer
+    let mut computer = controller_from_config(&config);
+    play_turn(&mut state, &mut computer, &mut SeededDice::new(4), &mut |_, _| {}).unwrap();
+    assert_eq!(state.phase(), TurnPhase::TurnOver);
+}
+
+#[test]
+fn test_partial_parameter_sets_keep_balanced_values() {
+    let params: PersonalityParams = serde_yaml::from_str("min_score: 450").unwrap();
+    assert_eq!(params.min_score, 450);
+    assert_eq!(params.max_score, AIPersonality::Balanced.params().max_score);
+    assert_eq!(AIPersonality::Balanced.params(), PersonalityParams::default());
+}
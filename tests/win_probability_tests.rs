@@ -0,0 +1,71 @@
+use dice6000::computer::controller_from_config;
+use dice6000::config::{Config, EndMode};
+use dice6000::dice::SeededDice;
+use dice6000::engine::{GameState, TurnPhase, play_turn};
+use dice6000::rules::RuleSet;
+use dice6000::strategy;
+use dice6000::win_probability::WinTable;
+
+const TARGET: u32 = 1000;
+
+fn table() -> std::sync::Arc<WinTable> {
+    WinTable::get_for(&RuleSet::default(), TARGET, EndMode::Reach, 0)
+}
+
+#[test]
+fn test_win_probabilities() {
+    let table = table();
+    // Starting first is an advantage
+    let first = table.start_value(0, 0);
+    assert!(first > 0.5 && first < 0.7, "W(0, 0) = {}", first);
+
+    for me in (0..TARGET).step_by(50) {
+        let mut previous = 1.0;
+        for opponent in (0..TARGET).step_by(50) {
+            let w = table.start_value(me, opponent);
+            assert!((0.0..=1.0).contains(&w));
+            // The opponent being closer never helps
+            assert!(w <= previous + 1e-12, "W({}, {})", me, opponent);
+            previous = w;
+        }
+    }
+    assert_eq!(table.start_value(TARGET, 0), 1.0);
+    assert_eq!(table.start_value(0, TARGET), 0.0);
+}
+
+#[test]
+fn test_risk_depends_on_the_opponent() {
+    let table = table();
+    let bust = |me, opponent| 1.0 - table.start_value(opponent, me);
+
+    // 400 points with 2 dice left: bank when ahead...
+    let ahead = table.turn(300, 0, bust(300, 0));
+    assert!(!ahead.should_roll(400, 2));
+    // ...but keep rolling when the opponent is about to win
+    let behind = table.turn(300, 900, bust(300, 900));
+    assert!(behind.should_roll(400, 2));
+
+    // Never roll past a win
+    assert!(!ahead.should_roll(700, 6));
+}
+
+#[test]
+fn test_opening_score_forces_rolls() {
+    let table = WinTable::get_for(&RuleSet::default(), TARGET, EndMode::Reach, 500);
+    let turn = table.turn(0, 0, 1.0 - table.start_value(0, 0));
+    assert!(turn.should_roll(450, 1));
+}
+
+#[test]
+fn test_win_probability_is_a_computer_strategy() {
+    let mut config = Config::init();
+    assert!(strategy::create("win_probability", &config).is_some());
+
+    config.game.computer_strategy = "win_probability".to_string();
+    config.game.target_score = TARGET;
+    let mut state = GameState::new("win".to_string(), false, false, &config);
+    state.current_player_index = 1; // the computer
+    let mut computer = controller_from_config(&config);
+    play_turn(&mut state, &mut computer, &mut SeededDice::new(9), &mut |_, _| {}).unwrap();
+    assert_eq!(state.phase(), TurnPhase::TurnOver);
+}